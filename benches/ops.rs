@@ -0,0 +1,74 @@
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mushin as mu;
+
+/// Throughput of the operations on square matrices large enough for the device to dominate
+fn operations(c: &mut Criterion) {
+    let x = mu::randn::<1, 1, 256, 256>();
+    let y = mu::randn::<1, 1, 256, 256>();
+
+    let mut group = c.benchmark_group("operations");
+    group.bench_function("add", |b| {
+        b.iter(|| {
+            black_box(mu::add(&x, &y)).eval();
+            mu::sync();
+        });
+    });
+    group.bench_function("mul", |b| {
+        b.iter(|| {
+            black_box(mu::mul(&x, &y)).eval();
+            mu::sync();
+        });
+    });
+    group.bench_function("sin", |b| {
+        b.iter(|| {
+            black_box(mu::sin(&x)).eval();
+            mu::sync();
+        });
+    });
+    group.bench_function("mm", |b| {
+        b.iter(|| {
+            black_box(mu::mm(&x, &y)).eval();
+            mu::sync();
+        });
+    });
+    group.bench_function("mm backward", |b| {
+        let z = mu::mm(&x, &y);
+        b.iter(|| {
+            z.backward();
+            x.eval();
+            mu::sync();
+        });
+    });
+    group.finish();
+}
+
+/// Overhead of recording operations in the computation graph and traversing it backwards,
+/// measured on scalars so that the arrayfire computations are negligible
+fn graph(c: &mut Criterion) {
+    let x = mu::fill::<1, 1, 1, 1>(1.0);
+
+    let mut group = c.benchmark_group("graph");
+    group.bench_function("build 100 nodes", |b| {
+        b.iter(|| {
+            let mut z = mu::add(&x, &x);
+            for _ in 0..99 {
+                z = mu::add(&z, &x);
+            }
+            black_box(z)
+        });
+    });
+    group.bench_function("backward 100 nodes", |b| {
+        let mut z = mu::add(&x, &x);
+        for _ in 0..99 {
+            z = mu::add(&z, &x);
+        }
+        b.iter(|| z.backward());
+    });
+    group.finish();
+}
+
+criterion_group!(benches, operations, graph);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use mu::nn::{
+    activations::relu,
+    layers::{Conv2D, Linear},
+    losses::{mse, Mean},
+    ops::flatten,
+    optimizers::{Optimizer, SGD},
+};
+use mushin as mu;
+
+/// A full training step of a small convolutional network: forward pass, backward pass and
+/// parameters update, on a batch of 16 single channel 28x28 images
+fn training_step(c: &mut Criterion) {
+    let x = mu::randu::<16, 1, 28, 28>().freeze();
+    let y = mu::eye::<16, 1, 1, 10>(1.0).freeze();
+
+    let conv = Conv2D::<1, 4, 3, 3>::randn();
+    let linear = Linear::<2704, 10>::randn();
+    let optim = SGD::new(&[conv.parameters(), linear.parameters()], 0.01);
+
+    c.bench_function("linear + conv2d training step", |b| {
+        b.iter(|| {
+            let z = linear.forward(&flatten(&relu(&conv.forward(&x))));
+            let loss = mse(&z, &y, Mean);
+
+            loss.backward();
+            optim.step();
+            optim.zero_grad();
+            mu::sync();
+        });
+    });
+}
+
+criterion_group!(benches, training_step);
+criterion_main!(benches);
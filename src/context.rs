@@ -0,0 +1,217 @@
+//! This module includes the `Context` type, an explicit owner of the computation graph
+//! for code that wants to control how long it lives, i.e. a training loop.
+//!
+//! A `Context` holds two kinds of nodes:
+//! - Persistent named variables, like the weights of a model, which are declared once and
+//!   kept across steps.
+//! - The tape of the current step, recorded from the tensors passed to `backward` or `track`.
+//!
+//! It also caches named constants, like positional encodings, which are computed once and
+//! taken by every step.
+//!
+//! Calling `reset` releases the operations of every recorded node, so intermediate results
+//! are freed even if some tensor of the step outlives it, and sets the gradients of the
+//! persistent variables to zero. The variables themselves are never reallocated.
+
+use crate::graph::{node::Node, shared::Shared, tape::Tape};
+use crate::tensor::{
+    constant::Constant, traits::Tensed, variable::Variable, BackwardOptions, Float, Tensor,
+};
+use arrayfire::{Array, Dim4};
+use std::collections::BTreeMap;
+
+/// Checks that the values declared with the given name have the requested `[B, C, H, W]` shape
+fn assert_shape(kind: &str, name: &str, dims: Dim4, shape: [u64; 4]) {
+    assert!(
+        [dims[3], dims[2], dims[0], dims[1]] == shape,
+        "{kind} {name} was declared with shape [{}, {}, {}, {}]",
+        dims[3],
+        dims[2],
+        dims[0],
+        dims[1]
+    );
+}
+
+/// Owner of persistent named variables and of the computation graph of the current step
+#[derive(Default)]
+pub struct Context {
+    variables: BTreeMap<String, Shared<Node>>,
+    constants: BTreeMap<String, Array<Float>>,
+    tapes: Vec<Tape>,
+}
+
+impl Context {
+    /// Creates an empty context, with no variables and no recorded graph
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the persistent variable with the given name, declaring it with the tensor
+    /// returned by `init` the first time the name is used
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name was already declared with a different shape
+    #[inline]
+    pub fn variable<const B: u64, const C: u64, const H: u64, const W: u64, F>(
+        &mut self,
+        name: &str,
+        init: F,
+    ) -> Tensor<B, C, H, W, Variable>
+    where
+        F: FnOnce() -> Tensor<B, C, H, W, Variable>,
+    {
+        if let Some(node) = self.variables.get(name) {
+            assert_shape("variable", name, node.data().dims(), [B, C, H, W]);
+            return Variable::from(node.clone()).into();
+        }
+
+        // Only declarations persist, an initializer built from other operations is detached
+        let tensor = init();
+        let tensor = if tensor.inner().node().is_declaration() {
+            tensor
+        } else {
+            tensor.freeze().unfreeze()
+        };
+        self.variables
+            .insert(String::from(name), tensor.inner().node());
+        tensor
+    }
+
+    /// Returns the constant with the given name, computing it with `init` only the first time
+    /// the name is used, so that constants taken by every step are not computed again
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name was already declared with a different shape
+    #[inline]
+    pub fn constant<const B: u64, const C: u64, const H: u64, const W: u64, F>(
+        &mut self,
+        name: &str,
+        init: F,
+    ) -> Tensor<B, C, H, W, Constant>
+    where
+        F: FnOnce() -> Tensor<B, C, H, W, Constant>,
+    {
+        if let Some(values) = self.constants.get(name) {
+            assert_shape("constant", name, values.dims(), [B, C, H, W]);
+            return Constant::new(values.clone()).into();
+        }
+
+        let tensor = init();
+        self.constants.insert(String::from(name), tensor.data());
+        tensor
+    }
+
+    /// Returns the nodes of the persistent variables sorted by name, i.e. to be optimized
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        self.variables.values().cloned().collect()
+    }
+
+    /// Records the computation graph up until the given tensor as part of the current step,
+    /// so that it is released by the next `reset`
+    #[inline]
+    pub fn track<const B: u64, const C: u64, const H: u64, const W: u64>(
+        &mut self,
+        tensor: &Tensor<B, C, H, W, Variable>,
+    ) {
+        self.tapes.push(tensor.inner().tape());
+    }
+
+    /// Records the computation graph up until the given tensor and computes the gradients of
+    /// all its ancestors, as `Tensor::backward` does
+    #[inline]
+    pub fn backward<const B: u64, const C: u64, const H: u64, const W: u64>(
+        &mut self,
+        tensor: &Tensor<B, C, H, W, Variable>,
+    ) {
+        self.track(tensor);
+        tensor.backward_with(BackwardOptions::new());
+    }
+
+    /// Starts a new step: releases the recorded computation graph and sets the gradients of
+    /// the persistent variables to zero, keeping their values
+    #[inline]
+    pub fn reset(&mut self) {
+        for tape in self.tapes.drain(..) {
+            for node in tape.nodes() {
+                node.release();
+            }
+        }
+        for node in self.variables.values() {
+            node.zero_grad();
+        }
+    }
+}
+
+/// The persistent variables of a context, named as they were declared
+#[cfg(feature = "nn")]
+impl crate::nn::Module for Context {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        self.variables
+            .iter()
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Context;
+    use crate as mu;
+    use crate::tensor::{constant::Constant, traits::Tensed, Tensor};
+    use crate::tests::equal_data;
+
+    #[test]
+    fn persistent_variables() {
+        let mut ctx = Context::new();
+        let w = ctx.variable("w", || mu::fill::<1, 1, 1, 1>(3.0));
+        let same = ctx.variable("w", || mu::fill::<1, 1, 1, 1>(0.0));
+        assert_eq!(w.inner().node().id(), same.inner().node().id());
+        assert_eq!(ctx.parameters().len(), 1);
+
+        let z = mu::mul(&w, &w);
+        ctx.backward(&z);
+        assert!(equal_data(
+            w.grad().data(),
+            arrayfire::constant!(6.0; 1,1,1,1)
+        ));
+
+        ctx.reset();
+        assert_eq!(z.inner().node().kind(), "Released");
+        assert!(equal_data(
+            w.grad().data(),
+            arrayfire::constant!(0.0; 1,1,1,1)
+        ));
+        assert!(equal_data(w.data(), arrayfire::constant!(3.0; 1,1,1,1)));
+    }
+
+    #[test]
+    fn cached_constants() {
+        let mut ctx = Context::new();
+        let encoding = ctx.constant("encoding", || {
+            mu::sin(&mu::fill::<1, 1, 2, 2>(1.0).freeze())
+        });
+        let cached = ctx.constant("encoding", || -> Tensor<1, 1, 2, 2, Constant> {
+            panic!("constants are only computed once")
+        });
+        assert!(equal_data(encoding.data(), cached.data()));
+
+        ctx.reset();
+        let cached = ctx.constant("encoding", || mu::fill::<1, 1, 2, 2>(0.0).freeze());
+        assert!(equal_data(encoding.data(), cached.data()));
+    }
+
+    #[test]
+    #[should_panic(expected = "variable w was declared with shape [1, 1, 1, 1]")]
+    fn variable_shape_mismatch() {
+        let mut ctx = Context::new();
+        ctx.variable("w", || mu::fill::<1, 1, 1, 1>(3.0));
+        ctx.variable("w", || mu::fill::<1, 1, 2, 1>(3.0));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::data::Dataset;
+use crate::tensor::Float;
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    mem,
+    path::Path,
+};
+
+/// Describes which columns of a CSV file hold the targets of each sample
+pub enum Labels<'c> {
+    /// The targets are the numeric values of the given columns
+    Columns(&'c [usize]),
+    /// The given column holds a class index, one-hot encoded among the targets
+    Class(usize),
+}
+
+/// A tabular dataset read from a CSV file, with `F` features and `T` targets per row
+pub struct CsvDataset<const F: u64, const T: u64> {
+    features: Vec<Float>,
+    targets: Vec<Float>,
+}
+
+impl<const F: u64, const T: u64> CsvDataset<F, T> {
+    /// Reads a comma separated file, taking the features from the given columns and the targets
+    /// as described by `labels`. Cells are parsed as floats, with `true` and `false` as 1 and 0.
+    /// Cells may be quoted as in RFC 4180, i.e. to hold commas in the columns that are not used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, a quoted cell is not closed, the number of
+    /// columns does not match `F` or `T`, or any cell can not be converted to a number
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline]
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        features: &[usize],
+        labels: &Labels,
+        header: bool,
+    ) -> Result<Self> {
+        let invalid = |msg: String| Error::new(ErrorKind::InvalidData, msg);
+
+        let targets_len = match *labels {
+            Labels::Columns(columns) => columns.len(),
+            Labels::Class(_) => T as usize,
+        };
+        if features.len() != F as usize || targets_len != T as usize {
+            return Err(invalid(String::from(
+                "number of columns does not match the dataset shape",
+            )));
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let mut dataset = Self {
+            features: Vec::new(),
+            targets: Vec::new(),
+        };
+
+        for (line, cells) in records(&contents)?
+            .into_iter()
+            .skip(usize::from(header))
+            .filter(|record| record.1.len() > 1 || !record.1[0].trim().is_empty())
+        {
+            let cell = |column: usize| {
+                cells
+                    .get(column)
+                    .and_then(|c| parse_cell(c.trim()))
+                    .ok_or_else(|| invalid(format!("invalid value at line {line}")))
+            };
+
+            for &column in features {
+                dataset.features.push(cell(column)?);
+            }
+
+            match *labels {
+                Labels::Columns(columns) => {
+                    for &column in columns {
+                        dataset.targets.push(cell(column)?);
+                    }
+                }
+                Labels::Class(column) => {
+                    let class = cells
+                        .get(column)
+                        .and_then(|c| c.trim().parse::<usize>().ok())
+                        .filter(|&c| c < T as usize)
+                        .ok_or_else(|| invalid(format!("invalid class at line {line}")))?;
+                    let mut target = vec![0.0; T as usize];
+                    target[class] = 1.0;
+                    dataset.targets.extend(target);
+                }
+            }
+        }
+
+        Ok(dataset)
+    }
+
+    /// Consumes this dataset and returns a copy with `f(column, value)` applied to every
+    /// feature, where `column` is the position of the feature in the row
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline]
+    pub fn map_features<M: Fn(usize, Float) -> Float>(mut self, f: M) -> Self {
+        for (i, value) in self.features.iter_mut().enumerate() {
+            *value = f(i % F as usize, *value);
+        }
+        self
+    }
+
+    /// Consumes this dataset and returns a copy with every feature scaled to zero mean
+    /// and unit variance
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    #[inline]
+    pub fn standardize(self) -> Self {
+        let (columns, rows) = (F as usize, self.len().max(1) as Float);
+
+        let mut means = vec![0.0; columns];
+        for (i, &value) in self.features.iter().enumerate() {
+            means[i % columns] += value / rows;
+        }
+
+        let mut stds = vec![0.0 as Float; columns];
+        for (i, &value) in self.features.iter().enumerate() {
+            stds[i % columns] += (value - means[i % columns]).powi(2) / rows;
+        }
+        let stds: Vec<Float> = stds.into_iter().map(|v| v.sqrt().max(1e-7)).collect();
+
+        self.map_features(|column, value| (value - means[column]) / stds[column])
+    }
+}
+
+/// Splits the contents of a CSV file into records of cells, each with the line it starts at.
+///
+/// As in RFC 4180, a cell starting with a double quote ends at the next lone one, and may hold
+/// commas, line breaks and doubled double quotes standing for a single one
+fn records(contents: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let (mut record, mut cell) = (Vec::new(), String::new());
+    let (mut line, mut start, mut quoted) = (1, 1, false);
+
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            ',' if !quoted => record.push(mem::take(&mut cell)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(mem::take(&mut cell));
+                records.push((start, mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            _ => {
+                line += usize::from(c == '\n');
+                cell.push(c);
+            }
+        }
+    }
+
+    if quoted {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unclosed quoted cell at line {start}"),
+        ));
+    }
+    if !record.is_empty() || !cell.is_empty() {
+        record.push(cell);
+        records.push((start, record));
+    }
+    Ok(records)
+}
+
+/// Converts a CSV cell into a float, accepting booleans as 1 and 0
+fn parse_cell(cell: &str) -> Option<Float> {
+    match cell {
+        "true" => Some(1.0),
+        "false" => Some(0.0),
+        _ => cell.parse().ok(),
+    }
+}
+
+impl<const F: u64, const T: u64> Dataset<1, 1, F, T> for CsvDataset<F, T> {
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline]
+    fn len(&self) -> usize {
+        self.targets.len() / T as usize
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline]
+    fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+        let (f, t) = (F as usize, T as usize);
+        (
+            self.features[index * f..(index + 1) * f].to_vec(),
+            self.targets[index * t..(index + 1) * t].to_vec(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvDataset, Labels};
+    use crate::data::Dataset;
+    use std::fs;
+
+    #[test]
+    fn csv_load() {
+        let path = std::env::temp_dir().join("mushin-dataset.csv");
+        fs::write(&path, "a,b,label\n1.0,true,2\n3.0,false,0\n").unwrap();
+
+        let dataset = CsvDataset::<2, 3>::new(&path, &[0, 1], &Labels::Class(2), true).unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(0), (vec![1.0, 1.0], vec![0.0, 0.0, 1.0]));
+
+        let dataset = dataset.standardize();
+        assert_eq!(dataset.get(1).0, vec![1.0, -1.0]);
+
+        assert!(CsvDataset::<1, 1>::new(&path, &[0], &Labels::Columns(&[1, 2]), true).is_err());
+    }
+
+    #[test]
+    fn csv_quoted_cells() {
+        let path = std::env::temp_dir().join("mushin-quoted.csv");
+        fs::write(
+            &path,
+            "name,a,label\r\n\"Smith, J.\",1.0,1\r\n\"say \"\"hi\"\",\nthere\", \"2.0\" ,0\r\n",
+        )
+        .unwrap();
+
+        let dataset = CsvDataset::<1, 2>::new(&path, &[1], &Labels::Class(2), true).unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.get(0), (vec![1.0], vec![0.0, 1.0]));
+        assert_eq!(dataset.get(1), (vec![2.0], vec![1.0, 0.0]));
+
+        fs::write(&path, "\"Smith, J.,1.0,1\n").unwrap();
+        assert!(CsvDataset::<1, 2>::new(&path, &[1], &Labels::Class(2), false).is_err());
+    }
+}
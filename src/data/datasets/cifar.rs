@@ -0,0 +1,154 @@
+#[cfg(feature = "download")]
+use crate::data::datasets::{download, gunzip, store};
+use crate::data::{
+    datasets::{check_labels, one_hot},
+    Dataset,
+};
+use crate::tensor::Float;
+#[cfg(feature = "download")]
+use std::io::Read;
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Side size of the CIFAR-10 square images
+const SIDE: usize = 32;
+
+/// Size in bytes of a CIFAR-10 record, one label byte followed by the three color planes
+const RECORD: usize = 1 + 3 * SIDE * SIDE;
+
+/// Location of the gzip compressed tar archive of binary batches downloaded by
+/// `Cifar10::download`
+#[cfg(feature = "download")]
+pub const CIFAR10_URL: &str = "https://www.cs.toronto.edu/~kriz/cifar-10-binary.tar.gz";
+
+/// The CIFAR-10 dataset of tiny images, with three channel 32x32 images scaled
+/// to [0, 1] and one-hot encoded labels for 10 classes
+pub struct Cifar10 {
+    records: Vec<u8>,
+}
+
+impl Cifar10 {
+    /// Loads either the train or the test split from a directory containing the standard
+    /// binary batches (i.e. `data_batch_1.bin` to `data_batch_5.bin` and `test_batch.bin`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files can not be read, their size is not a multiple of a record
+    /// or a label is not one of the 10 classes
+    #[inline]
+    pub fn new<P: AsRef<Path>>(dir: P, train: bool) -> Result<Self> {
+        let mut records = Vec::new();
+        for file in files(train) {
+            records.extend(fs::read(dir.as_ref().join(file))?);
+        }
+
+        if records.len() % RECORD != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "not a valid CIFAR-10 binary batch",
+            ));
+        }
+        check_labels::<10>(records.chunks_exact(RECORD).map(|record| record[0]))?;
+
+        Ok(Self { records })
+    }
+
+    /// Loads the split as `new` does, first downloading the binary batches from `CIFAR10_URL`
+    /// into the directory if any is missing
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can not be downloaded, extracted or written, or the
+    /// split can not be loaded
+    #[cfg(feature = "download")]
+    #[inline]
+    pub fn download<P: AsRef<Path>>(dir: P, train: bool) -> Result<Self> {
+        if !files(train)
+            .iter()
+            .all(|file| dir.as_ref().join(file).exists())
+        {
+            let archive = gunzip(&download(CIFAR10_URL)?)?;
+            for entry in tar::Archive::new(archive.as_slice()).entries()? {
+                let mut entry = entry?;
+                // Only the batches are kept, flattened into the directory
+                let path = entry.path()?.into_owned();
+                if path.extension().is_none_or(|extension| extension != "bin") {
+                    continue;
+                }
+                if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                    let mut bytes = Vec::new();
+                    entry.read_to_end(&mut bytes)?;
+                    store(dir.as_ref(), name, &bytes)?;
+                }
+            }
+        }
+        Self::new(dir, train)
+    }
+}
+
+/// Returns the names of the binary batches of either the train or the test split
+fn files(train: bool) -> Vec<String> {
+    if train {
+        (1..=5).map(|i| format!("data_batch_{i}.bin")).collect()
+    } else {
+        vec![String::from("test_batch.bin")]
+    }
+}
+
+impl Dataset<3, 32, 32, 10> for Cifar10 {
+    #[inline]
+    fn len(&self) -> usize {
+        self.records.len() / RECORD
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+        let record = &self.records[index * RECORD..(index + 1) * RECORD];
+        let (label, image) = (record[0], &record[1..]);
+        // Color planes are stored row by row, tensors are laid out column by column
+        let input = (0..3 * SIDE * SIDE)
+            .map(|i| {
+                let (plane, pixel) = (i / (SIDE * SIDE), i % (SIDE * SIDE));
+                Float::from(image[plane * SIDE * SIDE + (pixel % SIDE) * SIDE + pixel / SIDE])
+                    / 255.0
+            })
+            .collect();
+
+        (input, one_hot::<10>(label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cifar10, RECORD};
+    use crate::data::Dataset;
+    use crate::tensor::Float;
+    use std::fs;
+
+    #[test]
+    fn cifar10_load() {
+        let dir = std::env::temp_dir().join("mushin-cifar10");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut record = vec![0u8; RECORD];
+        record[0] = 7;
+        record[1 + 1024 + 1] = 255;
+        fs::write(dir.join("test_batch.bin"), record).unwrap();
+
+        let cifar = Cifar10::new(&dir, false).unwrap();
+        assert_eq!(cifar.len(), 1);
+
+        let (input, target) = cifar.get(0);
+        assert!((input[1024 + 32] - 1.0).abs() < Float::EPSILON);
+        assert!((target[7] - 1.0).abs() < Float::EPSILON);
+
+        // A label that is not one of the classes
+        let mut record = vec![0u8; RECORD];
+        record[0] = 10;
+        fs::write(dir.join("test_batch.bin"), record).unwrap();
+        assert!(Cifar10::new(&dir, false).is_err());
+    }
+}
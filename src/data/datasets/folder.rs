@@ -0,0 +1,126 @@
+use crate::data::{
+    datasets::one_hot,
+    vision::{encode, Normalization},
+    Dataset,
+};
+use crate::tensor::Float;
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+/// A dataset of RGB images arranged in one sub-directory per class (i.e. `root/dog/001.png`),
+/// resized to `H` height and `W` width and scaled to [0, 1], with one-hot encoded labels
+/// for `T` classes
+pub struct ImageFolder<const H: u64, const W: u64, const T: u64> {
+    classes: Vec<String>,
+    samples: Vec<(PathBuf, u8)>,
+}
+
+impl<const H: u64, const W: u64, const T: u64> ImageFolder<H, W, T> {
+    /// Indexes the images under the given root directory. Classes are sorted by name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory tree can not be read or the number of classes is not `T`
+    #[inline]
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(root)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+
+        if dirs.len() as u64 != T || T > 256 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "number of class directories does not match the dataset shape",
+            ));
+        }
+
+        let mut classes = Vec::with_capacity(dirs.len());
+        let mut samples = Vec::new();
+        for (class, dir) in (0..=u8::MAX).zip(dirs) {
+            let mut images: Vec<PathBuf> = fs::read_dir(&dir)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|path| path.is_file())
+                .collect();
+            images.sort();
+
+            samples.extend(images.into_iter().map(|path| (path, class)));
+            classes.push(
+                dir.file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            );
+        }
+
+        Ok(Self { classes, samples })
+    }
+
+    /// Returns the class names, sorted in the same order as their labels
+    #[must_use]
+    #[inline]
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+}
+
+impl<const H: u64, const W: u64, const T: u64> Dataset<3, H, W, T> for ImageFolder<H, W, T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Decodes and resizes the image at the given index
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image can not be decoded
+    #[inline]
+    fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+        let (ref path, class) = self.samples[index];
+        let image = image::open(path)
+            .unwrap_or_else(|e| panic!("failed to decode {}: {e}", path.display()))
+            .to_rgb8();
+
+        (
+            encode::<_, H, W>(&image, Normalization::Unit),
+            one_hot::<T>(class),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageFolder;
+    use crate::data::Dataset;
+    use image::{Rgb, RgbImage};
+    use std::fs;
+
+    #[test]
+    fn image_folder_load() {
+        let root = std::env::temp_dir().join("mushin-image-folder");
+        for class in ["cat", "dog"] {
+            fs::create_dir_all(root.join(class)).unwrap();
+        }
+        RgbImage::from_pixel(4, 4, Rgb([255, 0, 0]))
+            .save(root.join("dog").join("0.png"))
+            .unwrap();
+
+        let dataset = ImageFolder::<2, 2, 2>::new(&root).unwrap();
+        assert_eq!(dataset.classes(), ["cat", "dog"]);
+        assert_eq!(dataset.len(), 1);
+
+        let (input, target) = dataset.get(0);
+        assert_eq!(
+            input,
+            vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(target, vec![0.0, 1.0]);
+    }
+}
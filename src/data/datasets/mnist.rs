@@ -0,0 +1,153 @@
+#[cfg(feature = "download")]
+use crate::data::datasets::{download, gunzip, store};
+use crate::data::{
+    datasets::{check_labels, one_hot},
+    Dataset,
+};
+use crate::tensor::Float;
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Side size of the MNIST square images
+const SIDE: usize = 28;
+
+/// Location of the gzip compressed IDX files downloaded by `Mnist::download`
+#[cfg(feature = "download")]
+pub const MNIST_URL: &str = "https://ossci-datasets.s3.amazonaws.com/mnist/";
+
+/// The MNIST dataset of handwritten digits, with single channel 28x28 images scaled
+/// to [0, 1] and one-hot encoded labels for 10 classes
+pub struct Mnist {
+    images: Vec<u8>,
+    labels: Vec<u8>,
+}
+
+impl Mnist {
+    /// Loads either the train or the test split from a directory containing the standard
+    /// uncompressed IDX files (i.e. `train-images-idx3-ubyte` and `train-labels-idx1-ubyte`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files can not be read, are not valid IDX files or a label is
+    /// not a digit
+    #[inline]
+    pub fn new<P: AsRef<Path>>(dir: P, train: bool) -> Result<Self> {
+        let [images, labels] = files(train);
+        let images = fs::read(dir.as_ref().join(images))?;
+        let labels = fs::read(dir.as_ref().join(labels))?;
+
+        let images = parse_idx(images, 0x0803, 16)?;
+        let labels = parse_idx(labels, 0x0801, 8)?;
+        if images.len() != labels.len() * SIDE * SIDE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "number of images and labels differ",
+            ));
+        }
+        check_labels::<10>(labels.iter().copied())?;
+
+        Ok(Self { images, labels })
+    }
+
+    /// Loads the split as `new` does, first downloading into the directory the files it is
+    /// missing from `MNIST_URL`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the files can not be downloaded, decompressed or written, or the
+    /// split can not be loaded
+    #[cfg(feature = "download")]
+    #[inline]
+    pub fn download<P: AsRef<Path>>(dir: P, train: bool) -> Result<Self> {
+        for name in files(train) {
+            if !dir.as_ref().join(name).exists() {
+                let compressed = download(&format!("{MNIST_URL}{name}.gz"))?;
+                store(dir.as_ref(), name, &gunzip(&compressed)?)?;
+            }
+        }
+        Self::new(dir, train)
+    }
+}
+
+/// Returns the names of the images and labels files of either the train or the test split
+const fn files(train: bool) -> [&'static str; 2] {
+    if train {
+        ["train-images-idx3-ubyte", "train-labels-idx1-ubyte"]
+    } else {
+        ["t10k-images-idx3-ubyte", "t10k-labels-idx1-ubyte"]
+    }
+}
+
+/// Checks the magic number of an IDX file and returns its contents after the header
+fn parse_idx(mut bytes: Vec<u8>, magic: u32, header: usize) -> Result<Vec<u8>> {
+    match bytes.get(..4) {
+        Some(m)
+            if u32::from_be_bytes([m[0], m[1], m[2], m[3]]) == magic && bytes.len() >= header =>
+        {
+            Ok(bytes.split_off(header))
+        }
+        _ => Err(Error::new(ErrorKind::InvalidData, "not a valid IDX file")),
+    }
+}
+
+impl Dataset<1, 28, 28, 10> for Mnist {
+    #[inline]
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+        let image = &self.images[index * SIDE * SIDE..(index + 1) * SIDE * SIDE];
+        // IDX images are stored row by row, tensors are laid out column by column
+        let input = (0..SIDE * SIDE)
+            .map(|i| Float::from(image[(i % SIDE) * SIDE + i / SIDE]) / 255.0)
+            .collect();
+
+        (input, one_hot::<10>(self.labels[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mnist;
+    use crate::data::Dataset;
+    use crate::tensor::Float;
+    use std::fs;
+
+    #[test]
+    fn mnist_load() {
+        let dir = std::env::temp_dir().join("mushin-mnist");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut images = vec![0, 0, 8, 3, 0, 0, 0, 1, 0, 0, 0, 28, 0, 0, 0, 28];
+        let mut pixels = vec![0u8; 28 * 28];
+        pixels[1] = 255;
+        images.extend(pixels);
+        fs::write(dir.join("t10k-images-idx3-ubyte"), images).unwrap();
+        fs::write(
+            dir.join("t10k-labels-idx1-ubyte"),
+            [0, 0, 8, 1, 0, 0, 0, 1, 3],
+        )
+        .unwrap();
+
+        let mnist = Mnist::new(&dir, false).unwrap();
+        assert_eq!(mnist.len(), 1);
+
+        let (input, target) = mnist.get(0);
+        assert!((input[28] - 1.0).abs() < Float::EPSILON);
+        assert!((target[3] - 1.0).abs() < Float::EPSILON);
+        assert!(Mnist::new(&dir, true).is_err());
+
+        // A label that is not a digit
+        fs::write(
+            dir.join("t10k-labels-idx1-ubyte"),
+            [0, 0, 8, 1, 0, 0, 0, 1, 10],
+        )
+        .unwrap();
+        assert!(Mnist::new(&dir, false).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::tensor::Float;
+use std::io::{Error, ErrorKind, Result};
+#[cfg(feature = "download")]
+use std::{fs, io::Read, path::Path};
+
+mod cifar;
+#[cfg(feature = "image")]
+mod folder;
+mod mnist;
+
+pub use cifar::Cifar10;
+#[cfg(feature = "image")]
+pub use folder::ImageFolder;
+pub use mnist::Mnist;
+
+/// Checks that every label names one of `T` classes, so that they can be one-hot encoded
+fn check_labels<const T: u64>(mut labels: impl Iterator<Item = u8>) -> Result<()> {
+    labels
+        .find(|&label| u64::from(label) >= T)
+        .map_or(Ok(()), |label| {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("label {label} is not one of the {T} classes"),
+            ))
+        })
+}
+
+/// Downloads the file at the given URL, returning its contents
+#[cfg(feature = "download")]
+fn download(url: &str) -> Result<Vec<u8>> {
+    Ok(attohttpc::get(url).send()?.error_for_status()?.bytes()?)
+}
+
+/// Decompresses the given gzip contents
+#[cfg(feature = "download")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Writes a downloaded file aside and renames it, so that interrupted downloads are not kept
+#[cfg(feature = "download")]
+fn store(dir: &Path, name: &str, bytes: &[u8]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let partial = dir.join(format!("{name}.part"));
+    fs::write(&partial, bytes)?;
+    fs::rename(partial, dir.join(name))
+}
+
+/// Returns the one-hot encoding of the given label among `T` classes, see `check_labels`
+#[allow(clippy::cast_possible_truncation)]
+fn one_hot<const T: u64>(label: u8) -> Vec<Float> {
+    let mut target = vec![0.0; T as usize];
+    target[usize::from(label)] = 1.0;
+    target
+}
@@ -0,0 +1,295 @@
+//! This module exposes tooling to feed datasets to the computation graph in batches.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::{data::{DataLoader, Dataset}, Float};
+//!
+//! struct Squares;
+//!
+//! impl Dataset<1, 1, 1, 1> for Squares {
+//!     fn len(&self) -> usize {
+//!         10
+//!     }
+//!
+//!     fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+//!         let x = index as Float;
+//!         (vec![x], vec![x * x])
+//!     }
+//! }
+//!
+//! let loader = DataLoader::<_, 4, 1, 1, 1, 1>::new(&Squares, true, false);
+//! for (x, y) in &loader {
+//!     // x and y are constant tensors with a batch of 4 samples each
+//! }
+//! ```
+
+pub mod datasets;
+
+mod csv;
+mod text;
+#[cfg(feature = "image")]
+mod vision;
+
+pub use csv::{CsvDataset, Labels};
+#[cfg(feature = "tokenizers")]
+pub use text::from_encodings;
+pub use text::{pad_ids, Tokens};
+#[cfg(feature = "image")]
+pub use vision::{from_gray, from_rgb, to_gray, to_rgb, Normalization};
+
+use crate::tensor::{constant::Constant, on_device, Float, Tensor};
+use arrayfire::{dim4, Array};
+
+/// A collection of samples, each with an input of `C` channels, `H` height and `W` width
+/// and a target row vector of size `T`
+pub trait Dataset<const C: u64, const H: u64, const W: u64, const T: u64> {
+    /// Returns the number of samples in the dataset
+    fn len(&self) -> usize;
+
+    /// Returns true if the dataset has no samples
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the input and target values of the sample at the given index, laid out in the
+    /// same order `custom` expects them
+    fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>);
+}
+
+/// Iterates over a `Dataset` in batches of `B` samples, optionally shuffling them every epoch.
+/// The last incomplete batch is either dropped or padded with samples from the start of the epoch
+#[allow(clippy::module_name_repetitions)]
+pub struct DataLoader<
+    'd,
+    D: Dataset<C, H, W, T>,
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const T: u64,
+> {
+    dataset: &'d D,
+    shuffle: bool,
+    drop_last: bool,
+    device: Option<i32>,
+}
+
+impl<
+        'd,
+        D: Dataset<C, H, W, T>,
+        const B: u64,
+        const C: u64,
+        const H: u64,
+        const W: u64,
+        const T: u64,
+    > DataLoader<'d, D, B, C, H, W, T>
+{
+    /// Returns a new `DataLoader` over the given dataset
+    #[must_use]
+    #[inline]
+    pub const fn new(dataset: &'d D, shuffle: bool, drop_last: bool) -> Self {
+        Self {
+            dataset,
+            shuffle,
+            drop_last,
+            device: None,
+        }
+    }
+
+    /// Creates the batches in the device of the given id instead of the active one, i.e. to
+    /// feed a model running in another GPU. As with `Tensor::to_device`, the batches are copied
+    /// from pageable host memory rather than pinned, so the copies are not overlapped with compute
+    #[must_use]
+    #[inline]
+    pub const fn device(self, id: i32) -> Self {
+        Self {
+            device: Some(id),
+            ..self
+        }
+    }
+
+    /// Returns the number of batches in an epoch
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn len(&self) -> usize {
+        let (samples, batch) = (self.dataset.len(), B as usize);
+        if self.drop_last {
+            samples / batch
+        } else {
+            (samples + batch - 1) / batch
+        }
+    }
+
+    /// Returns true if an epoch yields no batches
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the batches of a new epoch
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn iter(&self) -> Batches<'d, D, B, C, H, W, T> {
+        let samples = self.dataset.len();
+        let order = if self.shuffle {
+            let keys = arrayfire::randu::<Float>(dim4!(samples as u64));
+            let (_, indices) = arrayfire::sort_index(&keys, 0, true);
+            let mut order = vec![0u32; samples];
+            indices.host(&mut order);
+            order.into_iter().map(|i| i as usize).collect()
+        } else {
+            (0..samples).collect()
+        };
+
+        Batches {
+            dataset: self.dataset,
+            order,
+            current: 0,
+            total: self.len(),
+            device: self.device,
+        }
+    }
+}
+
+impl<
+        'd,
+        D: Dataset<C, H, W, T>,
+        const B: u64,
+        const C: u64,
+        const H: u64,
+        const W: u64,
+        const T: u64,
+    > IntoIterator for &DataLoader<'d, D, B, C, H, W, T>
+{
+    type Item = (Tensor<B, C, H, W, Constant>, Tensor<B, 1, 1, T, Constant>);
+    type IntoIter = Batches<'d, D, B, C, H, W, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the batches of a single epoch, yielding constant tensors for the inputs and targets
+pub struct Batches<
+    'd,
+    D: Dataset<C, H, W, T>,
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const T: u64,
+> {
+    dataset: &'d D,
+    order: Vec<usize>,
+    current: usize,
+    total: usize,
+    device: Option<i32>,
+}
+
+impl<
+        D: Dataset<C, H, W, T>,
+        const B: u64,
+        const C: u64,
+        const H: u64,
+        const W: u64,
+        const T: u64,
+    > Iterator for Batches<'_, D, B, C, H, W, T>
+{
+    type Item = (Tensor<B, C, H, W, Constant>, Tensor<B, 1, 1, T, Constant>);
+
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current >= self.total {
+            return None;
+        }
+
+        let size = B as usize;
+        let mut inputs = Vec::with_capacity(size * (C * H * W) as usize);
+        let mut targets = Vec::with_capacity(size * T as usize);
+        for i in 0..size {
+            // Wraps around to pad the last batch with samples from the start of the epoch
+            let index = self.order[(self.current * size + i) % self.order.len()];
+            let (input, target) = self.dataset.get(index);
+            inputs.extend(input);
+            targets.extend(target);
+        }
+        self.current += 1;
+
+        let create = || {
+            (
+                Constant::new(Array::new(&inputs, dim4!(H, W, C, B))).into(),
+                Constant::new(Array::new(&targets, dim4!(1, T, 1, B))).into(),
+            )
+        };
+        Some(self.device.map_or_else(create, |id| on_device(id, create)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataLoader, Dataset};
+    use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+    use arrayfire::Array;
+
+    struct Range(usize);
+
+    impl Dataset<1, 1, 2, 1> for Range {
+        fn len(&self) -> usize {
+            self.0
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+            let x = index as Float;
+            (vec![x, -x], vec![x])
+        }
+    }
+
+    #[test]
+    fn dataloader_pads_last_batch() {
+        let dataset = Range(3);
+        let loader = DataLoader::<_, 2, 1, 1, 2, 1>::new(&dataset, false, false);
+        assert_eq!(loader.len(), 2);
+
+        let batches: Vec<_> = loader.iter().collect();
+        assert!(equal_data(
+            batches[1].0.data(),
+            Array::new(&[2.0, -2.0, 0.0, 0.0], arrayfire::dim4!(1, 2, 1, 2))
+        ));
+        assert!(equal_data(
+            batches[1].1.data(),
+            Array::new(&[2.0, 0.0], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn dataloader_drops_last_batch() {
+        let dataset = Range(3);
+        let loader = DataLoader::<_, 2, 1, 1, 2, 1>::new(&dataset, true, true);
+        assert_eq!(loader.len(), 1);
+        assert_eq!(loader.iter().count(), 1);
+    }
+
+    #[test]
+    fn dataloader_device() {
+        let dataset = Range(2);
+        let loader = DataLoader::<_, 2, 1, 1, 2, 1>::new(&dataset, false, true).device(0);
+        let (x, y) = loader.iter().next().unwrap();
+        assert_eq!((x.device(), y.device()), (0, 0));
+
+        let copy = x.to_device(0);
+        assert_eq!(copy.device(), 0);
+        assert!(equal_data(copy.data(), x.data()));
+    }
+}
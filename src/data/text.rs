@@ -0,0 +1,99 @@
+use crate::{
+    gen::ShapeError,
+    tensor::{constant::Constant, Float, Tensor},
+};
+use arrayfire::{dim4, Array};
+
+/// A batch of `B` token id sequences of length `L`, along with its attention mask holding
+/// 1 for actual tokens and 0 for padding
+pub type Tokens<const B: u64, const L: u64> =
+    (Tensor<B, 1, 1, L, Constant>, Tensor<B, 1, 1, L, Constant>);
+
+/// Pads with `pad_id` or truncates every row to length `L`, masking out padded positions
+/// and those already masked out by the row's own mask, if any
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn collate<const B: u64, const L: u64>(
+    rows: &[(&[u32], Option<&[u32]>)],
+    pad_id: u32,
+) -> Result<Tokens<B, L>, ShapeError> {
+    if rows.len() != B as usize {
+        return Err(ShapeError {
+            expected: B as usize,
+            found: rows.len(),
+        });
+    }
+
+    let length = L as usize;
+    let mut ids = vec![pad_id as Float; rows.len() * length];
+    let mut mask = vec![0.0; rows.len() * length];
+    for (row, &(tokens, attention)) in rows.iter().enumerate() {
+        for (position, &token) in tokens.iter().take(length).enumerate() {
+            ids[row * length + position] = token as Float;
+            mask[row * length + position] =
+                attention.map_or(1.0, |a| a.get(position).map_or(1.0, |&m| m as Float));
+        }
+    }
+
+    Ok((
+        Constant::new(Array::new(&ids, dim4!(1, L, 1, B))).into(),
+        Constant::new(Array::new(&mask, dim4!(1, L, 1, B))).into(),
+    ))
+}
+
+/// Turns `B` token id sequences into a batch of ids padded with `pad_id` or truncated to
+/// length `L`, plus its attention mask. Ids are stored as floats, so they are exact up to 2^24
+///
+/// # Errors
+///
+/// Returns an error if the number of sequences is not `B`
+#[inline]
+pub fn pad_ids<const B: u64, const L: u64, S: AsRef<[u32]>>(
+    sequences: &[S],
+    pad_id: u32,
+) -> Result<Tokens<B, L>, ShapeError> {
+    let rows: Vec<_> = sequences.iter().map(|s| (s.as_ref(), None)).collect();
+    collate(&rows, pad_id)
+}
+
+/// Turns `B` encodings produced by a `tokenizers` tokenizer into a batch, as `pad_ids` does.
+///
+/// The attention mask also honors the encodings' own attention masks
+///
+/// # Errors
+///
+/// Returns an error if the number of encodings is not `B`
+#[cfg(feature = "tokenizers")]
+#[inline]
+pub fn from_encodings<const B: u64, const L: u64>(
+    encodings: &[tokenizers::Encoding],
+    pad_id: u32,
+) -> Result<Tokens<B, L>, ShapeError> {
+    let rows: Vec<_> = encodings
+        .iter()
+        .map(|e| (e.get_ids(), Some(e.get_attention_mask())))
+        .collect();
+    collate(&rows, pad_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pad_ids;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn pad_and_truncate() {
+        let (ids, mask) = pad_ids::<2, 3, _>(&[vec![5, 6, 7, 8], vec![9]], 0).unwrap();
+        assert!(equal_data(
+            ids.data(),
+            Array::new(&[5.0, 6.0, 7.0, 9.0, 0.0, 0.0], dim4!(1, 3, 1, 2))
+        ));
+        assert!(equal_data(
+            mask.data(),
+            Array::new(&[1.0, 1.0, 1.0, 1.0, 0.0, 0.0], dim4!(1, 3, 1, 2))
+        ));
+
+        assert!(pad_ids::<3, 3, _>(&[vec![1]], 0).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::tensor::{constant::Constant, traits::Data, Float, Tensor};
+use arrayfire::{dim4, Array};
+use image::{imageops::FilterType, GrayImage, ImageBuffer, Pixel, RgbImage};
+
+/// How 8 bit pixel intensities are mapped to tensor values
+#[derive(Clone, Copy, Debug)]
+pub enum Normalization {
+    /// Intensities are scaled to [0, 1]
+    Unit,
+    /// Intensities are scaled to [-1, 1]
+    Symmetric,
+    /// Intensities are scaled to [0, 1] and then standardized with the given per channel
+    /// mean and standard deviation. Grayscale images only use the first channel
+    Standard {
+        /// Mean of every channel
+        mean: [Float; 3],
+        /// Standard deviation of every channel
+        std: [Float; 3],
+    },
+}
+
+impl Normalization {
+    fn normalize(self, channel: usize, intensity: u8) -> Float {
+        let unit = Float::from(intensity) / 255.0;
+        match self {
+            Self::Unit => unit,
+            Self::Symmetric => unit.mul_add(2.0, -1.0),
+            Self::Standard { mean, std } => (unit - mean[channel]) / std[channel],
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn denormalize(self, channel: usize, value: Float) -> u8 {
+        let unit = match self {
+            Self::Unit => value,
+            Self::Symmetric => value.mul_add(0.5, 0.5),
+            Self::Standard { mean, std } => value.mul_add(std[channel], mean[channel]),
+        };
+        (unit * 255.0).round().clamp(0.0, 255.0) as u8
+    }
+}
+
+/// Returns the normalized values of an image, resized to `H` height and `W` width if needed,
+/// laid out column by column, one channel plane after the other
+#[allow(clippy::cast_possible_truncation)]
+pub(super) fn encode<P, const H: u64, const W: u64>(
+    image: &ImageBuffer<P, Vec<u8>>,
+    normalization: Normalization,
+) -> Vec<Float>
+where
+    P: Pixel<Subpixel = u8> + 'static,
+{
+    let (height, width) = (H as u32, W as u32);
+    let resized;
+    let image = if image.dimensions() == (width, height) {
+        image
+    } else {
+        resized = image::imageops::resize(image, width, height, FilterType::Triangle);
+        &resized
+    };
+
+    let channels = usize::from(P::CHANNEL_COUNT);
+    let mut values = Vec::with_capacity(channels * (H * W) as usize);
+    for channel in 0..channels {
+        for x in 0..width {
+            for y in 0..height {
+                let intensity = image.get_pixel(x, y).channels()[channel];
+                values.push(normalization.normalize(channel, intensity));
+            }
+        }
+    }
+    values
+}
+
+/// Builds an image of `H` height and `W` width from the values of a single sample tensor
+#[allow(clippy::cast_possible_truncation)]
+fn decode<P, const C: u64, const H: u64, const W: u64, D: Data>(
+    tensor: &Tensor<1, C, H, W, D>,
+    normalization: Normalization,
+) -> ImageBuffer<P, Vec<u8>>
+where
+    P: Pixel<Subpixel = u8> + 'static,
+{
+    let values = tensor.to_vec();
+    let plane = (H * W) as usize;
+    ImageBuffer::from_fn(W as u32, H as u32, |x, y| {
+        let index = x as usize * H as usize + y as usize;
+        let intensities: Vec<u8> = (0..C as usize)
+            .map(|c| normalization.denormalize(c, values[c * plane + index]))
+            .collect();
+        *P::from_slice(&intensities)
+    })
+}
+
+/// Converts an RGB image into a constant tensor, resizing it to `H` height and `W` width if needed
+#[must_use]
+#[inline]
+pub fn from_rgb<const H: u64, const W: u64>(
+    image: &RgbImage,
+    normalization: Normalization,
+) -> Tensor<1, 3, H, W, Constant> {
+    let values = encode::<_, H, W>(image, normalization);
+    Constant::new(Array::new(&values, dim4!(H, W, 3, 1))).into()
+}
+
+/// Converts a grayscale image into a constant tensor, resizing it to `H` height and `W` width
+/// if needed
+#[must_use]
+#[inline]
+pub fn from_gray<const H: u64, const W: u64>(
+    image: &GrayImage,
+    normalization: Normalization,
+) -> Tensor<1, 1, H, W, Constant> {
+    let values = encode::<_, H, W>(image, normalization);
+    Constant::new(Array::new(&values, dim4!(H, W, 1, 1))).into()
+}
+
+/// Converts a tensor into an RGB image, reverting the given normalization.
+/// Intensities out of range are clamped
+#[must_use]
+#[inline]
+pub fn to_rgb<const H: u64, const W: u64, D: Data>(
+    tensor: &Tensor<1, 3, H, W, D>,
+    normalization: Normalization,
+) -> RgbImage {
+    decode(tensor, normalization)
+}
+
+/// Converts a tensor into a grayscale image, reverting the given normalization.
+/// Intensities out of range are clamped
+#[must_use]
+#[inline]
+pub fn to_gray<const H: u64, const W: u64, D: Data>(
+    tensor: &Tensor<1, 1, H, W, D>,
+    normalization: Normalization,
+) -> GrayImage {
+    decode(tensor, normalization)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, from_rgb, to_rgb, Normalization};
+    use image::{Luma, Rgb, RgbImage};
+
+    #[test]
+    fn encode_normalization() {
+        let image = image::GrayImage::from_pixel(2, 1, Luma([255]));
+        assert_eq!(
+            encode::<_, 1, 2>(&image, Normalization::Symmetric),
+            vec![1.0, 1.0]
+        );
+
+        let standard = Normalization::Standard {
+            mean: [0.5, 0.0, 0.0],
+            std: [0.5, 1.0, 1.0],
+        };
+        assert_eq!(encode::<_, 1, 2>(&image, standard), vec![1.0, 1.0]);
+        assert_eq!(standard.denormalize(0, 1.0), 255);
+    }
+
+    #[test]
+    fn rgb_roundtrip() {
+        let image = RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 50, y as u8 * 100, 7]));
+        let tensor = from_rgb::<2, 3>(&image, Normalization::Unit);
+        assert_eq!(to_rgb(&tensor, Normalization::Unit), image);
+    }
+}
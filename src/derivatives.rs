@@ -0,0 +1,288 @@
+//! Derivatives beyond the gradients of a scalar: full derivative matrices and gradients
+//! kept separate for every sample of a batch.
+//!
+//! Matrices are returned as constant tensors with one row per output value and one column
+//! per input value, both indexed in the column-major order taken by `custom`.
+
+use crate::graph::{node::Node, shared::Shared};
+use crate::tensor::{
+    constant::Constant,
+    traits::{Data, Tensed},
+    variable::Variable,
+    Float, Tensor,
+};
+use arrayfire::{dim4, seq, view, Array, Seq};
+
+/// Returns the jacobian of `y` with respect to `x`, computed with one backward pass per
+/// value of `y`. Values of `y` not depending on `x` have a row of zeros.
+///
+/// The gradients of the computation graph of `y` are set to zero once done
+///
+/// # Panics
+///
+/// Panics if the computation graph of `y` was released by a previous backward pass
+#[cfg(feature = "nightly")]
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn jacobian<
+    const YB: u64,
+    const YC: u64,
+    const YH: u64,
+    const YW: u64,
+    const XB: u64,
+    const XC: u64,
+    const XH: u64,
+    const XW: u64,
+>(
+    y: &Tensor<YB, YC, YH, YW, Variable>,
+    x: &Tensor<XB, XC, XH, XW, Variable>,
+) -> Tensor<1, 1, { YB * YC * YH * YW }, { XB * XC * XH * XW }, Constant> {
+    let (rows, cols) = ((YB * YC * YH * YW) as usize, (XB * XC * XH * XW) as usize);
+    let tape = y.inner().tape();
+    let (output, input) = (y.inner().node(), x.inner().node());
+    let mut values = vec![0.0; rows * cols];
+    if !tape.nodes().any(|node| Shared::ptr_eq(node, &input)) {
+        return Constant::new(Array::new(&values, dim4!(rows as u64, cols as u64, 1, 1))).into();
+    }
+
+    let mut seed = vec![0.0; rows];
+    for row in 0..rows {
+        for node in tape.nodes() {
+            node.zero_grad();
+        }
+        seed[row] = 1.0;
+        output.set_grad(Array::new(&seed, dim4!(YH, YW, YC, YB)));
+        seed[row] = 0.0;
+        for node in tape.nodes().rev() {
+            node.reverse();
+        }
+
+        let mut grad = vec![0.0; cols];
+        input.grad().host(&mut grad);
+        for (col, value) in grad.into_iter().enumerate() {
+            values[col * rows + row] = value;
+        }
+    }
+
+    for node in tape.nodes() {
+        node.zero_grad();
+    }
+    Constant::new(Array::new(&values, dim4!(rows as u64, cols as u64, 1, 1))).into()
+}
+
+/// Returns an approximation of the hessian of the scalar function `f` at `x`.
+///
+/// The backward passes are not differentiable, so the second derivatives are not exact: every
+/// column is the central finite difference of the exact gradients of `f`, which takes two
+/// backward passes per value of `x` and has an error quadratic on the step. The step is the
+/// power of two closest to the optimal one for the magnitude of every value, so that the
+/// shifted values are exact and so is the result for quadratic functions. The result is made
+/// symmetric by averaging it with its transpose
+#[cfg(feature = "nightly")]
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn approx_hessian<const B: u64, const C: u64, const H: u64, const W: u64, X: Data, F>(
+    f: F,
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<1, 1, { B * C * H * W }, { B * C * H * W }, Constant>
+where
+    F: Fn(&Tensor<B, C, H, W, Variable>) -> Tensor<1, 1, 1, 1, Variable>,
+{
+    let n = (B * C * H * W) as usize;
+    let gradient = |values: &[Float]| {
+        let input: Tensor<B, C, H, W, Variable> =
+            Variable::from(Array::new(values, dim4!(H, W, C, B))).into();
+        f(&input).backward();
+        input.grad().to_vec()
+    };
+
+    // The step minimizing the sum of the truncation and rounding errors
+    let epsilon = Float::EPSILON.cbrt();
+    let mut point = x.to_vec();
+    let mut values = vec![0.0; n * n];
+    for col in 0..n {
+        let value = point[col];
+        let step = (epsilon * value.abs().max(1.0)).log2().round().exp2();
+
+        point[col] = value + step;
+        let forward = gradient(&point);
+        point[col] = value - step;
+        let backward = gradient(&point);
+        point[col] = value;
+
+        for row in 0..n {
+            values[col * n + row] = (forward[row] - backward[row]) / (2.0 * step);
+        }
+    }
+
+    let hessian = Array::new(&values, dim4!(n as u64, n as u64, 1, 1));
+    let symmetric = (&hessian + &arrayfire::transpose(&hessian, false)) * (0.5 as Float);
+    Constant::new(symmetric).into()
+}
+
+/// Returns the gradients of every parameter for each sample of the batch `x` separately,
+/// i.e. to clip them individually for differential privacy.
+///
+/// `f` is called with every sample and its index in the batch, and returns its loss. The
+/// gradients of each parameter are stacked along the batch dimension, so they have the shape
+/// of the parameter with `B` times its batch size. Gradients accumulated by the parameters
+/// before the call are kept, while those of the intermediate results shared by the samples
+/// are computed anew for every one of them
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub fn per_sample_grads<const B: u64, const C: u64, const H: u64, const W: u64, X: Data, F>(
+    params: &[Shared<Node>],
+    x: &Tensor<B, C, H, W, X>,
+    f: F,
+) -> Vec<Array<Float>>
+where
+    F: Fn(&Tensor<1, C, H, W, Constant>, usize) -> Tensor<1, 1, 1, 1, Variable>,
+{
+    let saved: Vec<_> = params.iter().map(|p| p.grad()).collect();
+    let mut stacked: Vec<Vec<Float>> = params
+        .iter()
+        .map(|p| Vec::with_capacity(p.data().elements() * B as usize))
+        .collect();
+
+    let data = x.data();
+    for b in 0..B as usize {
+        let index = Seq::new(b as i32, b as i32, 1);
+        let all = seq!();
+        let sample: Tensor<1, C, H, W, Constant> =
+            Constant::new(view!(data[all, all, all, index])).into();
+
+        for param in params {
+            param.zero_grad();
+        }
+        f(&sample, b).backward();
+
+        for (param, values) in params.iter().zip(&mut stacked) {
+            let grad = param.grad();
+            let start = values.len();
+            values.resize(start + grad.elements(), 0.0);
+            grad.host(&mut values[start..]);
+        }
+    }
+
+    for (param, grad) in params.iter().zip(saved) {
+        param.set_grad(grad);
+    }
+
+    params
+        .iter()
+        .zip(stacked)
+        .map(|(param, values)| {
+            let dims = param.data().dims();
+            Array::new(&values, dim4!(dims[0], dims[1], dims[2], dims[3] * B))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::per_sample_grads;
+    #[cfg(feature = "nightly")]
+    use super::{approx_hessian, jacobian};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn jacobian_of_product() {
+        let x = mu::custom::<1, 1, 1, 2>(&[2.0, 3.0]);
+        let w = mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).freeze();
+        let y = mu::mm(&mu::mul(&x, &x), &w);
+
+        // dy_i/dx_j = 2 * x_j * w_ji
+        let expected = Array::new(&[4.0, 12.0, 20.0, 12.0, 24.0, 36.0], dim4!(3, 2, 1, 1));
+        assert!(equal_data(jacobian(&y, &x).data(), expected));
+
+        let unrelated = mu::fill::<1, 1, 1, 1>(1.0);
+        assert!(equal_data(
+            jacobian(&y, &unrelated).data(),
+            arrayfire::constant!(0.0; 3,1,1,1)
+        ));
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn hessian_of_quadratic() {
+        let x = mu::custom::<1, 1, 1, 2>(&[1.0, -3.0]).freeze();
+        let a = mu::custom::<1, 1, 2, 2>(&[1.0, 0.0, 2.0, 3.0]).freeze();
+        let ones = mu::fill::<1, 1, 2, 1>(1.0).freeze();
+
+        // f(x) = x A x^T, so H = A + A^T exactly
+        let h = approx_hessian(|x| mu::mm(&mu::mul(&mu::mm(x, &a), x), &ones), &x);
+        let expected = Array::new(&[2.0, 2.0, 2.0, 6.0], dim4!(2, 2, 1, 1));
+        assert!(equal_data(h.data(), expected));
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn hessian_of_cubic() {
+        let x = mu::custom::<1, 1, 1, 2>(&[1.0, 2.0]).freeze();
+        let a = mu::custom::<1, 1, 2, 2>(&[1.0, 0.0, 2.0, 3.0]).freeze();
+        let ones = mu::fill::<1, 1, 2, 1>(1.0).freeze();
+
+        // f(x) = x A x^T + x_0^3 + x_1^3, so H = A + A^T + diag(6 x), up to the step
+        let h = approx_hessian(
+            |x| {
+                let quadratic = mu::mul(&mu::mm(x, &a), x);
+                let cubic = mu::mul(&mu::mul(x, x), x);
+                mu::mm(&mu::add(&quadratic, &cubic), &ones)
+            },
+            &x,
+        );
+        let expected = Array::new(&[8.0, 2.0, 2.0, 18.0], dim4!(2, 2, 1, 1));
+        assert!(
+            arrayfire::all_true_all(&arrayfire::le(
+                &arrayfire::abs(&(h.data() - expected)),
+                &1e-2,
+                false
+            ))
+            .0
+        );
+    }
+
+    #[test]
+    fn per_sample_linear_grads() {
+        let w = mu::custom::<1, 1, 2, 1>(&[1.0, -1.0]);
+        let x = mu::custom::<2, 1, 1, 2>(&[1.0, 2.0, 3.0, 5.0]).freeze();
+
+        // The loss of every sample is x w, so its gradients are the sample itself
+        w.inner()
+            .node()
+            .set_grad(arrayfire::constant!(7.0; 2,1,1,1));
+        let grads = per_sample_grads(&[w.inner().node()], &x, |sample, _| mu::mm(sample, &w));
+        assert!(equal_data(
+            grads[0].clone(),
+            Array::new(&[1.0, 2.0, 3.0, 5.0], dim4!(2, 1, 1, 2))
+        ));
+        assert!(equal_data(
+            w.grad().data(),
+            arrayfire::constant!(7.0; 2,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn per_sample_shared_intermediate() {
+        let w = mu::custom::<1, 1, 2, 1>(&[1.0, -1.0]);
+        let x = mu::custom::<2, 1, 1, 2>(&[1.0, 2.0, 3.0, 5.0]).freeze();
+        let squared = mu::mul(&w, &w);
+
+        // The loss of every sample is x w^2, so its gradients are 2 w x, whatever the gradients
+        // the shared square got from the previous samples
+        let grads = per_sample_grads(&[w.inner().node()], &x, |sample, _| {
+            mu::mm(sample, &squared)
+        });
+        assert!(equal_data(
+            grads[0].clone(),
+            Array::new(&[2.0, -4.0, 6.0, -10.0], dim4!(2, 1, 1, 2))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Tensed},
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array};
+
+/// A categorical distribution over `W` classes for every sample of a batch, given by the
+/// unnormalized log probabilities (logits) of the classes
+pub struct Categorical<const B: u64, const W: u64, X: Data> {
+    logits: Tensor<B, 1, 1, W, X>,
+    /// The log probabilities of the classes
+    log_probs: Array<Float>,
+    /// The probabilities of the classes
+    probs: Array<Float>,
+}
+
+impl<const B: u64, const W: u64, X: Data> Categorical<B, W, X> {
+    /// Returns the distribution given by the logits of every sample
+    #[must_use]
+    #[inline]
+    pub fn new(logits: &Tensor<B, 1, 1, W, X>) -> Self
+    where
+        X: Clone,
+    {
+        let data = logits.data();
+        // Shift each sample by its maximum logit, this is required for numerical stability
+        let shift = arrayfire::sub(&data, &arrayfire::max(&data, 1), true);
+        let exps = arrayfire::exp(&shift);
+        let sums = arrayfire::sum(&exps, 1);
+        Self {
+            logits: logits.clone(),
+            log_probs: arrayfire::sub(&shift, &arrayfire::log(&sums), true),
+            probs: arrayfire::div(&exps, &sums, true),
+        }
+    }
+
+    /// Returns the probabilities of the classes
+    #[must_use]
+    #[inline]
+    pub fn probs(&self) -> Tensor<B, 1, 1, W, Constant> {
+        Constant::new(self.probs.clone()).into()
+    }
+
+    /// Samples a class for every sample of the batch, one-hot encoded as the targets taken by
+    /// `nn::losses::nll`. Sampling is not differentiable, so the classes are constant
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sample(&self) -> Tensor<B, 1, 1, W, Constant> {
+        let cdf = arrayfire::accum(&self.probs, 1);
+        let draws = arrayfire::randu!(Float; 1, 1, 1, B);
+        // The class drawn is the number of classes whose cumulative probability is below
+        // the draw, which rounding may take to `W` for draws close to one
+        let below = arrayfire::lt(&cdf, &draws, true).cast::<Float>();
+        let classes = arrayfire::minof(&arrayfire::sum(&below, 1), &((W - 1) as Float), false);
+        let indices = arrayfire::range::<Float>(dim4!(1, W, 1, B), 1);
+        Constant::new(arrayfire::eq(&indices, &classes, true).cast::<Float>()).into()
+    }
+
+    /// Returns the log probability of the given one-hot encoded classes, for every sample of
+    /// the batch
+    #[inline]
+    pub fn log_prob(&self, classes: &Tensor<B, 1, 1, W, Constant>) -> Tensor<B, 1, 1, 1, X> {
+        let _op = profiler::forward("categorical_log_prob");
+        let classes = classes.data();
+        let result = arrayfire::sum(&arrayfire::mul(&classes, &self.log_probs, false), 1);
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (p, c) = (&args[0], &args[1]);
+            let grad = arrayfire::sub(c, &arrayfire::mul(p, &arrayfire::sum(c, 1), true), false);
+            arrayfire::mul(df, &grad, true)
+        };
+
+        self.logits
+            .push_unary(result, reverse, vec![self.probs.clone(), classes])
+    }
+
+    /// Returns the entropy of the distribution of every sample of the batch, i.e. to
+    /// encourage exploration
+    #[inline]
+    pub fn entropy(&self) -> Tensor<B, 1, 1, 1, X> {
+        let _op = profiler::forward("categorical_entropy");
+        let result = -arrayfire::sum(&arrayfire::mul(&self.probs, &self.log_probs, false), 1);
+        // The derivative of the entropy with respect to every logit is -p * (log(p) + entropy)
+        let grad = -arrayfire::mul(
+            &self.probs,
+            &arrayfire::add(&self.log_probs, &result, true),
+            false,
+        );
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| arrayfire::mul(df, &args[0], true);
+
+        self.logits.push_unary(result, reverse, vec![grad])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Categorical;
+    use crate as mu;
+    use crate::tensor::{traits::Tensed, Float};
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn categorical_log_prob() {
+        let logits = mu::custom::<2, 1, 1, 2>(&[0.0, 0.0, 1.0, -1.0]);
+        let policy = Categorical::new(&logits);
+        let classes = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]).freeze();
+
+        let z = policy.log_prob(&classes);
+        let p = 1.0 / (1.0 + (2.0 as Float).exp());
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[(0.5 as Float).ln(), p.ln()], dim4!(1, 1, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            logits.grad().data(),
+            Array::new(&[0.5, -0.5, p - 1.0, 1.0 - p], dim4!(1, 2, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn categorical_entropy() {
+        let logits = mu::fill::<1, 1, 1, 4>(3.0);
+        let z = Categorical::new(&logits).entropy();
+        assert!((z.to_scalar() - (4.0 as Float).ln()).abs() < 1e-6);
+
+        // The uniform distribution has the largest entropy
+        z.backward();
+        assert!(equal_data(
+            logits.grad().data(),
+            arrayfire::constant!(0.0; 1, 4, 1, 1)
+        ));
+    }
+
+    #[test]
+    fn categorical_sample() {
+        let logits = mu::custom::<3, 1, 1, 3>(&[100.0, 0.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 100.0]);
+        let classes = Categorical::new(&logits).sample();
+        assert!(equal_data(
+            classes.data(),
+            Array::new(
+                &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+                dim4!(1, 3, 1, 3)
+            )
+        ));
+    }
+}
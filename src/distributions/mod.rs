@@ -0,0 +1,24 @@
+//! Probability distributions parameterized by tensors.
+//!
+//! Distributions sample values and compute their differentiable log probabilities, i.e. to
+//! train policies with REINFORCE or variational autoencoders.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::distributions::Categorical;
+//!
+//! let logits = mu::randn::<8, 1, 1, 4>();
+//! let policy = Categorical::new(&logits);
+//!
+//! let actions = policy.sample();
+//! let log_probs = policy.log_prob(&actions);
+//! ```
+
+mod categorical;
+mod normal;
+
+pub use categorical::Categorical;
+pub use normal::Normal;
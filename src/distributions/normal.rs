@@ -0,0 +1,147 @@
+use crate::{
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Pair, Tensed},
+        Float, Tensor,
+    },
+};
+use arrayfire::Array;
+
+/// A normal (Gaussian) distribution for every value of a tensor, given by the tensors of its
+/// means and standard deviations
+pub struct Normal<const B: u64, const C: u64, const H: u64, const W: u64, X: Data, Y: Data> {
+    mean: Tensor<B, C, H, W, X>,
+    std: Tensor<B, C, H, W, Y>,
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, X: Data + Pair<Y>, Y: Data>
+    Normal<B, C, H, W, X, Y>
+{
+    /// Returns the distribution with the given means and standard deviations, which must be
+    /// positive
+    #[must_use]
+    #[inline]
+    pub fn new(mean: &Tensor<B, C, H, W, X>, std: &Tensor<B, C, H, W, Y>) -> Self
+    where
+        X: Clone,
+        Y: Clone,
+    {
+        Self {
+            mean: mean.clone(),
+            std: std.clone(),
+        }
+    }
+
+    /// Returns values sampled from the distribution, which are constant as sampling is not
+    /// differentiable, see `rsample`
+    #[must_use]
+    #[inline]
+    pub fn sample(&self) -> Tensor<B, C, H, W, Constant> {
+        let noise = arrayfire::randn!(Float; H, W, C, B);
+        let values = arrayfire::add(
+            &self.mean.data(),
+            &arrayfire::mul(&self.std.data(), &noise, false),
+            false,
+        );
+        Constant::new(values).into()
+    }
+
+    /// Returns values sampled from the distribution with the reparameterization trick, as
+    /// `mean + std * noise` with the noise taken from a unit normal distribution, so that the
+    /// gradients of the values flow to the means and standard deviations
+    #[inline]
+    pub fn rsample(&self) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+        let _op = profiler::forward("normal_rsample");
+        let noise = arrayfire::randn!(Float; H, W, C, B);
+        let values = arrayfire::add(
+            &self.mean.data(),
+            &arrayfire::mul(&self.std.data(), &noise, false),
+            false,
+        );
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| (df.clone(), df * &args[0]);
+        self.mean
+            .push_binary(&self.std, values, reverse, vec![noise])
+    }
+
+    /// Returns the log probability density of the given values
+    #[inline]
+    pub fn log_prob(
+        &self,
+        values: &Tensor<B, C, H, W, Constant>,
+    ) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+        let _op = profiler::forward("normal_log_prob");
+        let (mean, deviation) = (self.mean.data(), self.std.data());
+        let standardized = arrayfire::div(
+            &arrayfire::sub(&values.data(), &mean, false),
+            &deviation,
+            false,
+        );
+        // log(sqrt(2 * pi))
+        let log_norm = 0.5 * (2.0 * std::f64::consts::PI).ln() as Float;
+        let result = -(arrayfire::mul(&standardized, &standardized, false) * (0.5 as Float))
+            - arrayfire::log(&deviation)
+            - log_norm;
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (z, s) = (&args[0], &args[1]);
+            // The derivatives are z / s for the mean and (z^2 - 1) / s for the deviation
+            let dmean = arrayfire::div(z, s, false);
+            let dstd = arrayfire::div(&(arrayfire::mul(z, z, false) - (1.0 as Float)), s, false);
+            (df * dmean, df * dstd)
+        };
+
+        self.mean
+            .push_binary(&self.std, result, reverse, vec![standardized, deviation])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Normal;
+    use crate as mu;
+    use crate::tensor::{traits::Tensed, Float};
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn normal_log_prob() {
+        let mean = mu::custom::<1, 1, 1, 2>(&[0.0, 1.0]);
+        let std = mu::custom::<1, 1, 1, 2>(&[1.0, 2.0]);
+        let z = Normal::new(&mean, &std).log_prob(&mu::custom::<1, 1, 1, 2>(&[0.0, 3.0]).freeze());
+
+        let log_norm = 0.5 * (2.0 * std::f64::consts::PI).ln() as Float;
+        let expected = [-log_norm, -0.5 - (2.0 as Float).ln() - log_norm];
+        assert!(equal_data(
+            z.data(),
+            Array::new(&expected, dim4!(1, 2, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            mean.grad().data(),
+            Array::new(&[0.0, 0.5], dim4!(1, 2, 1, 1))
+        ));
+        assert!(equal_data(
+            std.grad().data(),
+            Array::new(&[-1.0, 0.0], dim4!(1, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn normal_rsample() {
+        let mean = mu::fill::<1, 1, 100, 100>(0.0);
+        let std = mu::fill::<1, 1, 100, 100>(2.0);
+        let z = Normal::new(&mean, &std).rsample();
+
+        z.backward();
+        assert!(equal_data(
+            mean.grad().data(),
+            arrayfire::constant!(1.0; 100, 100, 1, 1)
+        ));
+        // The gradients of the deviations are the noise the values were sampled with
+        let noise = z.data() / 2.0 as Float;
+        assert!(equal_data(std.grad().data(), noise));
+    }
+}
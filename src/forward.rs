@@ -0,0 +1,111 @@
+//! Forward mode differentiation. Instead of propagating gradients backwards from a result,
+//! tangents are propagated forward from an input, which computes the product of the jacobian
+//! of a function with a vector in a single pass. This is cheaper than reverse mode when the
+//! function has more outputs than inputs.
+//!
+//! Tangents are propagated through the same computation graph built for reverse mode, using
+//! the forward mode derivatives each operation declares along with its reverse ones.
+
+use crate::graph::{node::Node, shared::Shared};
+use crate::tensor::{
+    constant::Constant,
+    traits::{Data, Tensed},
+    variable::Variable,
+    Tensor,
+};
+use std::collections::HashMap;
+
+/// Evaluates `f` at `x` and returns its result along with the jacobian-vector product of `f`
+/// at `x` with the tangent `v`, i.e. the derivative of `f` at `x` in the direction of `v`.
+///
+/// The input is given to `f` as a new variable, so its graph and gradients are not modified.
+/// Any other variable used by `f` is treated as a constant
+///
+/// # Panics
+///
+/// Panics if `f` performs an operation depending on `x` that does not support forward mode
+#[inline]
+pub fn jvp<
+    const XB: u64,
+    const XC: u64,
+    const XH: u64,
+    const XW: u64,
+    const YB: u64,
+    const YC: u64,
+    const YH: u64,
+    const YW: u64,
+    X: Data,
+    V: Data,
+    F,
+>(
+    f: F,
+    x: &Tensor<XB, XC, XH, XW, X>,
+    v: &Tensor<XB, XC, XH, XW, V>,
+) -> (
+    Tensor<YB, YC, YH, YW, Constant>,
+    Tensor<YB, YC, YH, YW, Constant>,
+)
+where
+    F: FnOnce(&Tensor<XB, XC, XH, XW, Variable>) -> Tensor<YB, YC, YH, YW, Variable>,
+{
+    let input: Tensor<XB, XC, XH, XW, Variable> = Variable::from(x.data()).into();
+    let output = f(&input);
+
+    let mut tangents = HashMap::new();
+    tangents.insert(Shared::as_ptr(&input.inner().node()), v.data());
+    for node in output.inner().tape().nodes() {
+        let tangent = node.tangent(|n: &Node| tangents.get(&std::ptr::from_ref(n)).cloned());
+        if let Some(tangent) = tangent {
+            tangents.insert(Shared::as_ptr(node), tangent);
+        }
+    }
+
+    let tangent = tangents
+        .remove(&Shared::as_ptr(&output.inner().node()))
+        .unwrap_or_else(|| arrayfire::constant!(0.0; YH, YW, YC, YB));
+    (output.freeze(), Constant::new(tangent).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::jvp;
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn jvp_matches_reverse_mode() {
+        let x = mu::custom::<1, 1, 1, 2>(&[0.5, 2.0]);
+        let w = mu::custom::<1, 1, 2, 2>(&[1.0, 3.0, 2.0, 4.0]).freeze();
+        let v = mu::custom::<1, 1, 1, 2>(&[1.0, 0.0]).freeze();
+
+        let (y, dy) = jvp(|x| mu::mul(&mu::sin(&mu::mm(x, &w)), x), &x, &v);
+        assert!(equal_data(
+            y.data(),
+            mu::mul(&mu::sin(&mu::mm(&x, &w)), &x).data()
+        ));
+
+        // The first column of the jacobian, computed in reverse mode one output at a time
+        let mut column = Vec::new();
+        for seed in [[1.0, 0.0], [0.0, 1.0]] {
+            let x = mu::custom::<1, 1, 1, 2>(&[0.5, 2.0]);
+            let y = mu::mul(&mu::sin(&mu::mm(&x, &w)), &x);
+            let z = mu::mm(&y, &mu::custom::<1, 1, 2, 1>(&seed).freeze());
+            z.backward();
+            column.push(x.grad().to_vec()[0]);
+        }
+        assert!(equal_data(
+            dy.data(),
+            Array::new(&column, dim4!(1, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn jvp_unrelated_output() {
+        let x = mu::fill::<1, 1, 2, 2>(1.0);
+        let c = mu::fill::<1, 1, 2, 2>(3.0);
+        let (_, dy) = jvp(|_| mu::sin(&c), &x, &x);
+        assert!(equal_data(dy.data(), arrayfire::constant!(0.0; 2,2,1,1)));
+    }
+}
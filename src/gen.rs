@@ -1,10 +1,12 @@
-use crate::tensor::{variable::Variable, Tensor};
+use crate::tensor::{variable::Variable, Batch, Float, Matrix, Scalar, Tensor, Vector};
+use arrayfire::{RandomEngine, RandomEngineType};
+use std::{error::Error, fmt};
 
 /// Creates a variable tensor filled with the given value
 #[must_use]
 #[inline]
 pub fn fill<const B: u64, const C: u64, const H: u64, const W: u64>(
-    v: f32,
+    v: Float,
 ) -> Tensor<B, C, H, W, Variable> {
     Variable::from(arrayfire::constant!(v; H,W,C,B)).into()
 }
@@ -13,9 +15,9 @@ pub fn fill<const B: u64, const C: u64, const H: u64, const W: u64>(
 #[must_use]
 #[inline]
 pub fn eye<const B: u64, const C: u64, const H: u64, const W: u64>(
-    v: f32,
+    v: Float,
 ) -> Tensor<B, C, H, W, Variable> {
-    Variable::from(v * arrayfire::identity::<f32>(arrayfire::dim4!(H, W, C, B))).into()
+    Variable::from(v * arrayfire::identity::<Float>(arrayfire::dim4!(H, W, C, B))).into()
 }
 
 /// Creates a variable tensor with random values taken from a uniform distribution between [0,1]
@@ -23,7 +25,7 @@ pub fn eye<const B: u64, const C: u64, const H: u64, const W: u64>(
 #[inline]
 pub fn randu<const B: u64, const C: u64, const H: u64, const W: u64>(
 ) -> Tensor<B, C, H, W, Variable> {
-    Variable::from(arrayfire::randu!(H, W, C, B)).into()
+    Variable::from(arrayfire::randu!(Float; H, W, C, B)).into()
 }
 
 /// Creates a variable tensor with random values taken from a normal distribution centered at 0
@@ -31,24 +33,273 @@ pub fn randu<const B: u64, const C: u64, const H: u64, const W: u64>(
 #[inline]
 pub fn randn<const B: u64, const C: u64, const H: u64, const W: u64>(
 ) -> Tensor<B, C, H, W, Variable> {
-    Variable::from(arrayfire::randn!(H, W, C, B)).into()
+    Variable::from(arrayfire::randn!(Float; H, W, C, B)).into()
 }
 
-/// Creates a variable tensor from the given array of values
+/// Creates a variable tensor with random values taken from a uniform distribution between
+/// [lo,hi]
+#[must_use]
+#[inline]
+pub fn randu_range<const B: u64, const C: u64, const H: u64, const W: u64>(
+    lo: Float,
+    hi: Float,
+) -> Tensor<B, C, H, W, Variable> {
+    Variable::from(arrayfire::randu!(Float; H, W, C, B) * (hi - lo) + lo).into()
+}
+
+/// Creates a variable tensor with random values taken from a normal distribution with the
+/// given mean and standard deviation
+#[must_use]
+#[inline]
+pub fn randn_with<const B: u64, const C: u64, const H: u64, const W: u64>(
+    mean: Float,
+    std: Float,
+) -> Tensor<B, C, H, W, Variable> {
+    Variable::from(arrayfire::randn!(Float; H, W, C, B) * std + mean).into()
+}
+
+/// Creates a variable tensor with random integer values taken uniformly from [low,high), i.e.
+/// synthetic class labels. The values are stored as floating point numbers
+///
+/// # Panics
+///
+/// Panics if `low` is not below `high`
+#[must_use]
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn randint<const B: u64, const C: u64, const H: u64, const W: u64>(
+    low: i64,
+    high: i64,
+) -> Tensor<B, C, H, W, Variable> {
+    assert!(low < high, "empty range of integers [{low},{high})");
+    let scaled = arrayfire::randu!(Float; H, W, C, B) * (high - low) as Float;
+    // Clamp in case rounding takes a value right below one up to it
+    let values = arrayfire::clamp(
+        &arrayfire::floor(&scaled),
+        &(0.0 as Float),
+        &((high - low - 1) as Float),
+        false,
+    );
+    Variable::from(values + low as Float).into()
+}
+
+/// Creates a variable tensor with values that are one with the given probability and zero
+/// otherwise, i.e. a random mask
+#[must_use]
+#[inline]
+pub fn bernoulli<const B: u64, const C: u64, const H: u64, const W: u64>(
+    p: Float,
+) -> Tensor<B, C, H, W, Variable> {
+    let values = arrayfire::lt(&arrayfire::randu!(Float; H, W, C, B), &p, false);
+    Variable::from(values.cast::<Float>()).into()
+}
+
+/// Returns the number of inputs and outputs every value of a weights tensor connects, see
+/// `glorot`
+const fn fans<const B: u64, const C: u64, const H: u64, const W: u64>() -> (u64, u64) {
+    if B == 1 && C == 1 {
+        (H, W)
+    } else {
+        (C * H * W, B * H * W)
+    }
+}
+
+/// Creates a variable tensor of weights initialized with the Glorot (Xavier) scheme.
+///
+/// The weights are taken from a uniform distribution between `±sqrt(6 / (fan_in + fan_out))`.
+/// Matrices are laid out as the weights of a `Linear` layer, with a row per input and a column
+/// per output. Otherwise the tensor is laid out as the kernels of a `Conv2D` layer, with a
+/// batch entry per output channel and a channel per input channel
+#[must_use]
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn glorot<const B: u64, const C: u64, const H: u64, const W: u64>(
+) -> Tensor<B, C, H, W, Variable> {
+    let (fan_in, fan_out) = fans::<B, C, H, W>();
+    let limit = (6.0 / (fan_in + fan_out) as Float).sqrt();
+    randu_range(-limit, limit)
+}
+
+/// Creates a variable tensor of weights initialized with the Kaiming (He) scheme.
+///
+/// The weights are taken from a normal distribution with mean 0 and standard deviation
+/// `sqrt(2 / fan_in)`, the number of inputs following from the shape as in `glorot`
+#[must_use]
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn kaiming<const B: u64, const C: u64, const H: u64, const W: u64>(
+) -> Tensor<B, C, H, W, Variable> {
+    let (fan_in, _) = fans::<B, C, H, W>();
+    randn_with(0.0, (2.0 / fan_in as Float).sqrt())
+}
+
+/// Seeds the random number generator used by `randu`, `randn`, dropout layers and the
+/// shuffling of data loaders, so that experiments can be reproduced
+#[inline]
+pub fn seed(seed: u64) {
+    arrayfire::set_seed(seed);
+}
+
+/// Returns a random number generator seeded with the given value, independent of the global one
+pub fn engine(seed: u64) -> RandomEngine {
+    RandomEngine::new(RandomEngineType::PHILOX_4X32_10, Some(seed))
+}
+
+/// Same as `randu`, with the values taken from a random number generator seeded with the given
+/// value instead of the global one, see `seed`
+#[must_use]
+#[inline]
+pub fn randu_seeded<const B: u64, const C: u64, const H: u64, const W: u64>(
+    seed: u64,
+) -> Tensor<B, C, H, W, Variable> {
+    Variable::from(arrayfire::random_uniform::<Float>(
+        arrayfire::dim4!(H, W, C, B),
+        &engine(seed),
+    ))
+    .into()
+}
+
+/// Same as `randn`, with the values taken from a random number generator seeded with the given
+/// value instead of the global one, see `seed`
+#[must_use]
+#[inline]
+pub fn randn_seeded<const B: u64, const C: u64, const H: u64, const W: u64>(
+    seed: u64,
+) -> Tensor<B, C, H, W, Variable> {
+    Variable::from(arrayfire::random_normal::<Float>(
+        arrayfire::dim4!(H, W, C, B),
+        &engine(seed),
+    ))
+    .into()
+}
+
+/// Creates a variable tensor with every value computed by the given function from its batch,
+/// channel, row and column indices. The values are computed on the host and copied to the
+/// device at once
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn from_fn<const B: u64, const C: u64, const H: u64, const W: u64>(
+    f: impl Fn(u64, u64, u64, u64) -> Float,
+) -> Tensor<B, C, H, W, Variable> {
+    let mut values = Vec::with_capacity((B * C * H * W) as usize);
+    for b in 0..B {
+        for c in 0..C {
+            for w in 0..W {
+                values.extend((0..H).map(|h| f(b, c, h, w)));
+            }
+        }
+    }
+    custom(&values)
+}
+
+/// Creates a variable tensor from the given array of values, laid out in column-major order
+///
+/// # Panics
+///
+/// Panics if the number of values does not match the tensor shape, see `try_custom`
 #[must_use]
 #[inline]
 pub fn custom<const B: u64, const C: u64, const H: u64, const W: u64>(
-    values: &[f32],
+    values: &[Float],
 ) -> Tensor<B, C, H, W, Variable> {
-    Variable::from(arrayfire::Array::new(values, arrayfire::dim4!(H, W, C, B))).into()
+    try_custom(values).unwrap_or_else(|e| panic!("{e}"))
 }
 
+/// Creates a variable tensor from the given array of values, laid out in column-major order
+///
+/// # Errors
+///
+/// Returns an error if the number of values does not match the tensor shape
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn try_custom<const B: u64, const C: u64, const H: u64, const W: u64>(
+    values: &[Float],
+) -> Result<Tensor<B, C, H, W, Variable>, ShapeError> {
+    let expected = (B * C * H * W) as usize;
+    if values.len() != expected {
+        return Err(ShapeError {
+            expected,
+            found: values.len(),
+        });
+    }
+    Ok(Variable::from(arrayfire::Array::new(values, arrayfire::dim4!(H, W, C, B))).into())
+}
+
+/// Creates a variable scalar with the given value
+#[must_use]
+#[inline]
+pub fn scalar(v: Float) -> Scalar {
+    fill(v)
+}
+
+/// Creates a variable vector from the given values, see `custom`
+///
+/// # Panics
+///
+/// Panics if the number of values is not `W`
+#[must_use]
+#[inline]
+pub fn vector<const W: u64>(values: &[Float]) -> Vector<W> {
+    custom(values)
+}
+
+/// Creates a variable matrix from the given values, laid out in column-major order, see `custom`
+///
+/// # Panics
+///
+/// Panics if the number of values is not `H * W`
+#[must_use]
+#[inline]
+pub fn matrix<const H: u64, const W: u64>(values: &[Float]) -> Matrix<H, W> {
+    custom(values)
+}
+
+/// Creates a variable batch of vectors from the given values, one vector after another, see
+/// `custom`
+///
+/// # Panics
+///
+/// Panics if the number of values is not `B * W`
+#[must_use]
+#[inline]
+pub fn batch<const B: u64, const W: u64>(values: &[Float]) -> Batch<B, W> {
+    custom(values)
+}
+
+/// The number of values given to build a tensor does not match its shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeError {
+    /// Number of values of the tensor shape
+    pub expected: usize,
+    /// Number of values given
+    pub found: usize,
+}
+
+impl fmt::Display for ShapeError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} values for the tensor shape, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl Error for ShapeError {}
+
 #[cfg(test)]
 mod tests {
-    use super::{custom, eye, fill, randn, randu};
+    use super::{
+        batch, bernoulli, custom, eye, fans, fill, from_fn, glorot, kaiming, matrix, randint,
+        randn, randn_seeded, randn_with, randu, randu_range, randu_seeded, scalar, seed,
+        try_custom, vector, ShapeError,
+    };
     use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
     use crate::tests::equal_data;
-    use arrayfire::{all_true_all, constant, dim4, identity, le};
+    use arrayfire::{all_true_all, constant, dim4, identity, le, Array};
 
     #[test]
     fn test_fill() {
@@ -61,7 +312,7 @@ mod tests {
         let x = eye::<1, 2, 3, 4>(2.0);
         assert!(equal_data(
             x.data(),
-            identity::<f32>(dim4!(3, 4, 2, 1)) * 2.0f32
+            identity::<Float>(dim4!(3, 4, 2, 1)) * (2.0 as Float)
         ));
     }
 
@@ -77,9 +328,131 @@ mod tests {
         assert!(all_true_all(&le(&x.data(), &constant!(5.0; 3,4,2,1), false)).0)
     }
 
+    #[test]
+    fn test_seed() {
+        seed(42);
+        let x = randn::<1, 2, 3, 4>();
+        seed(42);
+        assert!(equal_data(x.data(), randn::<1, 2, 3, 4>().data()));
+
+        assert!(equal_data(
+            randu_seeded::<1, 2, 3, 4>(7).data(),
+            randu_seeded::<1, 2, 3, 4>(7).data()
+        ));
+        assert!(equal_data(
+            randn_seeded::<1, 2, 3, 4>(7).data(),
+            randn_seeded::<1, 2, 3, 4>(7).data()
+        ));
+    }
+
     #[test]
     fn test_custom() {
         let x = custom::<1, 1, 1, 1>(&[1.0]);
         assert!(equal_data(x.data(), constant!(1.0;1,1,1,1)));
+        assert!((x.to_scalar() - 1.0).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn test_try_custom() {
+        let x = try_custom::<1, 1, 1, 2>(&[1.0, 2.0]).unwrap();
+        assert_eq!(x.to_vec(), vec![1.0, 2.0]);
+        assert_eq!(
+            try_custom::<1, 1, 1, 2>(&[1.0]).err(),
+            Some(ShapeError {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 values")]
+    fn test_custom_mismatch() {
+        let _ = custom::<1, 1, 2, 1>(&[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_shape_aliases() {
+        assert!((scalar(2.0).to_scalar() - 2.0).abs() < Float::EPSILON);
+        assert_eq!(vector::<3>(&[1.0, 2.0, 3.0]).to_vec(), vec![1.0, 2.0, 3.0]);
+        assert!(equal_data(
+            matrix::<2, 2>(&[1.0, 2.0, 3.0, 4.0]).data(),
+            custom::<1, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0]).data()
+        ));
+        assert!(equal_data(
+            batch::<2, 2>(&[1.0, 2.0, 3.0, 4.0]).data(),
+            arrayfire::Array::new(&[1.0, 2.0, 3.0, 4.0], dim4!(1, 2, 1, 2))
+        ));
+    }
+
+    /// Returns the mean of the given values
+    #[allow(clippy::cast_precision_loss)]
+    fn mean(x: &Array<Float>) -> Float {
+        arrayfire::sum_all(x).0 / x.elements() as Float
+    }
+
+    /// Returns the standard deviation of the given values
+    fn stdev(x: &Array<Float>) -> Float {
+        let centered = x - mean(x);
+        mean(&(&centered * &centered)).sqrt()
+    }
+
+    #[test]
+    fn test_distributions() {
+        let x = randu_range::<1, 1, 100, 100>(-2.0, 3.0).data();
+        assert!(arrayfire::min_all(&x).0 >= -2.0 && arrayfire::max_all(&x).0 <= 3.0);
+
+        let x = randn_with::<1, 1, 100, 100>(5.0, 0.5).data();
+        assert!((mean(&x) - 5.0).abs() < 0.05);
+        assert!((stdev(&x) - 0.5).abs() < 0.05);
+
+        let x = bernoulli::<1, 1, 100, 100>(0.3).data();
+        assert!(
+            all_true_all(&arrayfire::or(
+                &arrayfire::eq(&x, &(0.0 as Float), false),
+                &arrayfire::eq(&x, &(1.0 as Float), false),
+                false
+            ))
+            .0
+        );
+        assert!((mean(&x) - 0.3).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_initializers() {
+        assert_eq!(fans::<1, 1, 3, 5>(), (3, 5));
+        assert_eq!(fans::<4, 2, 3, 3>(), (18, 36));
+
+        let limit = (6.0 as Float / 8.0).sqrt();
+        let x = glorot::<1, 1, 3, 5>().data();
+        assert!(arrayfire::max_all(&arrayfire::abs(&x)).0 <= limit);
+
+        let x = kaiming::<64, 8, 5, 5>().data();
+        assert!((stdev(&x) - 0.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_from_fn() {
+        #[allow(clippy::cast_precision_loss)]
+        let x = from_fn::<2, 1, 2, 3>(|b, _, h, w| (b * 100 + h * 10 + w) as Float);
+        for (b, h, w) in [(0, 0, 0), (0, 1, 2), (1, 0, 1), (1, 1, 0)] {
+            #[allow(clippy::cast_precision_loss)]
+            let expected = (b * 100 + h * 10 + w) as Float;
+            assert!((x.at(b, 0, h, w) - expected).abs() < Float::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_randint() {
+        let x = randint::<1, 1, 100, 100>(-3, 2).data();
+        assert!(equal_data(arrayfire::floor(&x), x.clone()));
+        assert!((arrayfire::min_all(&x).0 + 3.0).abs() < Float::EPSILON);
+        assert!((arrayfire::max_all(&x).0 - 1.0).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range of integers [2,2)")]
+    fn test_randint_empty() {
+        let _ = randint::<1, 1, 1, 1>(2, 2);
     }
 }
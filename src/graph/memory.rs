@@ -0,0 +1,143 @@
+//! Accounting of the memory held by the computation graph. Every node keeps the counters of
+//! this module up to date as its values, gradients and operation arguments are allocated,
+//! replaced and dropped, so that `memory_stats` can report them at no traversal cost.
+
+use crate::graph::node::Node;
+use crate::tensor::Float;
+use arrayfire::Array;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static DATA: AtomicUsize = AtomicUsize::new(0);
+static GRADS: AtomicUsize = AtomicUsize::new(0);
+static ARGS: AtomicUsize = AtomicUsize::new(0);
+
+/// What the memory of an array held by a node is used for
+#[derive(Clone, Copy)]
+pub enum Usage {
+    /// The values of a tensor
+    Data,
+    /// The gradients of a tensor
+    Grad,
+    /// An argument saved by an operation for the backward pass
+    Args,
+}
+
+impl Usage {
+    /// Returns the counter of bytes in use
+    const fn counter(self) -> &'static AtomicUsize {
+        match self {
+            Self::Data => &DATA,
+            Self::Grad => &GRADS,
+            Self::Args => &ARGS,
+        }
+    }
+
+    /// Records that the given array is now in use
+    pub(crate) fn allocated(self, array: &Array<Float>) {
+        self.counter().fetch_add(bytes(array), Ordering::Relaxed);
+    }
+
+    /// Records that the given array is no longer in use
+    pub(crate) fn freed(self, array: &Array<Float>) {
+        self.counter().fetch_sub(bytes(array), Ordering::Relaxed);
+    }
+}
+
+/// Returns the size in bytes of the values of an array
+pub fn bytes(array: &Array<Float>) -> usize {
+    array.elements() * std::mem::size_of::<Float>()
+}
+
+/// Memory allocated by arrayfire in a device
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeviceMemory {
+    /// Device identifier, as used by `arrayfire::set_device`
+    pub device: i32,
+    /// Bytes allocated by arrayfire in the device, including freed buffers kept for reuse
+    pub allocated: usize,
+    /// Number of buffers allocated by arrayfire in the device
+    pub buffers: usize,
+    /// Bytes of the allocated buffers that are in use
+    pub locked: usize,
+    /// Number of the allocated buffers that are in use
+    pub locked_buffers: usize,
+}
+
+/// Memory held by the computation graph, see `memory_stats`
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Number of nodes alive, i.e. of variable tensors and of the operations they depend on
+    pub nodes: usize,
+    /// Bytes held by the values of the nodes
+    pub data: usize,
+    /// Bytes held by the gradients of the nodes
+    pub grads: usize,
+    /// Bytes held by the arguments the operations keep for the backward pass
+    pub args: usize,
+    /// Bytes held by the nodes themselves, without their arrays
+    pub metadata: usize,
+    /// Memory allocated by arrayfire in every device
+    pub devices: Vec<DeviceMemory>,
+}
+
+/// Returns the memory held by all the computation graphs alive and allocated in every device.
+///
+/// Arrays shared between nodes, i.e. the values of a tensor kept as the argument of an
+/// operation, are counted once per node, so the bytes held by the graph are an upper bound
+/// of the device memory it uses. The devices are visited in turn to query their memory, after
+/// which the active device is restored
+#[must_use]
+#[inline]
+#[allow(clippy::module_name_repetitions)]
+pub fn memory_stats() -> MemoryStats {
+    let active = arrayfire::get_device();
+    let devices = (0..arrayfire::device_count())
+        .map(|device| {
+            arrayfire::set_device(device);
+            let (allocated, buffers, locked, locked_buffers) = arrayfire::device_mem_info();
+            DeviceMemory {
+                device,
+                allocated,
+                buffers,
+                locked,
+                locked_buffers,
+            }
+        })
+        .collect();
+    arrayfire::set_device(active);
+
+    let nodes = Node::count();
+    MemoryStats {
+        nodes,
+        data: DATA.load(Ordering::Relaxed),
+        grads: GRADS.load(Ordering::Relaxed),
+        args: ARGS.load(Ordering::Relaxed),
+        metadata: nodes * std::mem::size_of::<Node>(),
+        devices,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::memory_stats;
+    use crate as mu;
+    use crate::tensor::Float;
+
+    #[test]
+    fn graph_memory() {
+        let bytes = 64 * std::mem::size_of::<Float>();
+        let x = mu::fill::<1, 1, 8, 8>(2.0);
+        let z = mu::sin(&x);
+        assert_eq!(z.node_count(), 2);
+
+        let stats = memory_stats();
+        assert!(stats.nodes >= 2);
+        assert!(stats.data >= 2 * bytes);
+        assert!(stats.args >= bytes);
+
+        z.backward();
+        assert!(memory_stats().grads >= 2 * bytes);
+    }
+}
@@ -3,16 +3,24 @@
 //! point, so that it can be traversed backwards to compute the gradients
 //! of the `Variable` operands.
 //!
-//! The `Tape` struct is basically a set of `Node`s, and it is owned by `Variable` tensors.
-//! It is the history of operations performed up until and including the existence
-//! of its `Variable` owner. Everytime a new `Variable` is created, the `Tape` from the
-//! parameters that originated it, is cloned and owned, the new `Node` associated to the
-//! operation is then pushed into the `Tape`.
+//! A `Node` contains the data and the gradients of the `Variable` that
+//! owns it, as well as a definition of the `Operation` that created that `Variable`,
+//! which holds references to the nodes of its `Variable` parameters. The nodes therefore
+//! form the computation graph themselves, and creating a new `Variable` only allocates its
+//! own `Node`. Ancestors are kept alive for as long as any of their descendants is.
 //!
-//! A `Node` in the tape contains the data and the gradients of the `Variable` that
-//! owns it, as well as a definition of the `Operation` that created that `Variable`.
-//! Following the parameters of the operations backward is what allows to traverse
-//! the the graph in reverse mode to perform the auto-differentiation.
+//! The `Tape` struct is the list of a `Node` and all its ancestors in topological order,
+//! collected on demand. Following the parameters of the operations backward is what allows
+//! to traverse the graph in reverse mode to perform the auto-differentiation.
+//!
+//! Every node accounts for the memory held by its arrays in the `memory` module, and takes
+//! the constants filling its gradients from the `pool` module.
+//!
+//! Nodes are shared through the primitives of the `shared` module, which are only thread
+//! safe with the `sync` feature.
 
+pub mod memory;
 pub mod node;
+pub mod pool;
+pub mod shared;
 pub mod tape;
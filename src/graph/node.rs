@@ -1,9 +1,11 @@
-use arrayfire::{constant, Array};
-use std::{
-    cell::{Ref, RefCell, RefMut},
-    rc::Rc,
-    sync::atomic::{AtomicUsize, Ordering},
+use crate::graph::{
+    memory::{self, Usage},
+    pool,
+    shared::{Lock, ReadGuard, Shared, Threaded},
 };
+use crate::{profiler, tensor::Float};
+use arrayfire::Array;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -19,52 +21,87 @@ enum Origin {
     Unary(UnaryOp),
     /// The node is the result of a binary operation, like `x + y`
     Binary(BinaryOp),
+    /// The node was the result of an operation whose graph has been released
+    Released,
 }
 
+/// Inspects the gradients of a `Node` during the backward pass, optionally returning
+/// new gradients to replace them
+#[cfg(not(feature = "sync"))]
+pub type Hook = Box<dyn Fn(&Array<Float>) -> Option<Array<Float>>>;
+/// Inspects the gradients of a `Node` during the backward pass, optionally returning
+/// new gradients to replace them
+#[cfg(feature = "sync")]
+pub type Hook = Box<dyn Fn(&Array<Float>) -> Option<Array<Float>> + Send + Sync>;
+
+/// Transforms every partial derivative accumulated to the gradients of a `Node`
+#[cfg(not(feature = "sync"))]
+pub type GradTransform = Box<dyn Fn(&Array<Float>) -> Array<Float>>;
+/// Transforms every partial derivative accumulated to the gradients of a `Node`
+#[cfg(feature = "sync")]
+pub type GradTransform = Box<dyn Fn(&Array<Float>) -> Array<Float> + Send + Sync>;
+
 /// A `Node` holds a `Variable` tensor data (values and gradients) as
 /// well as information about its `Origin`
 pub struct Node {
     id: NodeId,
-    data: RefCell<Array<f32>>,
-    grad: RefCell<Array<f32>>,
-    origin: Origin,
+    name: Option<&'static str>,
+    label: Lock<Option<&'static str>>,
+    data: Lock<Array<Float>>,
+    grad: Lock<Option<Array<Float>>>,
+    origin: Lock<Origin>,
+    hooks: Lock<Vec<Hook>>,
+    transforms: Lock<Vec<GradTransform>>,
 }
 
 impl Node {
     /// Creates a new `Node` with the given data and `Origin`. Gradients
-    /// are set to zero by default. Each new `Node` has a unique ID fetched
+    /// are not allocated until written to. Each new `Node` has a unique ID fetched
     /// from a global static incremental counter. Unique IDs are necessary
     /// to be able to tell if two nodes (tensors) are the same when used in
-    /// different operations.
-    fn new(data: Array<f32>, origin: Origin) -> Self {
-        let dims = data.dims();
-
+    /// different operations. Nodes resulting from an operation are named after it
+    fn new(data: Array<Float>, origin: Origin) -> Self {
+        Usage::Data.allocated(&data);
+        origin
+            .args()
+            .iter()
+            .for_each(|arg| Usage::Args.allocated(arg));
+        let name = if matches!(origin, Origin::Declaration) {
+            None
+        } else {
+            profiler::current()
+        };
         Self {
-            data: RefCell::new(data),
-            grad: RefCell::new(constant(0.0, dims)),
-            origin,
+            name,
+            label: Lock::new(None),
+            data: Lock::new(data),
+            grad: Lock::new(None),
+            origin: Lock::new(origin),
+            hooks: Lock::new(Vec::new()),
+            transforms: Lock::new(Vec::new()),
             id: COUNTER.fetch_add(1, Ordering::Relaxed),
         }
     }
 
     /// Creates a new `Node` with a declaration `Operation` as origin
-    pub(crate) fn declaration(data: Array<f32>) -> Self {
+    pub(crate) fn declaration(data: Array<Float>) -> Self {
         Self::new(data, Origin::Declaration)
     }
 
     /// Creates a new `Node` with a unary `Operation` as origin
     pub(crate) fn unary(
-        data: Array<f32>,
-        ancestor: Rc<Self>,
+        data: Array<Float>,
+        ancestor: Shared<Self>,
         reverse: UnaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self {
         Self::new(
             data,
             Origin::Unary(UnaryOp {
                 ancestor,
                 reverse,
-                args: args.to_vec(),
+                tangent: None,
+                args,
             }),
         )
     }
@@ -72,17 +109,18 @@ impl Node {
     /// Creates a new `Node` with a binary `Operation` as origin and both operation
     /// parameters are `Variable`s
     pub(crate) fn binary_varvar(
-        data: Array<f32>,
-        ancestors: (Rc<Self>, Rc<Self>),
+        data: Array<Float>,
+        ancestors: (Shared<Self>, Shared<Self>),
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self {
         Self::new(
             data,
             Origin::Binary(BinaryOp {
                 ancestors: BinaryParams::VarVar(ancestors.0, ancestors.1),
                 reverse,
-                args: args.to_vec(),
+                tangent: None,
+                args,
             }),
         )
     }
@@ -90,17 +128,18 @@ impl Node {
     /// Creates a new `Node` with a binary `Operation` as origin and only the
     /// first operation parameter is a `Variable`
     pub(crate) fn binary_varconst(
-        data: Array<f32>,
-        ancestor: Rc<Self>,
+        data: Array<Float>,
+        ancestor: Shared<Self>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self {
         Self::new(
             data,
             Origin::Binary(BinaryOp {
                 ancestors: BinaryParams::VarConst(ancestor),
                 reverse,
-                args: args.to_vec(),
+                tangent: None,
+                args,
             }),
         )
     }
@@ -108,65 +147,292 @@ impl Node {
     /// Creates a new `Node` with a binary `Operation` as origin and only the
     /// second operation parameter is a `Variable`
     pub(crate) fn binary_constvar(
-        data: Array<f32>,
-        ancestor: Rc<Self>,
+        data: Array<Float>,
+        ancestor: Shared<Self>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self {
         Self::new(
             data,
             Origin::Binary(BinaryOp {
                 ancestors: BinaryParams::ConstVar(ancestor),
                 reverse,
-                args: args.to_vec(),
+                tangent: None,
+                args,
             }),
         )
     }
 
     /// Returns the tensor data
-    pub(crate) fn data(&self) -> Ref<Array<f32>> {
+    pub(crate) fn data(&self) -> ReadGuard<Array<Float>> {
         self.data.borrow()
     }
 
-    /// Returns a mutable reference to the tensor data
-    pub(crate) fn data_mut(&self) -> RefMut<Array<f32>> {
-        self.data.borrow_mut()
+    /// Replaces the tensor data with the given values
+    pub(crate) fn set_data(&self, data: Array<Float>) {
+        Usage::Data.allocated(&data);
+        let previous = std::mem::replace(&mut *self.data.borrow_mut(), data);
+        Usage::Data.freed(&previous);
+    }
+
+    /// Returns the tensor gradients, which are zero if they were never written
+    pub(crate) fn grad(&self) -> Array<Float> {
+        self.grad
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| pool::zeros(self.data().dims()))
+    }
+
+    /// Evaluates the pending operations of the tensor data and gradients
+    pub(crate) fn eval(&self) {
+        self.data().eval();
+        if let Some(ref grad) = *self.grad.borrow() {
+            grad.eval();
+        }
     }
 
-    /// Returns the tensor gradients
-    pub(crate) fn grad(&self) -> Ref<Array<f32>> {
-        self.grad.borrow()
+    /// Adds the given partial derivatives to the tensor gradients, broadcasting them along
+    /// the batch dimension if needed. The gradients are allocated on the first write
+    pub(crate) fn accumulate_grad(&self, partial: Array<Float>) {
+        let partial = self
+            .transforms
+            .borrow()
+            .iter()
+            .fold(partial, |partial, transform| transform(&partial));
+
+        let grad = match self.grad.borrow().as_ref() {
+            Some(current) => arrayfire::add(current, &partial, true),
+            None if partial.dims() == self.data().dims() => partial,
+            None => arrayfire::add(&pool::zeros(self.data().dims()), &partial, true),
+        };
+        self.replace_grad(Some(grad));
     }
 
-    /// Returns a mutable reference to the tensor gradients
-    pub(crate) fn grad_mut(&self) -> RefMut<Array<f32>> {
-        self.grad.borrow_mut()
+    /// Replaces the tensor gradients, keeping track of the memory they hold
+    fn replace_grad(&self, grad: Option<Array<Float>>) {
+        if let Some(ref new) = grad {
+            Usage::Grad.allocated(new);
+        }
+        let previous = std::mem::replace(&mut *self.grad.borrow_mut(), grad);
+        if let Some(previous) = previous {
+            Usage::Grad.freed(&previous);
+        }
     }
 
     /// Computes the gradients of this node ancestors by following the
     /// computation graph backwards
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation that originated this node has been released
     pub(crate) fn reverse(&self) {
-        match self.origin {
+        // Nodes whose gradients were never written do not contribute to their ancestors
+        let Some(mut grad) = self.grad.borrow().clone() else {
+            return;
+        };
+
+        for hook in self.hooks.borrow().iter() {
+            if let Some(replaced) = hook(&grad) {
+                grad = replaced;
+                self.replace_grad(Some(grad.clone()));
+            }
+        }
+
+        let name = self.name();
+        match *self.origin.borrow() {
             Origin::Unary(ref op) => {
-                op.reverse(&self.grad());
+                let _span = profiler::backward(name);
+                op.reverse(&grad);
             }
             Origin::Binary(ref op) => {
-                op.reverse(&self.grad());
+                let _span = profiler::backward(name);
+                op.reverse(&grad);
             }
             Origin::Declaration => {}
+            Origin::Released => panic!(
+                "the computation graph was released by a previous backward pass, \
+                 retain it to call backward more than once"
+            ),
+        }
+    }
+
+    /// Registers a function to transform every partial derivative before it is accumulated to
+    /// the gradients of this node during the backward pass, i.e. to add noise to or mask the
+    /// gradients of a parameter. Transforms are applied in the order they were registered
+    #[inline]
+    pub fn register_grad_transform<F>(&self, transform: F)
+    where
+        F: Fn(&Array<Float>) -> Array<Float> + Threaded + 'static,
+    {
+        self.transforms.borrow_mut().push(Box::new(transform));
+    }
+
+    /// Zeroes the partial derivatives of the values of this node where the given mask is 0
+    /// before they are accumulated, so that only the values where it is 1 are trained, i.e. to
+    /// fine-tune the last rows of an embedding table. The mask applies to every later backward
+    /// pass, and optimizers with weight decay still shrink the frozen values
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mask does not have the shape of the values of this node
+    #[inline]
+    pub fn mask_grad(&self, mask: Array<Float>) {
+        assert_eq!(
+            mask.dims(),
+            self.data().dims(),
+            "the mask does not have the shape of the values"
+        );
+        self.register_grad_transform(move |partial| arrayfire::mul(partial, &mask, true));
+    }
+
+    /// Sets the forward mode derivatives of the operation that originated this node.
+    /// They are ignored if they do not match the kind of operation
+    pub(crate) fn set_tangent(&self, tangent: Tangent) {
+        match (&mut *self.origin.borrow_mut(), tangent) {
+            (&mut Origin::Unary(ref mut op), Tangent::Unary(f)) => op.tangent = Some(f),
+            (&mut Origin::Binary(ref mut op), Tangent::Binary(fa, fb)) => {
+                op.tangent = Some((fa, fb));
+            }
+            _ => {}
         }
     }
 
+    /// Computes the tangent of this node from those of its ancestors, given by `tangent_of`.
+    /// Returns `None` if no ancestor has a tangent, meaning it is zero
+    ///
+    /// # Panics
+    ///
+    /// Panics if an ancestor has a tangent but the operation has no forward mode derivatives
+    pub(crate) fn tangent<F>(&self, tangent_of: F) -> Option<Array<Float>>
+    where
+        F: Fn(&Self) -> Option<Array<Float>>,
+    {
+        const UNSUPPORTED: &str = "forward mode is not supported by an operation of the graph";
+
+        match *self.origin.borrow() {
+            Origin::Unary(ref op) => {
+                let dx = tangent_of(&op.ancestor)?;
+                Some(op.tangent.expect(UNSUPPORTED)(&dx, &op.args))
+            }
+            Origin::Binary(ref op) => {
+                let (da, db) = match op.ancestors {
+                    BinaryParams::VarVar(ref a, ref b) => (tangent_of(a), tangent_of(b)),
+                    BinaryParams::VarConst(ref a) => (tangent_of(a), None),
+                    BinaryParams::ConstVar(ref b) => (None, tangent_of(b)),
+                };
+                if da.is_none() && db.is_none() {
+                    return None;
+                }
+
+                let (fa, fb) = op.tangent.expect(UNSUPPORTED);
+                let ta = da.map(|d| fa(&d, &op.args));
+                let tb = db.map(|d| fb(&d, &op.args));
+                match (ta, tb) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    (a, b) => a.or(b),
+                }
+            }
+            Origin::Declaration | Origin::Released => None,
+        }
+    }
+
+    /// Registers a hook to be called with the node gradients, once fully accumulated,
+    /// right before they are propagated to its ancestors
+    pub(crate) fn register_hook(&self, hook: Hook) {
+        self.hooks.borrow_mut().push(hook);
+    }
+
+    /// Drops the operation that originated this node, along with its arguments and the
+    /// references to its ancestors. Declarations are kept as they are
+    pub(crate) fn release(&self) {
+        if !self.is_declaration() {
+            let previous = std::mem::replace(&mut *self.origin.borrow_mut(), Origin::Released);
+            previous
+                .args()
+                .iter()
+                .for_each(|arg| Usage::Args.freed(arg));
+        }
+    }
+
+    /// Sets its gradients to the given values, i.e. to seed a backward pass
+    pub(crate) fn set_grad(&self, grad: Array<Float>) {
+        self.replace_grad(Some(grad));
+    }
+
     /// Sets all its gradient values to one
     pub(crate) fn ones_grad(&self) {
-        let dims = self.grad().dims();
-        *self.grad_mut() = constant(1.0, dims);
+        let dims = self.data().dims();
+        self.replace_grad(Some(pool::ones(dims)));
     }
 
-    /// Sets all its gradient values to zero
+    /// Sets all its gradient values to zero, freeing their buffer until written again
     pub(crate) fn zero_grad(&self) {
-        let dims = self.grad().dims();
-        *self.grad_mut() = constant(0.0, dims);
+        self.replace_grad(None);
+    }
+
+    /// Returns what identifies the operation that originated this node along with its
+    /// arguments, or `None` if it is not the result of an operation
+    pub(crate) fn signature(&self) -> Option<Signature> {
+        match *self.origin.borrow() {
+            Origin::Unary(ref op) => Some(Signature {
+                reverse: op.reverse as usize,
+                ancestors: [Some(Shared::as_ptr(&op.ancestor)), None],
+            }),
+            Origin::Binary(ref op) => Some(Signature {
+                reverse: op.reverse as usize,
+                ancestors: match op.ancestors {
+                    BinaryParams::VarVar(ref a, ref b) => {
+                        [Some(Shared::as_ptr(a)), Some(Shared::as_ptr(b))]
+                    }
+                    BinaryParams::VarConst(ref a) => [Some(Shared::as_ptr(a)), None],
+                    BinaryParams::ConstVar(ref b) => [None, Some(Shared::as_ptr(b))],
+                },
+            }),
+            Origin::Declaration | Origin::Released => None,
+        }
+    }
+
+    /// Returns `true` if the operations that originated both nodes keep the same arguments,
+    /// compared by shape and values
+    pub(crate) fn same_args(&self, other: &Self) -> bool {
+        let (origin, other) = (self.origin.borrow(), other.origin.borrow());
+        let (args, other) = (origin.args(), other.args());
+        args.len() == other.len()
+            && args.iter().zip(other).all(|(a, b)| {
+                a.dims() == b.dims() && arrayfire::all_true_all(&arrayfire::eq(a, b, false)).0
+            })
+    }
+
+    /// Evaluates the pending operations of the arguments kept by the operation that originated
+    /// this node, so that they are computed once instead of on every backward pass
+    pub(crate) fn eval_args(&self) {
+        for arg in self.origin.borrow().args() {
+            arg.eval();
+        }
+    }
+
+    /// Replaces the operation that originated this node with the identity of the given node,
+    /// which computes the same values, so that its gradients are forwarded to it during the
+    /// backward pass. Its values and the arguments of its operation are released in favour of
+    /// those of the given node
+    pub(crate) fn forward_to(&self, node: Shared<Self>) {
+        self.set_data(node.data().clone());
+        let identity = Origin::Unary(UnaryOp {
+            ancestor: node,
+            reverse: |df, _| df.clone(),
+            tangent: Some(|dx, _| dx.clone()),
+            args: Vec::new(),
+        });
+        let previous = std::mem::replace(&mut *self.origin.borrow_mut(), identity);
+        previous
+            .args()
+            .iter()
+            .for_each(|arg| Usage::Args.freed(arg));
+    }
+
+    /// Returns the number of nodes alive
+    pub(crate) fn count() -> usize {
+        COUNTER.load(Ordering::Relaxed)
     }
 
     /// Returns node's ID
@@ -174,46 +440,143 @@ impl Node {
         self.id
     }
 
+    /// Returns the name of the kind of operation that originated this node
+    pub(crate) fn kind(&self) -> &'static str {
+        match *self.origin.borrow() {
+            Origin::Declaration => "Declaration",
+            Origin::Unary(_) => "Unary",
+            Origin::Binary(_) => "Binary",
+            Origin::Released => "Released",
+        }
+    }
+
+    /// Returns the name of the operation that originated this node, as given to the profiler,
+    /// or the name of its kind if it has none
+    pub(crate) fn op_name(&self) -> &'static str {
+        self.name.unwrap_or_else(|| self.kind())
+    }
+
+    /// Returns the name given to this node, if any
+    pub(crate) fn label(&self) -> Option<&'static str> {
+        *self.label.borrow()
+    }
+
+    /// Names this node, i.e. after the part of a model computing it
+    pub(crate) fn set_label(&self, label: &'static str) {
+        *self.label.borrow_mut() = Some(label);
+    }
+
+    /// Returns the name given to this node, or otherwise the name of its operation
+    pub(crate) fn name(&self) -> &'static str {
+        self.label().unwrap_or_else(|| self.op_name())
+    }
+
+    /// Returns the bytes held by the arrays of this node used as given
+    pub(crate) fn bytes(&self, usage: Usage) -> usize {
+        match usage {
+            Usage::Data => memory::bytes(&self.data.borrow()),
+            Usage::Grad => self.grad.borrow().as_ref().map_or(0, memory::bytes),
+            Usage::Args => self.origin.borrow().args().iter().map(memory::bytes).sum(),
+        }
+    }
+
+    /// Returns the `Variable` parameters of the operation that originated this node
+    pub(crate) fn ancestors(&self) -> Vec<Shared<Self>> {
+        match *self.origin.borrow() {
+            Origin::Unary(ref op) => vec![op.ancestor.clone()],
+            Origin::Binary(ref op) => match op.ancestors {
+                BinaryParams::VarVar(ref a, ref b) => vec![a.clone(), b.clone()],
+                BinaryParams::VarConst(ref a) | BinaryParams::ConstVar(ref a) => vec![a.clone()],
+            },
+            Origin::Declaration | Origin::Released => Vec::new(),
+        }
+    }
+
     /// Returns `true` if the node is `Variable` declaration, `false` otherwise
-    pub(crate) const fn is_declaration(&self) -> bool {
-        matches!(self.origin, Origin::Declaration)
+    pub(crate) fn is_declaration(&self) -> bool {
+        matches!(*self.origin.borrow(), Origin::Declaration)
     }
 }
 
 impl Drop for Node {
     fn drop(&mut self) {
+        Usage::Data.freed(&self.data.borrow());
+        if let Some(ref grad) = *self.grad.borrow() {
+            Usage::Grad.freed(grad);
+        }
+        for arg in self.origin.borrow().args() {
+            Usage::Args.freed(arg);
+        }
         COUNTER.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
+impl Origin {
+    /// Returns the arguments kept by the operation for the backward pass
+    fn args(&self) -> &[Array<Float>] {
+        match *self {
+            Self::Unary(ref op) => &op.args,
+            Self::Binary(ref op) => &op.args,
+            Self::Declaration | Self::Released => &[],
+        }
+    }
+}
+
+/// Identifies an operation by its reverse function and the nodes of its `Variable` parameters.
+/// Operations with the same signature and arguments compute the same values and derivatives
+#[derive(PartialEq, Eq, Hash)]
+pub struct Signature {
+    reverse: usize,
+    ancestors: [Option<*const Node>; 2],
+}
+
 /// Represents the different combination of parameters a binary `Operation`
 /// can have
 enum BinaryParams {
     /// Both parameters are `Variable`s
-    VarVar(Rc<Node>, Rc<Node>),
+    VarVar(Shared<Node>, Shared<Node>),
     /// Only one parameter is a `Variable`
-    VarConst(Rc<Node>),
+    VarConst(Shared<Node>),
     /// Only the second parameter is a `Variable`
-    ConstVar(Rc<Node>),
+    ConstVar(Shared<Node>),
 }
 
-/// Computes the partial adjoint derivative of a unary operation for its parameter
-pub type UnaryReverseFn = fn(df: &Array<f32>, args: &[Array<f32>]) -> Array<f32>;
+/// Computes the partial adjoint derivative of a unary operation for its parameter.
+///
+/// The arguments are the arrays the operation needs to compute it, moved into its node.
+/// Arrayfire arrays are reference counted handles, so an argument taken from the data of
+/// another tensor shares its device memory instead of copying it
+pub type UnaryReverseFn = fn(df: &Array<Float>, args: &[Array<Float>]) -> Array<Float>;
 /// Computes the partial adjoint derivative of a binary operation for each of its parameters
-pub type BinaryReverseFn = fn(df: &Array<f32>, args: &[Array<f32>]) -> (Array<f32>, Array<f32>);
+pub type BinaryReverseFn =
+    fn(df: &Array<Float>, args: &[Array<Float>]) -> (Array<Float>, Array<Float>);
+
+/// Computes the tangent of the result of an operation from the tangent of one of its
+/// parameters, the forward mode counterpart of the reverse functions
+pub type TangentFn = fn(dx: &Array<Float>, args: &[Array<Float>]) -> Array<Float>;
+
+/// The forward mode derivatives of an operation, one per parameter
+#[derive(Clone, Copy)]
+pub enum Tangent {
+    /// Derivative of a unary operation
+    Unary(TangentFn),
+    /// Derivatives of a binary operation with respect to its first and second parameters
+    Binary(TangentFn, TangentFn),
+}
 
 /// Represents a unary `Operation`
 struct UnaryOp {
-    ancestor: Rc<Node>,
+    ancestor: Shared<Node>,
     reverse: UnaryReverseFn,
-    args: Vec<Array<f32>>,
+    tangent: Option<TangentFn>,
+    args: Vec<Array<Float>>,
 }
 
 impl UnaryOp {
     /// Computes the partial adjoint derivative and accumulates it to the parameter gradients
-    fn reverse(&self, df: &Array<f32>) {
-        let partial = (self.reverse)(df, self.args.as_slice());
-        *self.ancestor.grad_mut() += partial;
+    fn reverse(&self, df: &Array<Float>) {
+        self.ancestor
+            .accumulate_grad((self.reverse)(df, self.args.as_slice()));
     }
 }
 
@@ -221,29 +584,26 @@ impl UnaryOp {
 struct BinaryOp {
     ancestors: BinaryParams,
     reverse: BinaryReverseFn,
-    args: Vec<Array<f32>>,
+    tangent: Option<(TangentFn, TangentFn)>,
+    args: Vec<Array<Float>>,
 }
 
 impl BinaryOp {
     /// Computes the partial adjoints derivatives and accumulates them to the parameters gradients
-    fn reverse(&self, df: &Array<f32>) {
+    fn reverse(&self, df: &Array<Float>) {
         match self.ancestors {
             BinaryParams::VarVar(ref ancestor_a, ref ancestor_b) => {
                 let (partial_a, partial_b) = (self.reverse)(df, self.args.as_slice());
-                let grad_a = arrayfire::add(&ancestor_a.grad().clone(), &partial_a, true);
-                let grad_b = arrayfire::add(&ancestor_b.grad().clone(), &partial_b, true);
-                *ancestor_a.grad_mut() = grad_a;
-                *ancestor_b.grad_mut() = grad_b;
+                ancestor_a.accumulate_grad(partial_a);
+                ancestor_b.accumulate_grad(partial_b);
             }
             BinaryParams::VarConst(ref ancestor) => {
                 let (partial, _) = (self.reverse)(df, self.args.as_slice());
-                let grad = arrayfire::add(&ancestor.grad().clone(), &partial, true);
-                *ancestor.grad_mut() = grad;
+                ancestor.accumulate_grad(partial);
             }
             BinaryParams::ConstVar(ref ancestor) => {
                 let (_, partial) = (self.reverse)(df, self.args.as_slice());
-                let grad = arrayfire::add(&ancestor.grad().clone(), &partial, true);
-                *ancestor.grad_mut() = grad;
+                ancestor.accumulate_grad(partial);
             }
         }
     }
@@ -261,11 +621,9 @@ pub(crate) mod tests {
             node.data().clone(),
             arrayfire::constant!(2.0; 1,2,3,4)
         ));
-        assert!(equal_data(
-            node.grad().clone(),
-            arrayfire::constant!(0.0; 1,2,3,4)
-        ));
-        assert!(matches!(node.origin, Origin::Declaration));
+        assert!(equal_data(node.grad(), arrayfire::constant!(0.0; 1,2,3,4)));
+        assert!(node.grad.borrow().is_none());
+        assert!(matches!(*node.origin.borrow(), Origin::Declaration));
         assert_eq!(node.id(), 0);
     }
 
@@ -294,19 +652,13 @@ pub(crate) mod tests {
     fn ones_grad() {
         let node = Node::new(arrayfire::constant!(2.0; 1,2,3,4), Origin::Declaration);
         node.ones_grad();
-        assert!(equal_data(
-            node.grad().clone(),
-            arrayfire::constant!(1.0; 1,2,3,4)
-        ));
+        assert!(equal_data(node.grad(), arrayfire::constant!(1.0; 1,2,3,4)));
     }
 
     #[test]
     fn zero_grad() {
         let node = Node::new(arrayfire::constant!(2.0; 1,2,3,4), Origin::Declaration);
         node.zero_grad();
-        assert!(equal_data(
-            node.grad().clone(),
-            arrayfire::constant!(0.0; 1,2,3,4)
-        ));
+        assert!(equal_data(node.grad(), arrayfire::constant!(0.0; 1,2,3,4)));
     }
 }
@@ -0,0 +1,65 @@
+//! Pool of the constant arrays filling the gradients, i.e. the ones seeding every backward
+//! pass or the zeros returned for gradients never written. They are allocated once per
+//! device and shape, and then shared by all the gradients taking them: arrayfire arrays are
+//! reference counted and copied on write, so sharing them is safe. The buffers of the
+//! intermediate results are already recycled by the arrayfire memory manager, which keeps
+//! those of dropped arrays for the later allocations of the same size.
+
+use crate::tensor::Float;
+use arrayfire::{Array, Dim4};
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, PoisonError},
+};
+
+/// Identifies pooled arrays by device, shape and whether they are filled with ones or zeros
+type Key = (i32, [u64; 4], bool);
+
+static POOL: Mutex<BTreeMap<Key, Array<Float>>> = Mutex::new(BTreeMap::new());
+
+/// Returns an array of the given shape filled with zeros or ones, allocating it in the active
+/// device only the first time it is requested
+fn get(dims: Dim4, ones: bool) -> Array<Float> {
+    let key = (arrayfire::get_device(), *dims.get(), ones);
+    POOL.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(key)
+        .or_insert_with(|| arrayfire::constant(if ones { 1.0 } else { 0.0 }, dims))
+        .clone()
+}
+
+/// Returns a pooled array of the given shape filled with zeros
+pub fn zeros(dims: Dim4) -> Array<Float> {
+    get(dims, false)
+}
+
+/// Returns a pooled array of the given shape filled with ones
+pub fn ones(dims: Dim4) -> Array<Float> {
+    get(dims, true)
+}
+
+/// Releases the arrays of the pool, i.e. once done training a model whose shapes are not
+/// going to be used anymore. Gradients still sharing them keep them alive
+#[inline]
+#[allow(clippy::module_name_repetitions)]
+pub fn clear_pool() {
+    POOL.lock().unwrap_or_else(PoisonError::into_inner).clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_pool, ones, zeros};
+    use crate::tests::equal_data;
+    use arrayfire::{constant, dim4};
+
+    #[test]
+    fn pooled_constants() {
+        let dims = dim4!(2, 3, 1, 1);
+        assert!(equal_data(ones(dims), constant!(1.0; 2, 3, 1, 1)));
+        assert!(equal_data(zeros(dims), constant!(0.0; 2, 3, 1, 1)));
+
+        let shared = ones(dims);
+        clear_pool();
+        assert!(equal_data(shared, ones(dims)));
+    }
+}
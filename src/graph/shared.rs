@@ -0,0 +1,105 @@
+//! Sharing primitives of the computation graph. By default nodes are shared with `Rc` and
+//! mutated through `RefCell`, which is the cheapest option for single threaded programs.
+//! The `sync` feature swaps them for `Arc` and `RwLock`, so that tensors, layers and
+//! optimizers can be sent to and shared between threads.
+
+#[cfg(not(feature = "sync"))]
+use std::cell::{Ref, RefCell, RefMut};
+#[cfg(feature = "sync")]
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Pointer with shared ownership of a graph node, `Arc` with the `sync` feature or `Rc` otherwise
+#[cfg(not(feature = "sync"))]
+pub use std::rc::Rc as Shared;
+/// Pointer with shared ownership of a graph node, `Arc` with the `sync` feature or `Rc` otherwise
+#[cfg(feature = "sync")]
+pub use std::sync::Arc as Shared;
+
+/// Bounds required to the closures stored in the graph, `Send + Sync` with the `sync` feature
+#[cfg(not(feature = "sync"))]
+pub trait Threaded {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> Threaded for T {}
+
+/// Bounds required to the closures stored in the graph, `Send + Sync` with the `sync` feature
+#[cfg(feature = "sync")]
+pub trait Threaded: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send + Sync> Threaded for T {}
+
+/// Shared reference to the value of a `Lock`
+#[cfg(not(feature = "sync"))]
+pub type ReadGuard<'a, T> = Ref<'a, T>;
+/// Shared reference to the value of a `Lock`
+#[cfg(feature = "sync")]
+pub type ReadGuard<'a, T> = RwLockReadGuard<'a, T>;
+
+/// Exclusive reference to the value of a `Lock`
+#[cfg(not(feature = "sync"))]
+pub type WriteGuard<'a, T> = RefMut<'a, T>;
+/// Exclusive reference to the value of a `Lock`
+#[cfg(feature = "sync")]
+pub type WriteGuard<'a, T> = RwLockWriteGuard<'a, T>;
+
+/// Interior mutability of the values held by graph nodes, a `RwLock` with the `sync` feature
+/// or a `RefCell` otherwise
+pub struct Lock<T> {
+    #[cfg(not(feature = "sync"))]
+    inner: RefCell<T>,
+    #[cfg(feature = "sync")]
+    inner: RwLock<T>,
+}
+
+impl<T> Lock<T> {
+    /// Wraps the given value
+    pub const fn new(value: T) -> Self {
+        Self {
+            #[cfg(not(feature = "sync"))]
+            inner: RefCell::new(value),
+            #[cfg(feature = "sync")]
+            inner: RwLock::new(value),
+        }
+    }
+
+    /// Returns a shared reference to the value
+    #[cfg(not(feature = "sync"))]
+    pub fn borrow(&self) -> ReadGuard<T> {
+        self.inner.borrow()
+    }
+
+    /// Returns a shared reference to the value. A panic while the value was being modified
+    /// does not prevent further access, as values are always replaced as a whole
+    #[cfg(feature = "sync")]
+    pub fn borrow(&self) -> ReadGuard<T> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns an exclusive reference to the value
+    #[cfg(not(feature = "sync"))]
+    pub fn borrow_mut(&self) -> WriteGuard<T> {
+        self.inner.borrow_mut()
+    }
+
+    /// Returns an exclusive reference to the value, see `borrow`
+    #[cfg(feature = "sync")]
+    pub fn borrow_mut(&self) -> WriteGuard<T> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(all(test, feature = "sync"))]
+mod tests {
+    use crate::tensor::{variable::Variable, Tensor};
+
+    fn assert_threaded<T: Send + Sync>() {}
+
+    #[test]
+    fn threaded_tensors() {
+        assert_threaded::<Tensor<1, 2, 3, 4, Variable>>();
+        assert_threaded::<crate::Context>();
+
+        let x = crate::fill::<1, 1, 1, 1>(2.0);
+        let y = std::thread::spawn(move || crate::sin(&x)).join().unwrap();
+        y.backward();
+    }
+}
@@ -1,56 +1,223 @@
-use crate::graph::node::{Node, NodeId};
-use std::collections::{btree_map::Values, BTreeMap};
-use std::rc::Rc;
+use crate::graph::{memory::Usage, node::Node, shared::Shared};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-/// Stores the computation graph as a set of operation `Node`s
-#[derive(Default, Clone)]
-pub struct Tape(BTreeMap<NodeId, Rc<Node>>);
+/// The computation graph up until a given `Node`, as the list of the node itself and all of
+/// its ancestors sorted so that every node comes after the parameters of its operation.
+///
+/// Nodes already link to their parameters, so the graph is built by the operations at no cost
+/// and only collected into a `Tape` when it has to be traversed, i.e. by `backward`
+pub struct Tape(Vec<Shared<Node>>);
+
+/// Statistics of the computation graph up until a tensor, see `Tensor::graph_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphInfo {
+    /// Number of nodes, including the tensor's own
+    pub nodes: usize,
+    /// Number of nodes resulting from every kind of operation, by the name the operation is
+    /// profiled with. Unnamed operations and declarations are counted by their kind
+    pub ops: BTreeMap<&'static str, usize>,
+    /// Bytes held by the values of the nodes
+    pub data: usize,
+    /// Bytes held by the gradients of the nodes
+    pub grads: usize,
+    /// Bytes held by the arguments the operations keep for the backward pass
+    pub args: usize,
+}
 
 impl Tape {
-    /// Adds the node to the computation graph
-    pub(crate) fn push(&mut self, node: Rc<Node>) {
-        self.0.insert(node.id(), node);
+    /// Collects the computation graph up until the given node with a depth first search,
+    /// visiting every shared ancestor only once
+    pub(crate) fn new(root: Shared<Node>) -> Self {
+        let mut visited = HashSet::new();
+        let mut nodes = Vec::new();
+
+        // A node is pushed to the tape once all of its ancestors have been
+        let mut stack = vec![(root, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                nodes.push(node);
+            } else if visited.insert(Shared::as_ptr(&node)) {
+                let ancestors = node.ancestors();
+                stack.push((node, true));
+                stack.extend(ancestors.into_iter().map(|ancestor| (ancestor, false)));
+            }
+        }
+
+        Self(nodes)
+    }
+
+    /// Return an iterator over the computation graph nodes, in topological order
+    pub(crate) fn nodes(&self) -> std::slice::Iter<Shared<Node>> {
+        self.0.iter()
     }
 
-    /// Return an iterator over the computation graph nodes
-    pub(crate) fn nodes(&self) -> Values<NodeId, Rc<Node>> {
-        self.0.values()
+    /// Rewires every node resulting from the same operation, on the same parameters and with
+    /// the same arguments as a previous node of the tape, to forward its gradients to the
+    /// latter, which then accumulates those of both. The derivatives of the operation are thus
+    /// computed once, and the arguments of the remaining operations are evaluated so that the
+    /// constant subexpressions left pending by lazy evaluation are computed only once too.
+    ///
+    /// Returns the number of nodes rewired. Rewired nodes depend on the node they forward to,
+    /// so the tape has to be collected again to traverse it
+    pub(crate) fn eliminate_duplicates(&self) -> usize {
+        let mut operations: HashMap<_, Vec<&Shared<Node>>> = HashMap::new();
+        let mut rewired = 0;
+        for node in self.nodes() {
+            let Some(signature) = node.signature() else {
+                continue;
+            };
+            let candidates = operations.entry(signature).or_default();
+            if let Some(&first) = candidates.iter().find(|first| first.same_args(node)) {
+                node.forward_to(first.clone());
+                rewired += 1;
+            } else {
+                node.eval_args();
+                candidates.push(node);
+            }
+        }
+        rewired
     }
 
-    /// Given another tape, returns a new tape with the joined computation graphs
-    pub(crate) fn merge(&self, other: &Self) -> Self {
-        let mut tape = self.clone();
-        let other = other.0.clone();
-        tape.0.extend(other.into_iter());
-        tape
+    /// Returns the number of nodes of the tape, how many result from every kind of operation
+    /// and the memory held by their arrays. Arrays shared between nodes are counted once per
+    /// node, as in `memory_stats`
+    pub(crate) fn info(&self) -> GraphInfo {
+        self.nodes().fold(GraphInfo::default(), |mut info, node| {
+            info.nodes += 1;
+            *info.ops.entry(node.op_name()).or_default() += 1;
+            info.data += node.bytes(Usage::Data);
+            info.grads += node.bytes(Usage::Grad);
+            info.args += node.bytes(Usage::Args);
+            info
+        })
+    }
+
+    /// Returns the computation graph in Graphviz DOT format, with one vertex per node labeled
+    /// with its ID, name if given, operation and shape `[B, C, H, W]`, and edges from the
+    /// parameters of every operation to its result. Declarations are drawn as boxes.
+    /// Vertices are named after the position of their node in the tape, as the IDs of dropped
+    /// nodes are reused
+    pub(crate) fn to_dot(&self) -> String {
+        let positions: HashMap<*const Node, usize> = self
+            .nodes()
+            .enumerate()
+            .map(|(i, node)| (Shared::as_ptr(node), i))
+            .collect();
+
+        let mut lines = vec![String::from("digraph {")];
+        for (i, node) in self.nodes().enumerate() {
+            let dims = node.data().dims();
+            let shape = if node.is_declaration() {
+                "box"
+            } else {
+                "ellipse"
+            };
+            let op = node.label().map_or_else(
+                || String::from(node.op_name()),
+                |label| format!("{label} ({})", node.op_name()),
+            );
+            lines.push(format!(
+                "    n{i} [label=\"#{} {op} [{}, {}, {}, {}]\", shape={shape}];",
+                node.id(),
+                dims[3],
+                dims[2],
+                dims[0],
+                dims[1],
+            ));
+            lines.extend(
+                node.ancestors().iter().map(|ancestor| {
+                    format!("    n{} -> n{i};", positions[&Shared::as_ptr(ancestor)])
+                }),
+            );
+        }
+        lines.push(String::from("}"));
+        lines.join("\n")
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Tape;
-    use crate::graph::node::Node;
-    use std::rc::Rc;
+    use crate::tensor::{traits::Tensed, Float};
+    use std::collections::HashSet;
 
     #[test]
-    fn merge_tapes() {
-        let mut first = Tape::default();
-        let mut second = Tape::default();
-        let node_0 = Rc::new(Node::declaration(arrayfire::constant!(1.0; 1,2,3,4)));
-        let node_1 = Rc::new(Node::declaration(arrayfire::constant!(1.0; 1,2,3,4)));
-        let node_2 = Rc::new(Node::declaration(arrayfire::constant!(1.0; 1,2,3,4)));
-
-        first.push(node_0.clone());
-        first.push(node_1.clone());
-        second.push(node_1.clone());
-        second.push(node_2.clone());
-
-        let result = first.merge(&second);
-        assert_eq!(result.0.len(), 3);
-
-        for (i, (k, v)) in result.0.iter().enumerate() {
-            assert_eq!(i, *k);
-            assert_eq!(i, v.id());
-        }
+    fn tape_topological_order() {
+        let x = crate::fill::<1, 1, 1, 1>(1.0);
+        let y = crate::sin(&x);
+        let z = crate::mul(&x, &y);
+
+        let tape = Tape::new(z.inner().node());
+        let ids: Vec<_> = tape.nodes().map(|node| node.id()).collect();
+        assert_eq!(
+            ids,
+            [
+                x.inner().node().id(),
+                y.inner().node().id(),
+                z.inner().node().id()
+            ]
+        );
+    }
+
+    #[test]
+    fn tape_info() {
+        let x = crate::fill::<1, 1, 2, 2>(1.0);
+        let y = crate::sin(&x);
+        let z = crate::mul(&y, &crate::fill::<1, 1, 2, 2>(2.0).freeze());
+        z.backward();
+
+        let info = Tape::new(z.inner().node()).info();
+        let bytes = 4 * std::mem::size_of::<Float>();
+        assert_eq!(info.nodes, 3);
+        assert_eq!(
+            info.ops.into_iter().collect::<Vec<_>>(),
+            [("Declaration", 1), ("mul", 1), ("sin", 1)]
+        );
+        assert_eq!(info.data, 3 * bytes);
+        assert_eq!(info.grads, 3 * bytes);
+        // The sine keeps its input and the product both of its factors
+        assert_eq!(info.args, 3 * bytes);
+    }
+
+    #[test]
+    fn tape_to_dot() {
+        let x = crate::fill::<1, 1, 2, 3>(1.0);
+        let z = crate::sin(&x);
+        let (i, j) = (x.inner().node().id(), z.inner().node().id());
+
+        assert_eq!(
+            z.to_dot(),
+            format!(
+                "digraph {{\n    n0 [label=\"#{i} Declaration [1, 1, 2, 3]\", shape=box];\n    \
+                 n1 [label=\"#{j} sin [1, 1, 2, 3]\", shape=ellipse];\n    n0 -> n1;\n}}"
+            )
+        );
+
+        let z = z.named("encoder_out");
+        assert!(z.to_dot().contains(&format!(
+            "n1 [label=\"#{j} encoder_out (sin) [1, 1, 2, 3]\""
+        )));
+    }
+
+    #[test]
+    fn to_dot_reused_ids() {
+        let x = crate::fill::<1, 1, 1, 1>(1.0);
+        let dropped = crate::fill::<1, 1, 1, 1>(1.0);
+        let y = crate::fill::<1, 1, 1, 1>(1.0);
+        drop(dropped);
+
+        // Takes the id of `y`, unless other nodes are created or dropped meanwhile
+        let w = crate::fill::<1, 1, 1, 1>(1.0);
+
+        let dot = crate::add(&crate::add(&x, &y), &w).to_dot();
+        let names = |edges: bool| {
+            dot.lines()
+                .filter(|line| line.contains(" -> ") == edges && line.contains(" n"))
+                .filter_map(|line| line.split_whitespace().next())
+                .collect::<HashSet<_>>()
+                .len()
+        };
+        assert_eq!(names(false), 5);
+        assert_eq!(names(true), 4);
     }
 }
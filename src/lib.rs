@@ -34,7 +34,8 @@
 //! We then compute the output (`z`) as `WX + b`. All the operations are eagerly evaluated, so the
 //! resulting tensor values are available at any time. Comparted to lazy evaluation, this has the
 //! benefit that the built computation graph is trully dynamic, i.e. your graph operations can depend
-//! on the result of previous operations.
+//! on the result of previous operations. Chains of element-wise operations can instead be fused
+//! into a single kernel by enabling lazy evaluation with `set_lazy(true)`.
 //!
 //! Mushin automatically keeps track of all the operations performed up until any given variable
 //! and calling `backward()` in one of them traverses the computation graph in reverse mode
@@ -47,6 +48,10 @@
 //! By default, **Mushin** includes the [nn module](https://docs.rs/mushin/latest/mushin/nn/index.html)
 //! that provides optimizers, activation functions, layers and losses ready to use to build neural network
 //! modules. Checkout the module docs for instructions on how to use them.
+//!
+//! The default `nightly` feature enables the `generic_const_exprs` compiler feature, needed to
+//! compute output shapes at compile time. Without it, the crate builds on stable Rust with
+//! `DynTensor` as a runtime checked alternative for shapes that can't be spelled out.
 
 #![deny(
     unsafe_code,
@@ -59,26 +64,56 @@
     clippy::shadow_unrelated,
     clippy::missing_inline_in_public_items
 )]
-#![allow(incomplete_features)]
-#![feature(generic_const_exprs)]
-#![feature(associated_const_equality)]
+#![cfg_attr(feature = "nightly", allow(incomplete_features))]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
 
 #[cfg(feature = "nn")]
 pub mod nn;
 
+pub mod data;
+pub mod distributions;
+pub mod profiler;
+
+mod context;
+mod derivatives;
+mod forward;
 mod gen;
 mod graph;
 mod ops;
 mod tensor;
 
-pub use gen::{custom, eye, fill, randn, randu};
-pub use ops::{add, cos, div, mm, mul, reshape, sin, sub};
+pub use context::Context;
+pub use derivatives::per_sample_grads;
+#[cfg(feature = "nightly")]
+pub use derivatives::{approx_hessian, jacobian};
+pub use forward::jvp;
+pub use gen::{
+    batch, bernoulli, custom, eye, fill, from_fn, glorot, kaiming, matrix, randint, randn,
+    randn_seeded, randn_with, randu, randu_range, randu_seeded, scalar, seed, try_custom, vector,
+    ShapeError,
+};
+pub use graph::{
+    memory::{memory_stats, DeviceMemory, MemoryStats},
+    pool::clear_pool,
+    tape::GraphInfo,
+};
+#[cfg(feature = "nightly")]
+pub use ops::split;
+pub use ops::{
+    add, cos, div, mm, mul, nan_to_num, reshape, roll, sin, slice, sub, to_bf16, to_f16, to_f32,
+    transpose,
+};
+pub use tensor::{
+    dynamic::{DynTensor, ShapeMismatch},
+    is_lazy, set_lazy, sync, BackwardOptions, Batch, Float, Matrix, Scalar, Vector,
+};
 
 #[cfg(test)]
 mod tests {
+    use crate::tensor::Float;
     use arrayfire::{abs, all_true_all, le, Array};
 
-    pub(crate) fn equal_data(x: Array<f32>, y: Array<f32>) -> bool {
+    pub(crate) fn equal_data(x: Array<Float>, y: Array<Float>) -> bool {
         all_true_all(&le(&abs(&(x - y)), &1e-6, false)).0
     }
 }
@@ -1,32 +1,44 @@
-use crate::tensor::{traits::Tensed, Tensor};
+use crate::{
+    graph::node::Tangent,
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Tensed},
+        Float, Tensor,
+    },
+};
 use arrayfire::{Array, MatProp};
 
 /// Performs the `ReLu` activation function on the given tensor
 #[inline]
-pub fn relu<X: Tensed>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, X::Data> {
+pub fn relu<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("relu");
     let result = arrayfire::maxof(
         &x.data(),
-        &arrayfire::constant!(0.0f32; X::HEIGHT,X::WIDTH,X::CHANNELS,X::BATCH),
+        &arrayfire::constant!(0.0 as Float; H,W,C,B),
         false,
     );
-    let reverse =
-        |df: &Array<f32>, args: &[Array<f32>]| df * arrayfire::gt(&args[0], &0.0f32, false);
-    x.push_unary(result, reverse, &[x.data()])
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        df * arrayfire::gt(&args[0], &(0.0 as Float), false)
+    };
+    x.push_unary(result, reverse, vec![x.data()])
+        .with_tangent(Tangent::Unary(reverse))
 }
 
 /// Performs the `Softmax` activation function on the given row vector
 #[inline]
-pub fn softmax<X: Tensed<CHANNELS = 1, HEIGHT = 1>>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, 1, 1, { X::WIDTH }, X::Data> {
+pub fn softmax<const B: u64, const W: u64, X: Data>(
+    x: &Tensor<B, 1, 1, W, X>,
+) -> Tensor<B, 1, 1, W, X> {
+    let _op = profiler::forward("softmax");
     // This is required for numerical stability
     let shift = arrayfire::sub(&x.data(), &arrayfire::max_all(&x.data()).0, true);
     let exps = arrayfire::exp(&shift);
     let result = arrayfire::div(&exps, &arrayfire::sum_all(&exps).0, false);
 
-    let reverse = |df: &Array<f32>, args: &[Array<f32>]| {
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
         let softmax = &args[0];
         arrayfire::matmul(
             df,
@@ -40,28 +52,31 @@ pub fn softmax<X: Tensed<CHANNELS = 1, HEIGHT = 1>>(
         )
     };
 
-    x.push_unary(result.clone(), reverse, &[result])
+    // The jacobian is symmetric, so the reverse and forward derivatives are the same
+    x.push_unary(result.clone(), reverse, vec![result])
+        .with_tangent(Tangent::Unary(reverse))
 }
 
 /// Performs the `log(Softmax)` activation function on the given row vector
 #[inline]
-pub fn logsoftmax<X: Tensed<CHANNELS = 1, HEIGHT = 1>>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, 1, 1, { X::WIDTH }, X::Data> {
+pub fn logsoftmax<const B: u64, const W: u64, X: Data>(
+    x: &Tensor<B, 1, 1, W, X>,
+) -> Tensor<B, 1, 1, W, X> {
+    let _op = profiler::forward("logsoftmax");
     // This is required for numerical stability
     let shift = arrayfire::sub(&x.data(), &arrayfire::max_all(&x.data()).0, true);
     let exps = arrayfire::exp(&shift);
     let softmax = arrayfire::div(&exps, &arrayfire::sum_all(&exps).0, false);
     let result = arrayfire::log(&softmax);
 
-    let reverse = |df: &Array<f32>, args: &[Array<f32>]| {
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
         let s = &args[0];
         arrayfire::matmul(
             df,
             &arrayfire::sub(
-                &arrayfire::identity::<f32>(arrayfire::dim4!(X::WIDTH, X::WIDTH, 1, X::BATCH)),
+                &arrayfire::identity::<Float>(arrayfire::dim4!(W, W, 1, B)),
                 &arrayfire::matmul(
-                    &arrayfire::constant!(1.0; X::WIDTH, 1, 1, X::BATCH),
+                    &arrayfire::constant!(1.0; W, 1, 1, B),
                     s,
                     MatProp::NONE,
                     MatProp::NONE,
@@ -73,12 +88,55 @@ pub fn logsoftmax<X: Tensed<CHANNELS = 1, HEIGHT = 1>>(
         )
     };
 
-    x.push_unary(result, reverse, &[softmax])
+    let tangent = |dx: &Array<Float>, args: &[Array<Float>]| {
+        arrayfire::sub(
+            dx,
+            &arrayfire::matmul(dx, &args[0], MatProp::NONE, MatProp::TRANS),
+            true,
+        )
+    };
+
+    x.push_unary(result, reverse, vec![softmax])
+        .with_tangent(Tangent::Unary(tangent))
+}
+
+/// Performs the `Softmax` activation function on every row vector, over the positions where
+/// the mask is 1 only.
+///
+/// This ignores the padding of sequences: masked positions have probability 0, as do all the
+/// positions of fully masked rows
+#[inline]
+pub fn masked_softmax<const B: u64, const W: u64, X: Data>(
+    x: &Tensor<B, 1, 1, W, X>,
+    mask: &Tensor<B, 1, 1, W, Constant>,
+) -> Tensor<B, 1, 1, W, X> {
+    let _op = profiler::forward("masked_softmax");
+    let keep = arrayfire::gt(&mask.data(), &(0.0 as Float), false);
+    let fill = |value: Float| arrayfire::constant(value, arrayfire::dim4!(1, W, 1, B));
+    // Shift each row by its maximum kept value, this is required for numerical stability
+    let max = arrayfire::max(&arrayfire::select(&x.data(), &keep, &fill(Float::MIN)), 1);
+    let shift = arrayfire::select(
+        &arrayfire::sub(&x.data(), &max, true),
+        &keep,
+        &fill(Float::NEG_INFINITY),
+    );
+    let exps = arrayfire::exp(&shift);
+    let sums = arrayfire::maxof(&arrayfire::sum(&exps, 1), &Float::MIN_POSITIVE, false);
+    let result = arrayfire::div(&exps, &sums, true);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let s = &args[0];
+        s * arrayfire::sub(df, &arrayfire::sum(&(df * s), 1), true)
+    };
+
+    // The jacobian is symmetric, so the reverse and forward derivatives are the same
+    x.push_unary(result.clone(), reverse, vec![result])
+        .with_tangent(Tangent::Unary(reverse))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{logsoftmax, relu, softmax};
+    use super::{logsoftmax, masked_softmax, relu, softmax};
     use crate as mu;
     use crate::tensor::traits::Tensed;
     use crate::tests::equal_data;
@@ -134,4 +192,23 @@ mod tests {
             Array::new(&[0.04038084, 0.13170063, -0.17208147], dim4!(1, 3, 1, 1)),
         ));
     }
+
+    #[test]
+    fn masked_softmax_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 3>(&[1.0, 1.0, 100.0, 2.0, 3.0, 4.0]);
+        let mask = mu::custom::<2, 1, 1, 3>(&[1.0, 1.0, 0.0, 0.0, 0.0, 0.0]).freeze();
+        let z = masked_softmax(&x, &mask);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[0.5, 0.5, 0.0, 0.0, 0.0, 0.0], dim4!(1, 3, 1, 2))
+        ));
+
+        // Masked positions get no gradients
+        let weights = mu::custom::<2, 1, 1, 3>(&[1.0, 0.0, 5.0, 1.0, 2.0, 3.0]).freeze();
+        mu::mul(&z, &weights).backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[0.25, -0.25, 0.0, 0.0, 0.0, 0.0], dim4!(1, 3, 1, 2))
+        ));
+    }
 }
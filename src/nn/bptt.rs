@@ -0,0 +1,124 @@
+//! Truncated backpropagation through time, to train recurrent models on long sequences with
+//! bounded memory.
+//!
+//! The sequence is split into windows of consecutive time steps. The loss of every window is
+//! back-propagated and the parameters updated, then the hidden state is detached from the
+//! computation graph, so that the graph never grows beyond a single window.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{activations::relu, bptt::truncated, layers::Linear, losses::{mse, Mean}, optimizers::SGD};
+//!
+//! let (input, recurrent) = (Linear::<1, 4>::randn(), Linear::<4, 4>::randn());
+//! let optim = SGD::new(&[input.parameters(), recurrent.parameters()], 0.01);
+//! let sequence: Vec<_> = (0..100).map(|_| mu::randn::<8, 1, 1, 1>().freeze()).collect();
+//!
+//! let (hidden, losses) = truncated(&optim, mu::fill(0.0).freeze(), &sequence, 10, |x, h| {
+//!     let next = relu(&mu::add(&input.forward(x), &recurrent.forward(h)));
+//!     let loss = mse(&next, &mu::fill::<8, 1, 1, 4>(0.0).freeze(), Mean);
+//!     (next, loss)
+//! });
+//! ```
+
+use crate::{
+    nn::optimizers::Optimizer,
+    ops::{add, mul},
+    tensor::{constant::Constant, variable::Variable, Float, Tensor},
+};
+
+/// Runs a recurrent step function over a sequence in windows of at most `window` time steps,
+/// starting from the given hidden state.
+///
+/// The step function takes the input of a time step and the previous hidden state, and
+/// returns the next hidden state and the loss of the step.
+/// After every window the mean loss of its steps is back-propagated, the optimizer steps and
+/// the gradients are zeroed. Returns the last hidden state, detached, and the mean loss of
+/// every window
+///
+/// # Panics
+///
+/// Panics if the window is empty
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn truncated<O, I, F, const B: u64, const C: u64, const H: u64, const W: u64>(
+    optim: &O,
+    initial: Tensor<B, C, H, W, Constant>,
+    sequence: &[I],
+    window: usize,
+    mut step: F,
+) -> (Tensor<B, C, H, W, Constant>, Vec<Float>)
+where
+    O: Optimizer,
+    F: FnMut(
+        &I,
+        &Tensor<B, C, H, W, Variable>,
+    ) -> (Tensor<B, C, H, W, Variable>, Tensor<1, 1, 1, 1, Variable>),
+{
+    assert!(window > 0, "the window has to span at least one time step");
+    let mut hidden = initial;
+    let mut losses = Vec::with_capacity(sequence.len().div_ceil(window));
+
+    for inputs in sequence.chunks(window) {
+        // The hidden state starts every window as a new declaration, cut from the previous one
+        let mut state = hidden.unfreeze();
+        let mut total: Option<Tensor<1, 1, 1, 1, Variable>> = None;
+        for input in inputs {
+            let (next, loss) = step(input, &state);
+            total = Some(match total {
+                Some(sum) => add(&sum, &loss),
+                None => loss,
+            });
+            state = next;
+        }
+
+        if let Some(total) = total {
+            let scale = crate::fill::<1, 1, 1, 1>(1.0 / inputs.len() as Float).freeze();
+            let loss = mul(&total, &scale);
+            loss.backward();
+            optim.step();
+            optim.zero_grad();
+            losses.push(loss.to_scalar());
+        }
+        hidden = state.detach();
+    }
+
+    (hidden, losses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncated;
+    use crate as mu;
+    use crate::nn::optimizers::SGD;
+    use crate::tensor::traits::Tensed;
+
+    #[test]
+    fn truncated_bptt() {
+        // A linear recurrence h' = w * h + x, learning to bring its hidden state to one
+        let w = mu::fill::<1, 1, 1, 1>(0.5);
+        let optim = SGD::new(&[w.inner().node()], 0.1);
+        let sequence: Vec<_> = (0..10)
+            .map(|_| mu::fill::<1, 1, 1, 1>(0.5).freeze())
+            .collect();
+        let target = mu::fill::<1, 1, 1, 1>(1.0).freeze();
+
+        let mut nodes = Vec::new();
+        let (hidden, losses) = truncated(&optim, mu::fill(0.0).freeze(), &sequence, 4, |x, h| {
+            let next = mu::add(&mu::mul(h, &w), x);
+            let diff = mu::sub(&next, &target);
+            nodes.push(next.node_count());
+            (next, mu::mul(&diff, &diff))
+        });
+
+        // Windows of 4, 4 and 2 steps
+        assert_eq!(losses.len(), 3);
+        // The graph only spans the steps of the current window
+        assert_eq!(nodes[4], nodes[0]);
+        assert_eq!(nodes.iter().max(), Some(&nodes[3]));
+        assert!(hidden.to_scalar().is_finite());
+        assert!(losses[2] < losses[0]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::optimizers::{
+        schedulers::{CosineWithWarmup, ExponentialLR, ReduceLROnPlateau, Scheduler, StepLR},
+        Optimizer,
+    },
+    tensor::{variable::Variable, Float, Tensor},
+};
+use arrayfire::Array;
+
+/// Metrics tracked by the `Trainer` at the end of every epoch
+#[derive(Clone, Copy, Debug)]
+pub struct Metrics {
+    /// Index of the epoch, starting at zero
+    pub epoch: usize,
+    /// Mean loss over the training batches
+    pub train_loss: Float,
+    /// Mean loss over the validation batches, if any
+    pub valid_loss: Option<Float>,
+}
+
+/// Hooks into the training events of the `Trainer`, or of raw backward passes through
+/// `backward`. All methods do nothing by default
+pub trait Callback {
+    /// Called once before training starts with the number of epochs to run
+    #[inline]
+    fn on_train_begin(&mut self, _epochs: usize) {}
+
+    /// Called after the gradients of a loss have been computed
+    #[inline]
+    fn on_backward_end(&mut self) {}
+
+    /// Called after every batch with its index and loss
+    #[inline]
+    fn on_batch_end(&mut self, _batch: usize, _loss: Float) {}
+
+    /// Called after every epoch with its metrics and the optimizer, i.e. to update its learning rate
+    #[inline]
+    fn on_epoch_end(&mut self, _metrics: &Metrics, _optim: &mut dyn Optimizer) {}
+
+    /// Returns true if training should halt after the current epoch
+    #[inline]
+    fn should_stop(&self) -> bool {
+        false
+    }
+}
+
+/// Computes the gradients of the given tensor, as `backward()` does, and then invokes
+/// `on_backward_end` on every callback
+#[inline]
+pub fn backward<const B: u64, const C: u64, const H: u64, const W: u64>(
+    x: &Tensor<B, C, H, W, Variable>,
+    callbacks: &mut [&mut dyn Callback],
+) {
+    x.backward();
+    for callback in callbacks {
+        callback.on_backward_end();
+    }
+}
+
+impl Callback for StepLR {
+    #[inline]
+    fn on_epoch_end(&mut self, _metrics: &Metrics, optim: &mut dyn Optimizer) {
+        self.step(optim);
+    }
+}
+
+impl Callback for ExponentialLR {
+    #[inline]
+    fn on_epoch_end(&mut self, _metrics: &Metrics, optim: &mut dyn Optimizer) {
+        self.step(optim);
+    }
+}
+
+impl Callback for CosineWithWarmup {
+    #[inline]
+    fn on_epoch_end(&mut self, _metrics: &Metrics, optim: &mut dyn Optimizer) {
+        self.step(optim);
+    }
+}
+
+impl Callback for ReduceLROnPlateau {
+    /// Observes the validation loss, or the training loss if there is no validation data
+    #[inline]
+    fn on_epoch_end(&mut self, metrics: &Metrics, optim: &mut dyn Optimizer) {
+        self.observe(metrics.valid_loss.unwrap_or(metrics.train_loss));
+        self.step(optim);
+    }
+}
+
+/// The metric monitored by `EarlyStopping`
+#[derive(Clone, Copy, Debug)]
+pub enum Monitor {
+    /// The mean training loss
+    TrainLoss,
+    /// The mean validation loss, falling back to the training loss if there is no validation data
+    ValidLoss,
+}
+
+/// Signals that training should halt once the monitored metric has not improved by at least
+/// `min_delta` for more than `patience` epochs, optionally restoring the best parameters seen
+pub struct EarlyStopping {
+    monitor: Monitor,
+    patience: usize,
+    min_delta: Float,
+    best: Float,
+    bad_epochs: usize,
+    stopped: bool,
+    params: Vec<Shared<Node>>,
+    best_params: Vec<Array<Float>>,
+}
+
+impl EarlyStopping {
+    /// Returns a new `EarlyStopping` monitoring the given metric
+    #[must_use]
+    #[inline]
+    pub const fn new(monitor: Monitor, patience: usize, min_delta: Float) -> Self {
+        Self {
+            monitor,
+            patience,
+            min_delta,
+            best: Float::INFINITY,
+            bad_epochs: 0,
+            stopped: false,
+            params: Vec::new(),
+            best_params: Vec::new(),
+        }
+    }
+
+    /// Consumes this callback and returns a copy keeping a snapshot of the given parameters
+    /// whenever the metric improves, which are restored when training is halted
+    #[must_use]
+    #[inline]
+    pub fn restore_best(mut self, params: &[Shared<Node>]) -> Self {
+        self.params = params.to_vec();
+        self
+    }
+
+    /// Records a new value of the monitored metric, to be called once per epoch when used
+    /// standalone. Returns true if training should halt
+    #[inline]
+    pub fn update(&mut self, metric: Float) -> bool {
+        if metric < self.best - self.min_delta {
+            self.best = metric;
+            self.bad_epochs = 0;
+            self.best_params = self.params.iter().map(|n| n.data().clone()).collect();
+        } else {
+            self.bad_epochs += 1;
+        }
+
+        if self.bad_epochs > self.patience && !self.stopped {
+            self.stopped = true;
+            self.restore();
+        }
+        self.stopped
+    }
+
+    /// Overwrites the parameters with the best snapshot taken, if any
+    #[inline]
+    pub fn restore(&self) {
+        for (node, best) in self.params.iter().zip(&self.best_params) {
+            node.set_data(best.clone());
+        }
+    }
+
+    /// Returns the best value of the monitored metric seen so far
+    #[must_use]
+    #[inline]
+    pub const fn best(&self) -> Float {
+        self.best
+    }
+}
+
+impl Callback for EarlyStopping {
+    #[inline]
+    fn on_epoch_end(&mut self, metrics: &Metrics, _optim: &mut dyn Optimizer) {
+        let metric = match self.monitor {
+            Monitor::TrainLoss => metrics.train_loss,
+            Monitor::ValidLoss => metrics.valid_loss.unwrap_or(metrics.train_loss),
+        };
+        self.update(metric);
+    }
+
+    #[inline]
+    fn should_stop(&self) -> bool {
+        self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backward, Callback, EarlyStopping, Monitor};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn early_stopping_restores_best() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut early =
+            EarlyStopping::new(Monitor::TrainLoss, 1, 0.1).restore_best(&[x.inner().node()]);
+
+        assert!(!early.update(1.0));
+        x.inner()
+            .node()
+            .set_data(arrayfire::constant!(2.0; 1,1,1,1));
+        assert!(!early.update(0.95));
+        assert!(early.update(1.0));
+
+        assert!((early.best() - 1.0).abs() < Float::EPSILON);
+        assert!(equal_data(x.data(), arrayfire::constant!(1.0; 1,1,1,1)));
+    }
+
+    #[test]
+    fn backward_invokes_callbacks() {
+        struct Counter(usize);
+
+        impl Callback for Counter {
+            fn on_backward_end(&mut self) {
+                self.0 += 1;
+            }
+        }
+
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut counter = Counter(0);
+        backward(&x, &mut [&mut counter]);
+        assert_eq!(counter.0, 1);
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(1.0; 1,1,1,1)
+        ));
+    }
+}
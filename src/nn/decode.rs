@@ -0,0 +1,219 @@
+//! Generation of sequences with sequence models, one token at a time. Models are given as step
+//! functions returning the scores (logits) of the next token of every sequence decoded so far.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{decode::Decoder, layers::Linear};
+//!
+//! // A toy model scoring the next token given the last one, out of 4
+//! let model = Linear::<4, 4>::randn();
+//! let step = |sequences: &[Vec<u64>]| {
+//!     let last = sequences.iter().map(|sequence| sequence[sequence.len() - 1]);
+//!     let one_hot: Vec<_> = last
+//!         .flat_map(|token| (0..4).map(move |i| if i == token { 1.0 } else { 0.0 }))
+//!         .collect();
+//!     model.forward(&mu::custom::<2, 1, 1, 4>(&one_hot).freeze()).freeze()
+//! };
+//!
+//! let decoder = Decoder::new(8).end(3);
+//! let sequences = decoder.greedy(&[vec![0], vec![1]], step);
+//! let hypotheses = decoder.beam_search::<2, 4, _>(&[0], step);
+//! ```
+
+use crate::tensor::{constant::Constant, traits::Tensed, Float, Tensor};
+use arrayfire::{dim4, Array, TopkFn};
+
+/// Decodes sequences until they end or reach a maximum length, either greedily or with
+/// beam search
+#[derive(Clone, Copy, Debug)]
+pub struct Decoder {
+    max_len: usize,
+    end: Option<u64>,
+}
+
+impl Decoder {
+    /// Returns a `Decoder` adding at most `max_len` tokens to every sequence
+    #[must_use]
+    #[inline]
+    pub const fn new(max_len: usize) -> Self {
+        Self { max_len, end: None }
+    }
+
+    /// Sets the token ending the sequences, which is kept as their last token
+    #[must_use]
+    #[inline]
+    pub const fn end(mut self, token: u64) -> Self {
+        self.end = Some(token);
+        self
+    }
+
+    /// Extends `B` sequences out of a vocabulary of `V` tokens with the highest scoring token
+    /// at every step. The step function is given all the sequences, including those already
+    /// ended, whose scores are ignored
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `B` prompts
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn greedy<const B: u64, const V: u64, F>(
+        &self,
+        prompts: &[Vec<u64>],
+        mut step: F,
+    ) -> Vec<Vec<u64>>
+    where
+        F: FnMut(&[Vec<u64>]) -> Tensor<B, 1, 1, V, Constant>,
+    {
+        assert_eq!(prompts.len() as u64, B, "expected {B} prompts");
+        let mut sequences = prompts.to_vec();
+        let mut ended = vec![false; B as usize];
+        let mut tokens = vec![0_u32; B as usize];
+
+        for _ in 0..self.max_len {
+            let (_, best) = arrayfire::imax(&step(&sequences).data(), 1);
+            best.host(&mut tokens);
+            for ((sequence, done), &token) in sequences.iter_mut().zip(&mut ended).zip(&tokens) {
+                if !*done {
+                    sequence.push(u64::from(token));
+                    *done = self.end == Some(u64::from(token));
+                }
+            }
+            if ended.iter().all(|&done| done) {
+                break;
+            }
+        }
+        sequences
+    }
+
+    /// Extends a sequence out of a vocabulary of `V` tokens keeping the `K` most likely
+    /// sequences (beams) at every step, scored by the sum of the log probabilities of their
+    /// tokens. The step function is given the `K` beams.
+    ///
+    /// Returns at most `K` sequences along with their scores, the most likely first. `K` is
+    /// at most 256
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn beam_search<const K: u64, const V: u64, F>(
+        &self,
+        prompt: &[u64],
+        mut step: F,
+    ) -> Vec<(Vec<u64>, Float)>
+    where
+        F: FnMut(&[Vec<u64>]) -> Tensor<K, 1, 1, V, Constant>,
+    {
+        let mut beams = vec![prompt.to_vec(); K as usize];
+        // The beams are copies of the prompt, only the first is expanded at the first step
+        let mut scores = vec![Float::NEG_INFINITY; K as usize];
+        scores[0] = 0.0;
+        let mut hypotheses = Vec::new();
+
+        for _ in 0..self.max_len {
+            let logits = step(&beams).data();
+            let shifted = arrayfire::sub(&logits, &arrayfire::max(&logits, 1), true);
+            let log_probs = arrayfire::sub(
+                &shifted,
+                &arrayfire::log(&arrayfire::sum(&arrayfire::exp(&shifted), 1)),
+                true,
+            );
+            let totals = arrayfire::add(&log_probs, &Array::new(&scores, dim4!(1, 1, 1, K)), true);
+            let (values, indices) =
+                arrayfire::topk(&arrayfire::flat(&totals), K as u32, 0, TopkFn::MAX);
+            let mut candidates = vec![0_u32; K as usize];
+            values.host(&mut scores);
+            indices.host(&mut candidates);
+
+            beams = candidates
+                .iter()
+                .zip(&mut scores)
+                .map(|(&candidate, score)| {
+                    let (beam, token) = (u64::from(candidate) / V, u64::from(candidate) % V);
+                    let mut sequence = beams[beam as usize].clone();
+                    sequence.push(token);
+                    // Ended beams are kept aside, their slot is not expanded anymore
+                    if self.end == Some(token) && score.is_finite() {
+                        hypotheses.push((sequence.clone(), *score));
+                        *score = Float::NEG_INFINITY;
+                    }
+                    sequence
+                })
+                .collect();
+            if scores.iter().all(|score| !score.is_finite()) {
+                break;
+            }
+        }
+
+        hypotheses.extend(
+            beams
+                .into_iter()
+                .zip(scores)
+                .filter(|&(_, score)| score.is_finite()),
+        );
+        hypotheses.sort_by(|&(_, a), &(_, b)| b.total_cmp(&a));
+        hypotheses.truncate(K as usize);
+        hypotheses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decoder;
+    use crate as mu;
+    use crate::tensor::{constant::Constant, Float, Tensor};
+
+    /// Returns the logits of the given probabilities of the next token of every sequence
+    fn logits<const B: u64>(
+        sequences: &[Vec<u64>],
+        probs: impl Fn(&[u64]) -> [Float; 3],
+    ) -> Tensor<B, 1, 1, 3, Constant> {
+        let values: Vec<_> = sequences
+            .iter()
+            .flat_map(|sequence| probs(sequence).map(Float::ln))
+            .collect();
+        mu::custom(&values).freeze()
+    }
+
+    /// The next token is likely the one following the last, and 2 ends the sequences. After
+    /// `[0, 0]` the sequence ends with a higher probability than after `[0, 1]`
+    fn probs(sequence: &[u64]) -> [Float; 3] {
+        match sequence {
+            [0] => [0.4, 0.6, 1e-6],
+            [0, 0] => [0.05, 0.05, 0.9],
+            [.., 0] => [0.1, 0.8, 0.1],
+            [.., 1] => [0.25, 0.25, 0.5],
+            _ => [0.1, 0.1, 0.8],
+        }
+    }
+
+    #[test]
+    fn greedy_decoding() {
+        let decoder = Decoder::new(4).end(2);
+        let sequences = decoder.greedy(&[vec![0], vec![1]], |sequences| {
+            logits::<2>(sequences, probs)
+        });
+        assert_eq!(sequences, [vec![0, 1, 2], vec![1, 2]]);
+
+        // Without an end token every sequence grows up to the maximum length
+        let sequences = Decoder::new(2).greedy(&[vec![1]], |sequences| {
+            logits::<1>(sequences, |_| [0.2, 0.7, 0.1])
+        });
+        assert_eq!(sequences, [vec![1, 1, 1]]);
+    }
+
+    #[test]
+    fn beam_search_decoding() {
+        let decoder = Decoder::new(4).end(2);
+        let hypotheses =
+            decoder.beam_search::<2, 3, _>(&[0], |sequences| logits::<2>(sequences, probs));
+
+        // Greedy decoding misses the most likely sequence
+        let sequences: Vec<_> = hypotheses.iter().map(|(sequence, _)| sequence).collect();
+        assert_eq!(sequences, [&vec![0, 0, 2], &vec![0, 1, 2]]);
+        assert!((hypotheses[0].1 - (0.4 * 0.9 as Float).ln()).abs() < 1e-4);
+        assert!((hypotheses[1].1 - (0.6 * 0.5 as Float).ln()).abs() < 1e-4);
+    }
+}
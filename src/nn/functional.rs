@@ -0,0 +1,295 @@
+//! Stateless forms of the layers, taking their parameters as tensors, i.e. to share weights
+//! among several parts of a model or to keep them in custom structures.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{functional::{conv2d, linear}, ops::flatten};
+//!
+//! let kernel = mu::randn::<4, 1, 3, 3>();
+//! let (weights, biases) = (mu::randn::<1, 1, 256, 10>(), mu::fill::<1, 1, 1, 10>(0.0));
+//!
+//! // A stride of 2 and a padding of 1 halve the height and width of the images
+//! let x = mu::randn::<8, 1, 16, 16>().freeze();
+//! let z = conv2d::<2, 1, 8, 1, 4, 16, 16, 3, 3, _, _>(&x, &kernel);
+//! let z = linear(&flatten(&z), &weights, &biases);
+//! ```
+
+use crate::{
+    ops::mm,
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Pair, Tensed},
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array, ConvGradientType, Dim4};
+
+/// Value added to the variances by `batch_norm`, to avoid dividing by zero
+pub const EPSILON: Float = 1e-5;
+
+/// Sums the gradients of an operand over the dimensions it was broadcast along
+fn unbroadcast(df: &Array<Float>, dims: Dim4) -> Array<Float> {
+    (0..4).zip(0_i32..).fold(df.clone(), |grad, (axis, dim)| {
+        if dims[axis] == 1 && grad.dims()[axis] > 1 {
+            arrayfire::sum(&grad, dim)
+        } else {
+            grad
+        }
+    })
+}
+
+/// Adds `y` to `x`, broadcasting it along the dimensions it has size one
+pub(crate) fn shift<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const YB: u64,
+    const YC: u64,
+    const YH: u64,
+    const YW: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, YC, YH, YW, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let reverse =
+        |df: &Array<Float>, args: &[Array<Float>]| (df.clone(), unbroadcast(df, args[0].dims()));
+    x.push_binary(
+        y,
+        arrayfire::add(&x.data(), &y.data(), true),
+        reverse,
+        vec![y.data()],
+    )
+}
+
+/// Multiplies `x` by `y`, broadcasting it along the dimensions it has size one
+pub(crate) fn scale<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const YB: u64,
+    const YC: u64,
+    const YH: u64,
+    const YW: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, YC, YH, YW, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (a, b) = (&args[0], &args[1]);
+        (
+            arrayfire::mul(df, b, true),
+            unbroadcast(&(df * a), b.dims()),
+        )
+    };
+    x.push_binary(
+        y,
+        arrayfire::mul(&x.data(), &y.data(), true),
+        reverse,
+        vec![x.data(), y.data()],
+    )
+}
+
+/// Computes `x * w + b` for every sample of the batch, as `layers::Linear` does with its own
+/// weights and biases
+#[inline]
+pub fn linear<const B: u64, const I: u64, const O: u64, X: Data + Pair<Y>, Y: Data, Z: Data>(
+    x: &Tensor<B, 1, 1, I, X>,
+    w: &Tensor<1, 1, I, O, Y>,
+    b: &Tensor<1, 1, 1, O, Z>,
+) -> Tensor<B, 1, 1, O, <<X as Pair<Y>>::Output as Pair<Z>>::Output>
+where
+    <X as Pair<Y>>::Output: Pair<Z>,
+{
+    let _op = profiler::forward("linear");
+    shift(&mm(x, w), b)
+}
+
+/// Convolves the input with a kernel of `O` output channels, moving it `S` values at a time
+/// over the input zero padded by `P` values on every side.
+///
+/// The kernel has the layout of the parameters of `layers::Conv2D`, which equals this
+/// function with a stride of 1 and no padding
+#[inline]
+pub fn conv2d<
+    const S: u64,
+    const P: u64,
+    const B: u64,
+    const I: u64,
+    const O: u64,
+    const XH: u64,
+    const XW: u64,
+    const KH: u64,
+    const KW: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, I, XH, XW, X>,
+    kernel: &Tensor<O, I, KH, KW, Y>,
+) -> Tensor<
+    B,
+    O,
+    { (XH + 2 * P - KH) / S + 1 },
+    { (XW + 2 * P - KW) / S + 1 },
+    <X as Pair<Y>>::Output,
+> {
+    let _op = profiler::forward("conv2d");
+    let result = arrayfire::convolve2_nn(
+        &x.data(),
+        &kernel.data(),
+        dim4!(S, S),
+        dim4!(P, P),
+        dim4!(1, 1),
+    );
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (a, k, out) = (&args[0], &args[1], &args[2]);
+        let gradient = |kind| {
+            arrayfire::convolve2_gradient_nn(
+                df,
+                a,
+                k,
+                out,
+                dim4!(S, S),
+                dim4!(P, P),
+                dim4!(1, 1),
+                kind,
+            )
+        };
+        (
+            gradient(ConvGradientType::DATA),
+            gradient(ConvGradientType::FILTER),
+        )
+    };
+
+    x.push_binary(
+        kernel,
+        result.clone(),
+        reverse,
+        vec![x.data(), kernel.data(), result],
+    )
+}
+
+/// Normalizes every channel of the input with the given mean and variance, i.e. the running
+/// statistics of the training data, then scales it by `gamma` and shifts it by `beta`.
+///
+/// The gradients flow to the input, `gamma` and `beta`. `EPSILON` is added to the variances
+#[inline]
+pub fn batch_norm<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+    Z: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    mean: &Tensor<1, C, 1, 1, Constant>,
+    var: &Tensor<1, C, 1, 1, Constant>,
+    gamma: &Tensor<1, C, 1, 1, Y>,
+    beta: &Tensor<1, C, 1, 1, Z>,
+) -> Tensor<B, C, H, W, <<X as Pair<Y>>::Output as Pair<Z>>::Output>
+where
+    <X as Pair<Y>>::Output: Pair<Z>,
+{
+    let _op = profiler::forward("batch_norm");
+    let inv_std = (1.0 as Float) / arrayfire::sqrt(&(var.data() + EPSILON));
+    let normalized = arrayfire::mul(
+        &arrayfire::sub(&x.data(), &mean.data(), true),
+        &inv_std,
+        true,
+    );
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| arrayfire::mul(df, &args[0], true);
+    let normalized: Tensor<B, C, H, W, X> = x.push_unary(normalized, reverse, vec![inv_std]);
+    shift(&scale(&normalized, gamma), beta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_norm, conv2d, linear, EPSILON};
+    use crate as mu;
+    use crate::nn::layers::Conv2D;
+    use crate::tensor::{traits::Tensed, variable::Variable, Tensor};
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn linear_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 2>(&[1.0, 2.0, 3.0, 4.0]);
+        let w = mu::custom::<1, 1, 2, 1>(&[1.0, -1.0]);
+        let b = mu::custom::<1, 1, 1, 1>(&[0.5]);
+
+        let z = linear(&x, &w, &b);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[-0.5, -0.5], dim4!(1, 1, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[1.0, -1.0, 1.0, -1.0], dim4!(1, 2, 1, 2))
+        ));
+        // The biases are broadcast to every sample of the batch
+        assert!(equal_data(
+            b.grad().data(),
+            Array::new(&[2.0], dim4!(1, 1, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn conv2d_stride_padding() {
+        let layer = Conv2D::<2, 3, 3, 3>::randn();
+        let kernel: Tensor<3, 2, 3, 3, Variable> = Variable::from(layer.parameters()).into();
+        let x = mu::randn::<2, 2, 5, 5>().freeze();
+        assert!(equal_data(
+            conv2d::<1, 0, 2, 2, 3, 5, 5, 3, 3, _, _>(&x, &kernel).data(),
+            layer.forward(&x).data()
+        ));
+
+        let z = conv2d::<2, 1, 2, 2, 3, 5, 5, 3, 3, _, _>(&x, &kernel);
+        assert_eq!(z.data().dims(), dim4!(3, 3, 3, 2));
+        z.backward();
+        assert_eq!(kernel.grad().data().dims(), dim4!(3, 3, 2, 3));
+    }
+
+    #[test]
+    fn batch_norm_forward_backward() {
+        let x = mu::custom::<2, 2, 1, 1>(&[1.0, 2.0, 3.0, 4.0]);
+        let mean = mu::custom::<1, 2, 1, 1>(&[2.0, 3.0]).freeze();
+        let var = mu::custom::<1, 2, 1, 1>(&[1.0 - EPSILON, 4.0 - EPSILON]).freeze();
+        let gamma = mu::custom::<1, 2, 1, 1>(&[2.0, 1.0]);
+        let beta = mu::custom::<1, 2, 1, 1>(&[0.0, 1.0]);
+
+        let z = batch_norm(&x, &mean, &var, &gamma, &beta);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[-2.0, 0.5, 2.0, 1.5], dim4!(1, 1, 2, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[2.0, 0.5, 2.0, 0.5], dim4!(1, 1, 2, 2))
+        ));
+        assert!(equal_data(
+            gamma.grad().data(),
+            Array::new(&[0.0, 0.0], dim4!(1, 1, 2, 1))
+        ));
+        assert!(equal_data(
+            beta.grad().data(),
+            Array::new(&[2.0, 2.0], dim4!(1, 1, 2, 1))
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+//! Initialization schemes of the parameters of the layers, to be given to their builders.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{init::Init, layers::{Conv2D, Linear}};
+//!
+//! let conv = Conv2D::<3, 16, 3, 3>::builder().init(Init::Kaiming).build();
+//! let linear = Linear::<64, 10>::builder().init(Init::Xavier).seed(42).build();
+//! ```
+
+use crate::tensor::Float;
+use arrayfire::{Array, Dim4, RandomEngine};
+
+/// How the weights of a layer are initialized, given the number of inputs (`fan_in`) and
+/// outputs (`fan_out`) every weight connects
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Init {
+    /// Normal distribution with mean 0 and standard deviation 1, biases included
+    #[default]
+    Normal,
+    /// Uniform distribution between `±sqrt(6 / (fan_in + fan_out))`, suited to `tanh` or
+    /// `sigmoid` activations. Biases are zero
+    Xavier,
+    /// Normal distribution with mean 0 and standard deviation `sqrt(2 / fan_in)`, suited to
+    /// `ReLu` activations. Biases are zero
+    Kaiming,
+    /// All the weights and biases are zero
+    Zeros,
+}
+
+impl Init {
+    /// Returns weights of the given dimensions, taken from the given random number generator
+    /// or from the global one, see `mushin::seed`
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn weights(
+        self,
+        dims: Dim4,
+        fan_in: u64,
+        fan_out: u64,
+        engine: Option<&RandomEngine>,
+    ) -> Array<Float> {
+        match self {
+            Self::Normal => normal(dims, engine),
+            Self::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out) as Float).sqrt();
+                uniform(dims, engine) * (2.0 * limit) - limit
+            }
+            Self::Kaiming => normal(dims, engine) * (2.0 / fan_in as Float).sqrt(),
+            Self::Zeros => arrayfire::constant(0.0 as Float, dims),
+        }
+    }
+
+    /// Returns biases of the given dimensions, see `weights`
+    pub(crate) fn biases(self, dims: Dim4, engine: Option<&RandomEngine>) -> Array<Float> {
+        match self {
+            Self::Normal => normal(dims, engine),
+            Self::Xavier | Self::Kaiming | Self::Zeros => arrayfire::constant(0.0 as Float, dims),
+        }
+    }
+}
+
+/// Returns values taken from a normal distribution with mean 0 and standard deviation 1
+fn normal(dims: Dim4, engine: Option<&RandomEngine>) -> Array<Float> {
+    engine.map_or_else(
+        || arrayfire::randn::<Float>(dims),
+        |engine| arrayfire::random_normal::<Float>(dims, engine),
+    )
+}
+
+/// Returns values taken from a uniform distribution between 0 and 1
+fn uniform(dims: Dim4, engine: Option<&RandomEngine>) -> Array<Float> {
+    engine.map_or_else(
+        || arrayfire::randu::<Float>(dims),
+        |engine| arrayfire::random_uniform::<Float>(dims, engine),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Init;
+    use crate::nn::layers::{Conv2D, Linear};
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn initialization_schemes() {
+        let linear = Linear::<100, 50>::builder().init(Init::Xavier).build();
+        let parameters = linear.parameters().data().clone();
+        let weights = arrayfire::rows(&parameters, 0, 99);
+        let limit = (6.0 as Float / 150.0).sqrt();
+        assert!(arrayfire::max_all(&arrayfire::abs(&weights)).0 <= limit);
+        assert!(
+            arrayfire::max_all(&arrayfire::abs(&arrayfire::row(&parameters, 100))).0
+                < Float::EPSILON
+        );
+
+        let conv = Conv2D::<8, 4, 3, 3>::builder().init(Init::Zeros).build();
+        assert!(equal_data(
+            conv.parameters().data().clone(),
+            arrayfire::constant!(0.0; 3, 3, 8, 4)
+        ));
+    }
+
+    #[test]
+    fn seeded_initialization() {
+        let x = Linear::<4, 2>::builder()
+            .init(Init::Kaiming)
+            .seed(7)
+            .build();
+        let y = Linear::<4, 2>::builder()
+            .init(Init::Kaiming)
+            .seed(7)
+            .build();
+        assert!(equal_data(
+            x.parameters().data().clone(),
+            y.parameters().data().clone()
+        ));
+    }
+}
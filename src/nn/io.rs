@@ -0,0 +1,165 @@
+//! Persistence of model parameters in the [safetensors](https://github.com/huggingface/safetensors)
+//! format. Parameters are stored row-major with shape `[B, C, H, W]` and the tensors element type.
+
+use crate::graph::{node::Node, shared::Shared};
+use crate::tensor::Float;
+use arrayfire::{dim4, Array};
+use safetensors::{tensor::TensorView, Dtype, SafeTensors};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Safetensors data type of the stored parameters
+#[cfg(not(feature = "f64"))]
+const DTYPE: Dtype = Dtype::F32;
+/// Safetensors data type of the stored parameters
+#[cfg(feature = "f64")]
+const DTYPE: Dtype = Dtype::F64;
+
+/// Size in bytes of every stored value
+const SIZE: usize = std::mem::size_of::<Float>();
+
+/// Returns an invalid data error with the given cause
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(cause: E) -> Error {
+    Error::new(ErrorKind::InvalidData, cause)
+}
+
+/// Writes the given named parameters to a safetensors file, i.e. those returned by
+/// `Module::named_state`
+///
+/// # Errors
+///
+/// Returns an error if the file can not be written
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn save<P: AsRef<Path>, S: AsRef<str>>(path: P, params: &[(S, Shared<Node>)]) -> Result<()> {
+    let tensors: Vec<_> = params
+        .iter()
+        .map(|param| {
+            let values = param.1.data().clone();
+            let dims = values.dims();
+
+            // Arrayfire arrays are column-major, transposing rows and columns makes them row-major
+            let mut host = vec![0.0 as Float; values.elements()];
+            arrayfire::transpose(&values, false).host(&mut host);
+            let bytes: Vec<u8> = host.iter().flat_map(|v| v.to_le_bytes()).collect();
+            let shape = [dims[3], dims[2], dims[0], dims[1]].map(|n| n as usize);
+            (param.0.as_ref(), shape.to_vec(), bytes)
+        })
+        .collect();
+
+    let views = tensors
+        .iter()
+        .map(|tensor| {
+            let (name, shape, bytes) = (tensor.0, &tensor.1, &tensor.2);
+            TensorView::new(DTYPE, shape.clone(), bytes).map(|view| (name, view))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+    fs::write(path, safetensors::serialize(views, None).map_err(invalid)?)
+}
+
+/// Reads the given named parameters from a safetensors file, overwriting their values
+///
+/// # Errors
+///
+/// Returns an error if the file can not be read, is not a valid safetensors file, or any of the
+/// parameters is missing, its type or shape do not match, or its values are not aligned to
+/// their size
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn load<P: AsRef<Path>, S: AsRef<str>>(path: P, params: &[(S, Shared<Node>)]) -> Result<()> {
+    let bytes = fs::read(path)?;
+    // The header size and the offsets of every tensor are checked against the file size
+    let (size, metadata) = SafeTensors::read_metadata(&bytes).map_err(invalid)?;
+    let data = size
+        .checked_add(8)
+        .and_then(|start| bytes.get(start..))
+        .ok_or_else(|| invalid("invalid safetensors header size"))?;
+
+    for param in params {
+        let (name, node) = (param.0.as_ref(), &param.1);
+        let info = metadata
+            .info(name)
+            .ok_or_else(|| invalid(format!("missing parameter {name}")))?;
+
+        if info.dtype != DTYPE {
+            return Err(invalid(format!("parameter {name} is not {DTYPE}")));
+        }
+
+        let dims = node.data().dims();
+        let shape = [dims[3], dims[2], dims[0], dims[1]].map(|n| n as usize);
+        if info.shape != shape {
+            return Err(invalid(format!("shape mismatch for parameter {name}")));
+        }
+
+        let (start, end) = info.data_offsets;
+        let values: Vec<Float> = match data.get(start..end) {
+            Some(values) if start % SIZE == 0 => values
+                .chunks_exact(SIZE)
+                .map(|b| {
+                    let mut value = [0; SIZE];
+                    value.copy_from_slice(b);
+                    Float::from_le_bytes(value)
+                })
+                .collect(),
+            _ => return Err(invalid(format!("invalid offsets for parameter {name}"))),
+        };
+
+        node.set_data(arrayfire::transpose(
+            &Array::new(&values, dim4!(dims[1], dims[0], dims[2], dims[3])),
+            false,
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn safetensors_save_load() {
+        let path = std::env::temp_dir().join("mushin-params.safetensors");
+        let x = mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        save(&path, &[("x", x.inner().node())]).unwrap();
+
+        let y = mu::fill::<1, 1, 2, 3>(0.0);
+        load(&path, &[("x", y.inner().node())]).unwrap();
+        assert!(equal_data(x.data(), y.data()));
+
+        let z = mu::fill::<1, 1, 3, 2>(0.0);
+        assert!(load(&path, &[("x", z.inner().node())]).is_err());
+        assert!(load(&path, &[("y", y.inner().node())]).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "f64"))]
+    fn safetensors_untrusted_header() {
+        let path = std::env::temp_dir().join("mushin-untrusted.safetensors");
+        let x = mu::fill::<1, 1, 1, 1>(0.0);
+        let write = |header: &str, data: &[u8]| {
+            let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+            bytes.extend(header.as_bytes());
+            bytes.extend(data);
+            std::fs::write(&path, bytes).unwrap();
+        };
+
+        // A header size overflowing the file
+        std::fs::write(&path, u64::MAX.to_le_bytes()).unwrap();
+        assert!(load(&path, &[("x", x.inner().node())]).is_err());
+
+        // A value following two bytes, not aligned to its size
+        write(
+            r#"{"a":{"dtype":"U8","shape":[2],"data_offsets":[0,2]},"x":{"dtype":"F32","shape":[1,1,1,1],"data_offsets":[2,6]}}"#,
+            &[0; 6],
+        );
+        assert!(load(&path, &[("x", x.inner().node())]).is_err());
+    }
+}
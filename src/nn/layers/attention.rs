@@ -0,0 +1,317 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::Module,
+    profiler,
+    tensor::{
+        traits::{Data, Pair, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array, MatProp};
+
+/// An attention pooling layer, summarizing a sequence of `D` sized vectors into a single one.
+///
+/// The sequence is laid out along the width of the input, one vector per column. Every vector
+/// is scored by its dot product with a learnt query, and the output is the sum of the vectors
+/// weighted by the softmax of their scores, i.e. for sequence classification heads
+pub struct AttentionPool<const D: u64>(Tensor<1, 1, D, 1, Variable>);
+
+impl<const D: u64> AttentionPool<D> {
+    /// Returns a new `AttentionPool` layer with its query taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self(crate::randn())
+    }
+
+    /// Given a sequence of length `L` computes its weighted sum
+    #[inline]
+    pub fn forward<const B: u64, const L: u64, X: Data + Pair<Variable>>(
+        &self,
+        x: &Tensor<B, 1, D, L, X>,
+    ) -> Tensor<B, 1, 1, D, <X as Pair<Variable>>::Output> {
+        let _op = profiler::forward("attention_pool");
+        let (values, query) = (x.data(), self.0.data());
+        let scores = arrayfire::sum(&arrayfire::mul(&values, &query, true), 0);
+        // Shift the scores by their maximum, this is required for numerical stability
+        let exps = arrayfire::exp(&arrayfire::sub(&scores, &arrayfire::max(&scores, 1), true));
+        let weights = arrayfire::div(&exps, &arrayfire::sum(&exps, 1), true);
+        let pooled = arrayfire::sum(&arrayfire::mul(&values, &weights, true), 1);
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (v, q, w) = (&args[0], &args[1], &args[2]);
+            let dpooled = arrayfire::moddims(df, dim4!(D, 1, 1, B));
+            // Backpropagates through the softmax turning the scores into weights
+            let dweights = arrayfire::sum(&arrayfire::mul(v, &dpooled, true), 0);
+            let dscores = w * arrayfire::sub(&dweights, &arrayfire::sum(&(w * &dweights), 1), true);
+            (
+                arrayfire::mul(&dpooled, w, true) + arrayfire::mul(q, &dscores, true),
+                arrayfire::sum(&arrayfire::sum(&arrayfire::mul(v, &dscores, true), 1), 3),
+            )
+        };
+
+        x.push_binary(
+            &self.0,
+            arrayfire::moddims(&pooled, dim4!(1, D, 1, B)),
+            reverse,
+            vec![values, query, weights],
+        )
+    }
+
+    /// Returns the layer's trainable parameters, the query
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Shared<Node> {
+        self.0.inner().node()
+    }
+}
+
+impl<const D: u64> Module for AttentionPool<D> {
+    /// The query is named `query`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("query"), self.parameters())]
+    }
+}
+
+/// A self-attention layer over sequences of `D` sized vectors, where every position only
+/// attends to the `R` positions before and after it.
+///
+/// The sequence is laid out along the height of the input, one vector per row. Queries, keys
+/// and values are learnt projections of the vectors. Every position is scored against its
+/// window only, so the cost grows with the length of the sequence times `2R + 1` instead of
+/// its square, i.e. for long sequences
+pub struct LocalAttention<const D: u64, const R: u64>(Tensor<1, 3, D, D, Variable>);
+
+/// Returns the indices of the `2R + 1` positions of the window of every position of a
+/// sequence of length `L`, clamped to the sequence, along with whether they are within it
+#[allow(clippy::cast_possible_truncation)]
+fn windows<const L: u64, const R: u64>() -> (Array<u32>, Array<bool>) {
+    let offsets = || (0..=2 * R).flat_map(|j| (0..L).map(move |l| (l + j).checked_sub(R)));
+    let indices: Vec<_> = offsets()
+        .map(|index| index.unwrap_or(0).min(L - 1) as u32)
+        .collect();
+    let within: Vec<Float> = offsets()
+        .map(|index| match index {
+            Some(index) if index < L => 1.0,
+            _ => 0.0,
+        })
+        .collect();
+    (
+        Array::new(&indices, dim4!(L * (2 * R + 1))),
+        arrayfire::gt(
+            &Array::new(&within, dim4!(L, 2 * R + 1)),
+            &(0.0 as Float),
+            false,
+        ),
+    )
+}
+
+/// Gathers the windows of every position of a sequence of `D` sized rows, with shape
+/// `[L, 2R + 1, D, B]`
+fn unfold<const B: u64, const L: u64, const D: u64, const R: u64>(
+    x: &Array<Float>,
+    indices: &Array<u32>,
+) -> Array<Float> {
+    arrayfire::moddims(&arrayfire::lookup(x, indices, 0), dim4!(L, 2 * R + 1, D, B))
+}
+
+/// Sums the values of the windows back into the positions they were gathered from, the
+/// reverse of `unfold` for values outside of the sequence set to zero
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn fold<const B: u64, const L: u64, const D: u64, const R: u64>(
+    windows: &Array<Float>,
+) -> Array<Float> {
+    let folded = (0..=2 * R).fold(
+        arrayfire::constant(0.0 as Float, dim4!(L, 1, D, B)),
+        |sum, j| {
+            let offset = j as i32 - R as i32;
+            sum + arrayfire::shift(&arrayfire::col(windows, j as i64), &[offset, 0, 0, 0])
+        },
+    );
+    arrayfire::moddims(&folded, dim4!(L, D, 1, B))
+}
+
+impl<const D: u64, const R: u64> LocalAttention<D, R> {
+    /// Returns a new `LocalAttention` layer with its projections taken from a normal
+    /// distribution with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self(crate::randn())
+    }
+
+    /// Given a sequence of length `L` computes the attended value of every position
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn forward<const B: u64, const L: u64, X: Data + Pair<Variable>>(
+        &self,
+        x: &Tensor<B, 1, L, D, X>,
+    ) -> Tensor<B, 1, L, D, <X as Pair<Variable>>::Output> {
+        let _op = profiler::forward("local_attention");
+        let (inputs, weights) = (x.data(), self.0.data());
+        let project = |p| {
+            arrayfire::matmul(
+                &inputs,
+                &arrayfire::slice(&weights, p),
+                MatProp::NONE,
+                MatProp::NONE,
+            )
+        };
+        let (queries, keys, values) = (project(0), project(1), project(2));
+
+        let (indices, within) = windows::<L, R>();
+        let rows = arrayfire::moddims(&queries, dim4!(L, 1, D, B));
+        let scale = (D as Float).sqrt().recip();
+        let scores = arrayfire::sum(
+            &arrayfire::mul(&rows, &unfold::<B, L, D, R>(&keys, &indices), true),
+            2,
+        ) * scale;
+        // Positions outside of the sequence are masked, every window has at least its center
+        let keep = arrayfire::tile(&within, dim4!(1, 1, 1, B));
+        let fill = arrayfire::constant(Float::NEG_INFINITY, dim4!(L, 2 * R + 1, 1, B));
+        let scores = arrayfire::select(&scores, &keep, &fill);
+        // Shift the scores by their maximum, this is required for numerical stability
+        let exps = arrayfire::exp(&arrayfire::sub(&scores, &arrayfire::max(&scores, 1), true));
+        let attention = arrayfire::div(&exps, &arrayfire::sum(&exps, 1), true);
+        let attended = arrayfire::sum(
+            &arrayfire::mul(&attention, &unfold::<B, L, D, R>(&values, &indices), true),
+            1,
+        );
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (input, projections) = (&args[0], &args[1]);
+            let (q, k, v, att) = (&args[2], &args[3], &args[4], &args[5]);
+            let (positions, _) = windows::<L, R>();
+            let norm = (D as Float).sqrt().recip();
+            let dattended = arrayfire::moddims(df, dim4!(L, 1, D, B));
+            // Backpropagates through the softmax turning the scores into attention
+            let dattention = arrayfire::sum(
+                &arrayfire::mul(&dattended, &unfold::<B, L, D, R>(v, &positions), true),
+                2,
+            );
+            let dscores = att
+                * arrayfire::sub(&dattention, &arrayfire::sum(&(att * &dattention), 1), true)
+                * norm;
+
+            let dq = arrayfire::moddims(
+                &arrayfire::sum(
+                    &arrayfire::mul(&dscores, &unfold::<B, L, D, R>(k, &positions), true),
+                    1,
+                ),
+                dim4!(L, D, 1, B),
+            );
+            let dk = fold::<B, L, D, R>(&arrayfire::mul(
+                &dscores,
+                &arrayfire::moddims(q, dim4!(L, 1, D, B)),
+                true,
+            ));
+            let dv = fold::<B, L, D, R>(&arrayfire::mul(att, &dattended, true));
+
+            let dprojected = [dq, dk, dv];
+            let dx = dprojected.iter().zip(0..).fold(
+                arrayfire::constant(0.0 as Float, dim4!(L, D, 1, B)),
+                |sum, (dp, index)| {
+                    sum + arrayfire::matmul(
+                        dp,
+                        &arrayfire::slice(projections, index),
+                        MatProp::NONE,
+                        MatProp::TRANS,
+                    )
+                },
+            );
+            let dw: Vec<_> = dprojected
+                .iter()
+                .map(|dp| {
+                    arrayfire::sum(
+                        &arrayfire::matmul(input, dp, MatProp::TRANS, MatProp::NONE),
+                        3,
+                    )
+                })
+                .collect();
+            (dx, arrayfire::join_many(2, dw.iter().collect()))
+        };
+
+        x.push_binary(
+            &self.0,
+            arrayfire::moddims(&attended, dim4!(L, D, 1, B)),
+            reverse,
+            vec![inputs, weights, queries, keys, values, attention],
+        )
+    }
+
+    /// Returns the layer's trainable parameters, the query, key and value projections
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Shared<Node> {
+        self.0.inner().node()
+    }
+}
+
+impl<const D: u64, const R: u64> Module for LocalAttention<D, R> {
+    /// The projections are named `qkv`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("qkv"), self.parameters())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttentionPool, LocalAttention};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn attention_pool_forward_backward() {
+        // A zero query weights the vectors (1, 2) and (3, 4) equally
+        let pool = AttentionPool::<2>(mu::fill(0.0));
+        let x = mu::custom::<1, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0]);
+        let z = pool.forward(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[2.0, 3.0], dim4!(1, 2, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(0.5; 2, 2, 1, 1)
+        ));
+        // The query turns towards the vector with the largest sum of gradients
+        assert!(equal_data(
+            pool.parameters().grad(),
+            Array::new(&[2.0, 2.0], dim4!(2, 1, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn local_attention_forward_backward() {
+        // Zero queries and keys average the values within one position of every position
+        let attention = LocalAttention::<1, 1>(mu::custom(&[0.0, 0.0, 1.0]));
+        let x = mu::custom::<1, 1, 4, 1>(&[1.0, 2.0, 3.0, 4.0]);
+        let z = attention.forward(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.5, 2.0, 3.0, 3.5], dim4!(4, 1, 1, 1))
+        ));
+
+        z.backward();
+        // Every position receives the attention it gets from the windows it is part of
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(
+                &[5.0 / 6.0, 7.0 / 6.0, 7.0 / 6.0, 5.0 / 6.0],
+                dim4!(4, 1, 1, 1)
+            )
+        ));
+        assert!(equal_data(
+            attention.parameters().grad(),
+            Array::new(&[0.0, 0.0, 10.0], dim4!(1, 1, 3, 1))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::{
+        functional::{self, EPSILON},
+        Module,
+    },
+    profiler,
+    tensor::{
+        traits::{Data, Pair, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
+};
+use arrayfire::Array;
+
+/// Returns the mean of every channel over the samples, height and width of the values
+fn channel_mean(values: &Array<Float>) -> Array<Float> {
+    arrayfire::mean(&arrayfire::mean(&arrayfire::mean(values, 0), 1), 3)
+}
+
+/// A batch normalization layer over `C` channels: normalizes every channel with the mean and
+/// variance of the batch, then scales it by `gamma` and shifts it by `beta`.
+///
+/// Training keeps running averages of the statistics, which `infer` normalizes with instead.
+/// They are the `running_mean` and `running_var` buffers of the module, so that they are saved
+/// and loaded along with the parameters but never reach the optimizers
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchNorm<const C: u64> {
+    gamma: Tensor<1, C, 1, 1, Variable>,
+    beta: Tensor<1, C, 1, 1, Variable>,
+    running_mean: Tensor<1, C, 1, 1, Variable>,
+    running_var: Tensor<1, C, 1, 1, Variable>,
+    momentum: Float,
+}
+
+impl<const C: u64> BatchNorm<C> {
+    /// Returns a new `BatchNorm` layer that neither scales nor shifts, whose running statistics
+    /// move towards those of every batch by the given momentum, i.e. 0.1
+    #[must_use]
+    #[inline]
+    pub fn new(momentum: Float) -> Self {
+        Self {
+            gamma: crate::fill(1.0),
+            beta: crate::fill(0.0),
+            running_mean: crate::fill(0.0),
+            running_var: crate::fill(1.0),
+            momentum,
+        }
+    }
+
+    /// Given an input computes the output, normalizing it with the statistics of the batch
+    /// and updating the running ones
+    #[allow(clippy::cast_precision_loss)]
+    #[inline]
+    pub fn forward<
+        const B: u64,
+        const H: u64,
+        const W: u64,
+        X: Data + Pair<Variable, Output = Variable>,
+    >(
+        &self,
+        x: &Tensor<B, C, H, W, X>,
+    ) -> Tensor<B, C, H, W, Variable> {
+        let _op = profiler::forward("batch_norm");
+        let values = x.data();
+        let mean = channel_mean(&values);
+        let centered = arrayfire::sub(&values, &mean, true);
+        let var = channel_mean(&(&centered * &centered));
+        let inv_std = (1.0 as Float) / arrayfire::sqrt(&(&var + EPSILON));
+        let normalized = arrayfire::mul(&centered, &inv_std, true);
+
+        // The running variance is unbiased, as the batch is a sample of the training data
+        let n = (B * H * W) as Float;
+        let unbiased = if n > 1.0 { var * (n / (n - 1.0)) } else { var };
+        let momentum = self.momentum;
+        self.running_mean
+            .set_data(&(self.running_mean.data() * (1.0 - momentum) + mean * momentum));
+        self.running_var
+            .set_data(&(self.running_var.data() * (1.0 - momentum) + unbiased * momentum));
+
+        // The statistics depend on every value of the batch, so the gradients lose their
+        // components along the mean and the normalized values of every channel
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (y, scale) = (&args[0], &args[1]);
+            let projected = arrayfire::sub(
+                &arrayfire::sub(df, &channel_mean(df), true),
+                &arrayfire::mul(y, &channel_mean(&(df * y)), true),
+                false,
+            );
+            arrayfire::mul(&projected, scale, true)
+        };
+        let normalized: Tensor<B, C, H, W, X> =
+            x.push_unary(normalized.clone(), reverse, vec![normalized, inv_std]);
+        functional::shift(&functional::scale(&normalized, &self.gamma), &self.beta)
+    }
+
+    /// Given an input computes the output, normalizing it with the running statistics
+    #[inline]
+    pub fn infer<
+        const B: u64,
+        const H: u64,
+        const W: u64,
+        X: Data + Pair<Variable, Output = Variable>,
+    >(
+        &self,
+        x: &Tensor<B, C, H, W, X>,
+    ) -> Tensor<B, C, H, W, Variable> {
+        functional::batch_norm(
+            x,
+            &self.running_mean.detach(),
+            &self.running_var.detach(),
+            &self.gamma,
+            &self.beta,
+        )
+    }
+
+    /// Returns the layer's trainable parameters, `gamma` and `beta`
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        vec![self.gamma.inner().node(), self.beta.inner().node()]
+    }
+}
+
+impl<const C: u64> Module for BatchNorm<C> {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![
+            (String::from("gamma"), self.gamma.inner().node()),
+            (String::from("beta"), self.beta.inner().node()),
+        ]
+    }
+
+    #[inline]
+    fn named_buffers(&self) -> Vec<(String, Shared<Node>)> {
+        vec![
+            (
+                String::from("running_mean"),
+                self.running_mean.inner().node(),
+            ),
+            (String::from("running_var"), self.running_var.inner().node()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchNorm;
+    use crate as mu;
+    use crate::nn::Module;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn batch_norm_forward_backward() {
+        let layer = BatchNorm::<1>::new(0.5);
+        let x = mu::custom::<4, 1, 1, 1>(&[1.0, 2.0, 3.0, 4.0]);
+
+        let z = layer.forward(&x);
+        let std = (1.25 + super::EPSILON).sqrt();
+        assert!(equal_data(
+            z.data(),
+            Array::new(
+                &[-1.5 / std, -0.5 / std, 0.5 / std, 1.5 / std],
+                dim4!(1, 1, 1, 4)
+            )
+        ));
+
+        // The outputs sum to zero whatever the input, so do the gradients of the input
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(0.0; 1,1,1,4)
+        ));
+
+        // Half way from the initial statistics to the mean 2.5 and unbiased variance 5/3
+        let z = layer.infer(&x);
+        let std = ((1.0 + 5.0 / 3.0) / 2.0 + super::EPSILON).sqrt();
+        assert!(equal_data(
+            z.data(),
+            Array::new(
+                &[
+                    (1.0 - 1.25) / std,
+                    (2.0 - 1.25) / std,
+                    (3.0 - 1.25) / std,
+                    (4.0 - 1.25) / std
+                ],
+                dim4!(1, 1, 1, 4)
+            )
+        ));
+        assert_eq!(layer.parameters().len(), 2);
+
+        // The running statistics are stored along with the parameters, but not trained
+        let names: Vec<String> = layer
+            .named_parameters()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["gamma", "beta"]);
+        let state = layer.state_dict();
+        assert_eq!(state.len(), 4);
+        assert!(state.contains_key("running_var"));
+    }
+}
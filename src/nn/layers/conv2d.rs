@@ -1,45 +1,121 @@
 use crate::{
-    graph::node::Node,
+    gen::engine,
+    graph::{node::Node, shared::Shared},
+    nn::{
+        init::Init,
+        quantize::{Calibration, QuantizedConv2D},
+        Module,
+    },
+    profiler,
     tensor::{
         constant::Constant,
         traits::{Data, Pair, Tensed},
         variable::Variable,
-        Tensor,
+        Float, Tensor,
     },
 };
-use arrayfire::{dim4, Array, ConvGradientType};
-use std::rc::Rc;
+use arrayfire::{dim4, Array, ConvGradientType, Dim4};
 
 /// A 2 dimensional convolutional layer with `I` input channels, `O` output channels and `H` height and `W` width kernel size
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        transparent,
+        bound(serialize = "", deserialize = "T: From<Array<Float>>")
+    )
+)]
 pub struct Conv2D<const I: u64, const O: u64, const H: u64, const W: u64, T: Data = Variable>(
     Tensor<O, I, H, W, T>,
 );
 
 impl<const I: u64, const O: u64, const H: u64, const W: u64, T: Data> Conv2D<I, O, H, W, T> {
+    /// Fails to compile unless the kernel has an odd height and width, which `forward_same`
+    /// requires to pad the input evenly on both sides
+    const ODD_KERNEL: () = assert!(
+        H % 2 == 1 && W % 2 == 1,
+        "same padding requires an odd kernel size"
+    );
+
     /// Given an input computes the output
     #[inline]
-    pub fn forward<X: Tensed<CHANNELS = { I }>>(
+    pub fn forward<const B: u64, const XH: u64, const XW: u64, X: Data + Pair<T>>(
+        &self,
+        x: &Tensor<B, I, XH, XW, X>,
+    ) -> Tensor<B, O, { XH - H + 1 }, { XW - W + 1 }, <X as Pair<T>>::Output> {
+        self.convolve::<B, XH, XW, { XH - H + 1 }, { XW - W + 1 }, 1, false, X>(x)
+    }
+
+    /// Given an input computes the output, zero padding the input so that the output keeps
+    /// its height and width. Even kernel sizes are rejected at compile time.
+    #[inline]
+    pub fn forward_same<const B: u64, const XH: u64, const XW: u64, X: Data + Pair<T>>(
+        &self,
+        x: &Tensor<B, I, XH, XW, X>,
+    ) -> Tensor<B, O, XH, XW, <X as Pair<T>>::Output> {
+        let () = Self::ODD_KERNEL;
+        self.convolve::<B, XH, XW, XH, XW, 1, true, X>(x)
+    }
+
+    /// Given an input computes the output as `forward_same` does, but moving the kernel `S`
+    /// values at a time, which divides the height and width of the input by `S`
+    #[inline]
+    pub fn forward_strided<
+        const S: u64,
+        const B: u64,
+        const XH: u64,
+        const XW: u64,
+        X: Data + Pair<T>,
+    >(
+        &self,
+        x: &Tensor<B, I, XH, XW, X>,
+    ) -> Tensor<B, O, { (XH - 1) / S + 1 }, { (XW - 1) / S + 1 }, <X as Pair<T>>::Output> {
+        let () = Self::ODD_KERNEL;
+        self.convolve::<B, XH, XW, { (XH - 1) / S + 1 }, { (XW - 1) / S + 1 }, S, true, X>(x)
+    }
+
+    /// Returns the layer with its kernel quantized to 8 bit integers, to take inputs in the
+    /// range observed by the given calibration, see the `quantize` module
+    #[must_use]
+    #[inline]
+    pub fn quantize(&self, calibration: &Calibration) -> QuantizedConv2D<I, O, H, W> {
+        QuantizedConv2D::new(&self.0.data(), calibration.scale())
+    }
+
+    /// Returns half the kernel size if `same`, zero otherwise
+    fn padding(same: bool) -> Dim4 {
+        if same {
+            dim4!((H - 1) / 2, (W - 1) / 2)
+        } else {
+            dim4!(0, 0)
+        }
+    }
+
+    /// Convolves the input with the layer kernel moved `S` values at a time, zero padding it by
+    /// half the kernel size if `SAME`
+    fn convolve<
+        const B: u64,
+        const XH: u64,
+        const XW: u64,
+        const YH: u64,
+        const YW: u64,
+        const S: u64,
+        const SAME: bool,
+        X: Data + Pair<T>,
+    >(
         &self,
-        x: &X,
-    ) -> Tensor<
-        { X::BATCH },
-        O,
-        { X::HEIGHT - H + 1 },
-        { X::WIDTH - W + 1 },
-        <X::Data as Pair<T>>::Output,
-    >
-    where
-        <X as Tensed>::Data: Pair<T>,
-    {
+        x: &Tensor<B, I, XH, XW, X>,
+    ) -> Tensor<B, O, YH, YW, <X as Pair<T>>::Output> {
+        let _op = profiler::forward("conv2d");
         let result = arrayfire::convolve2_nn(
             &x.data(),
             &self.0.data(),
-            dim4!(1, 1),
-            dim4!(0, 0),
+            dim4!(S, S),
+            Self::padding(SAME),
             dim4!(1, 1),
         );
 
-        let reverse = |df: &Array<f32>, args: &[Array<f32>]| {
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
             let (a, k, out) = (&args[0], &args[1], &args[2]);
             (
                 arrayfire::convolve2_gradient_nn(
@@ -47,8 +123,8 @@ impl<const I: u64, const O: u64, const H: u64, const W: u64, T: Data> Conv2D<I,
                     a,
                     k,
                     out,
-                    dim4!(1, 1),
-                    dim4!(0, 0),
+                    dim4!(S, S),
+                    Self::padding(SAME),
                     dim4!(1, 1),
                     ConvGradientType::DATA,
                 ),
@@ -57,8 +133,8 @@ impl<const I: u64, const O: u64, const H: u64, const W: u64, T: Data> Conv2D<I,
                     a,
                     k,
                     out,
-                    dim4!(1, 1),
-                    dim4!(0, 0),
+                    dim4!(S, S),
+                    Self::padding(SAME),
                     dim4!(1, 1),
                     ConvGradientType::FILTER,
                 ),
@@ -69,20 +145,33 @@ impl<const I: u64, const O: u64, const H: u64, const W: u64, T: Data> Conv2D<I,
             &self.0,
             result.clone(),
             reverse,
-            &[x.data(), self.0.data(), result],
+            vec![x.data(), self.0.data(), result],
         )
     }
 }
 
 impl<const I: u64, const O: u64, const H: u64, const W: u64> Conv2D<I, O, H, W, Variable> {
-    /// Returns a new `Conv2D` layer with its weights and biases taken from a normal
-    /// distribution with mean 0 and standard deviation 1
+    /// Returns a new `Conv2D` layer with its kernels taken from a normal distribution with
+    /// mean 0 and standard deviation 1. The layer has no biases
     #[must_use]
     #[inline]
     pub fn randn() -> Self {
         Self(crate::randn())
     }
 
+    /// Returns a builder to configure how the kernels of a new layer are initialized.
+    ///
+    /// The padding is not part of the configuration, as the output shape is checked at compile
+    /// time: it is chosen on every call instead, with `forward` or `forward_same`
+    #[must_use]
+    #[inline]
+    pub const fn builder() -> Conv2DBuilder<I, O, H, W> {
+        Conv2DBuilder {
+            init: Init::Normal,
+            seed: None,
+        }
+    }
+
     /// Consumes this layer and returns a copy with constant parameters
     #[must_use]
     #[inline]
@@ -93,11 +182,59 @@ impl<const I: u64, const O: u64, const H: u64, const W: u64> Conv2D<I, O, H, W,
     /// Returns the layer's trainable parameters
     #[must_use]
     #[inline]
-    pub fn parameters(&self) -> Rc<Node> {
+    pub fn parameters(&self) -> Shared<Node> {
         self.0.inner().node()
     }
 }
 
+/// Configures a new `Conv2D` layer, see `Conv2D::builder`
+#[derive(Clone, Copy, Debug)]
+pub struct Conv2DBuilder<const I: u64, const O: u64, const H: u64, const W: u64> {
+    init: Init,
+    seed: Option<u64>,
+}
+
+impl<const I: u64, const O: u64, const H: u64, const W: u64> Conv2DBuilder<I, O, H, W> {
+    /// Initializes the kernels with the given scheme, a unit normal distribution by default
+    #[must_use]
+    #[inline]
+    pub const fn init(self, init: Init) -> Self {
+        Self { init, ..self }
+    }
+
+    /// Takes the initial kernels from a random number generator seeded with the given value
+    /// instead of the global one, see `mushin::seed`
+    #[must_use]
+    #[inline]
+    pub const fn seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Returns the configured layer
+    #[must_use]
+    #[inline]
+    pub fn build(self) -> Conv2D<I, O, H, W, Variable> {
+        let engine = self.seed.map(engine);
+        let kernels = self
+            .init
+            .weights(dim4!(H, W, I, O), I * H * W, O * H * W, engine.as_ref());
+        Conv2D(Variable::from(kernels).into())
+    }
+}
+
+impl<const I: u64, const O: u64, const H: u64, const W: u64> Module
+    for Conv2D<I, O, H, W, Variable>
+{
+    /// The kernels are named `kernels`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("kernels"), self.parameters())]
+    }
+}
+
 impl<const I: u64, const O: u64, const H: u64, const W: u64> Conv2D<I, O, H, W, Constant> {
     /// Consumes this layer and returns a copy with trainable parameters
     #[must_use]
@@ -128,11 +265,29 @@ mod tests {
             arrayfire::constant!(1.0; 1, 1, 1, 1)
         ));
         assert!(equal_data(
-            conv2d.parameters().grad().clone(),
+            conv2d.parameters().grad(),
             arrayfire::constant!(0.5; 1, 1, 1, 1)
         ));
     }
 
+    #[test]
+    fn conv2d_forward_same() {
+        let conv2d = Conv2D::<1, 1, 3, 3>(mu::fill(1.0));
+        let x = mu::fill::<1, 1, 3, 3>(1.0);
+
+        let z = conv2d.forward_same(&x);
+        assert!(equal_data(
+            z.data(),
+            mu::custom::<1, 1, 3, 3>(&[4.0, 6.0, 4.0, 6.0, 9.0, 6.0, 4.0, 6.0, 4.0]).data()
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            mu::custom::<1, 1, 3, 3>(&[4.0, 6.0, 4.0, 6.0, 9.0, 6.0, 4.0, 6.0, 4.0]).data()
+        ));
+    }
+
     #[test]
     fn conv2d_freeze_unfreeze() {
         let conv2d = Conv2D::<3, 5, 2, 2>::randn();
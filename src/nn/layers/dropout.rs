@@ -1,8 +1,12 @@
-use crate::tensor::{
-    constant::Constant,
-    traits::{Data, Tensed},
-    variable::Variable,
-    Tensor,
+use crate::{
+    graph::node::Tangent,
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
 };
 use arrayfire::Array;
 use std::marker::PhantomData;
@@ -10,30 +14,34 @@ use std::marker::PhantomData;
 /// A Dropout neural network layer.
 /// During training mode (`Dropout<Variable>`) the layer will set values
 /// to zero with the given probability. Otherwise it does nothing.
-pub struct Dropout<T: Data = Variable>(f32, PhantomData<T>);
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(serialize = "", deserialize = ""))
+)]
+pub struct Dropout<T: Data = Variable>(Float, PhantomData<T>);
 
 impl<T: Data> Dropout<T> {
     #[must_use]
     #[inline]
-    pub fn prob(probability: f32) -> Self {
+    pub fn prob(probability: Float) -> Self {
         Self(probability, PhantomData::default())
     }
 }
 
 impl Dropout<Variable> {
     #[inline]
-    pub fn forward<X: Tensed>(
+    pub fn forward<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
         &self,
-        x: &X,
-    ) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, X::Data> {
-        let mask = arrayfire::gt(
-            &arrayfire::randu!(X::HEIGHT, X::WIDTH, X::CHANNELS, X::BATCH),
-            &self.0,
-            false,
-        ) / (1.0 - self.0);
+        x: &Tensor<B, C, H, W, X>,
+    ) -> Tensor<B, C, H, W, X> {
+        let _op = profiler::forward("dropout");
+        let mask =
+            arrayfire::gt(&arrayfire::randu!(Float; H, W, C, B), &self.0, false) / (1.0 - self.0);
 
-        let reverse = |df: &Array<f32>, args: &[Array<f32>]| df * &args[0];
-        x.push_unary(arrayfire::mul(&x.data(), &mask, false), reverse, &[mask])
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| df * &args[0];
+        x.push_unary(arrayfire::mul(&x.data(), &mask, false), reverse, vec![mask])
+            .with_tangent(Tangent::Unary(reverse))
     }
 
     #[must_use]
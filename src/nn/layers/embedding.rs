@@ -0,0 +1,258 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::Module,
+    ops::{mm, transpose},
+    profiler,
+    tensor::{
+        traits::{Data, Pair, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array, MatProp, SparseFormat};
+
+/// How the embeddings of a bag are combined by an `EmbeddingBag` layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BagMode {
+    /// Sums the embeddings of the bag
+    Sum,
+    /// Averages the embeddings of the bag
+    Mean,
+}
+
+/// An embedding layer for bags of indices, returning the sum or mean of the `D` sized
+/// embeddings of every bag out of a table of `V` rows.
+///
+/// Bags are combined in a single operation, without computing the embedding of every index,
+/// and the gradients of the table are only written to the rows of the indices, i.e. for
+/// bag-of-words or recommendation models
+pub struct EmbeddingBag<const V: u64, const D: u64> {
+    table: Tensor<1, 1, V, D, Variable>,
+    mode: BagMode,
+}
+
+impl<const V: u64, const D: u64> EmbeddingBag<V, D> {
+    /// Returns a new `EmbeddingBag` layer averaging the embeddings of every bag, with its
+    /// table taken from a normal distribution with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            table: crate::randn(),
+            mode: BagMode::Mean,
+        }
+    }
+
+    /// Returns a new `EmbeddingBag` layer averaging the embeddings of every bag, with the
+    /// given table. The table is shared, not copied, see `TiedProjection`
+    #[must_use]
+    #[inline]
+    pub fn from_table(table: &Tensor<1, 1, V, D, Variable>) -> Self {
+        Self {
+            table: table.clone(),
+            mode: BagMode::Mean,
+        }
+    }
+
+    /// Returns the embedding table, sharing its node with this layer so that it can be tied
+    /// to other layers
+    #[must_use]
+    #[inline]
+    pub fn table(&self) -> Tensor<1, 1, V, D, Variable> {
+        self.table.clone()
+    }
+
+    /// Sets how the embeddings of every bag are combined
+    #[must_use]
+    #[inline]
+    pub fn mode(self, mode: BagMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Given `B` bags of indices computes their combined embeddings. Empty bags have zero
+    /// embeddings
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `B` bags or an index is not below `V`
+    #[must_use]
+    #[inline]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_precision_loss
+    )]
+    pub fn forward<const B: u64>(&self, bags: &[&[u64]]) -> Tensor<B, 1, 1, D, Variable> {
+        let _op = profiler::forward("embedding_bag");
+        assert_eq!(bags.len() as u64, B, "expected {B} bags");
+
+        // Every row of the sparse matrix weights the indices of a bag
+        let mut offsets = vec![0_i32];
+        let (mut columns, mut weights) = (Vec::new(), Vec::new());
+        for bag in bags {
+            let weight = match self.mode {
+                BagMode::Sum => 1.0,
+                BagMode::Mean => (1.0 as Float) / bag.len() as Float,
+            };
+            for &index in *bag {
+                assert!(index < V, "index {index} is out of a table of {V} rows");
+                columns.push(index as i32);
+                weights.push(weight);
+            }
+            offsets.push(columns.len() as i32);
+        }
+        let bagging = arrayfire::sparse_from_host(
+            B,
+            V,
+            columns.len() as u64,
+            &weights,
+            &offsets,
+            &columns,
+            SparseFormat::CSR,
+        );
+
+        let embeddings = arrayfire::matmul(
+            &bagging,
+            &arrayfire::moddims(&self.table.data(), dim4!(V, D)),
+            MatProp::NONE,
+            MatProp::NONE,
+        );
+
+        // The gradients of every bag are scattered to the rows of its indices
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let grad = arrayfire::transpose(&arrayfire::moddims(df, dim4!(D, B)), false);
+            arrayfire::matmul(&args[0], &grad, MatProp::TRANS, MatProp::NONE)
+        };
+
+        self.table.push_unary(
+            arrayfire::moddims(&arrayfire::transpose(&embeddings, false), dim4!(1, D, 1, B)),
+            reverse,
+            vec![bagging],
+        )
+    }
+
+    /// Returns the layer's trainable parameters, the embedding table
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Shared<Node> {
+        self.table.inner().node()
+    }
+}
+
+impl<const V: u64, const D: u64> Module for EmbeddingBag<V, D> {
+    /// The embedding table is named `table`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("table"), self.parameters())]
+    }
+}
+
+/// An output projection scoring `D` sized vectors against every row of a table of `V`
+/// embeddings, i.e. the logits of the next token of a language model.
+///
+/// The table is shared with the layer it is taken from, usually an `EmbeddingBag`, so its
+/// gradients accumulate the ones of both layers and optimizers update it once
+pub struct TiedProjection<const V: u64, const D: u64>(Tensor<1, 1, V, D, Variable>);
+
+impl<const V: u64, const D: u64> TiedProjection<V, D> {
+    /// Returns a new `TiedProjection` layer sharing the given table
+    #[must_use]
+    #[inline]
+    pub fn new(table: &Tensor<1, 1, V, D, Variable>) -> Self {
+        Self(table.clone())
+    }
+
+    /// Given a batch of vectors computes their dot products with every row of the table
+    #[inline]
+    pub fn forward<const B: u64, X: Data + Pair<Variable>>(
+        &self,
+        x: &Tensor<B, 1, 1, D, X>,
+    ) -> Tensor<B, 1, 1, V, <X as Pair<Variable>>::Output> {
+        mm(x, &transpose(&self.0))
+    }
+
+    /// Returns the layer's trainable parameters, the shared table
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Shared<Node> {
+        self.0.inner().node()
+    }
+}
+
+impl<const V: u64, const D: u64> Module for TiedProjection<V, D> {
+    /// The shared table is named `table`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("table"), self.parameters())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BagMode, EmbeddingBag, TiedProjection};
+    use crate as mu;
+    use crate::nn::optimizers::{Optimizer, SGD};
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn embedding_bag_forward_backward() {
+        let layer = EmbeddingBag::<3, 2> {
+            // The rows are (1, 2), (3, 4) and (5, 6)
+            table: mu::custom(&[1.0, 3.0, 5.0, 2.0, 4.0, 6.0]),
+            mode: BagMode::Mean,
+        };
+        let z = layer.forward::<2>(&[&[0, 2], &[]]);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[3.0, 4.0, 0.0, 0.0], dim4!(1, 2, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            layer.parameters().grad(),
+            Array::new(&[0.5, 0.0, 0.5, 0.5, 0.0, 0.5], dim4!(3, 2, 1, 1))
+        ));
+
+        // Repeated indices accumulate their gradients
+        let layer = layer.mode(BagMode::Sum);
+        layer.parameters().zero_grad();
+        let z = layer.forward::<1>(&[&[1, 1, 2]]);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[11.0, 14.0], dim4!(1, 2, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            layer.parameters().grad(),
+            Array::new(&[0.0, 2.0, 1.0, 0.0, 2.0, 1.0], dim4!(3, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn tied_projection_shares_table() {
+        // The rows are (1, 2), (3, 4) and (5, 6)
+        let layer = EmbeddingBag::<3, 2>::from_table(&mu::custom(&[1.0, 3.0, 5.0, 2.0, 4.0, 6.0]))
+            .mode(BagMode::Sum);
+        let projection = TiedProjection::new(&layer.table());
+
+        let z = projection.forward(&mu::fill::<1, 1, 1, 2>(1.0).freeze());
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[3.0, 7.0, 11.0], dim4!(1, 3, 1, 1))
+        ));
+
+        // The gradients of both layers accumulate into the single table
+        z.backward();
+        layer.forward::<1>(&[&[0]]).backward();
+        assert!(equal_data(
+            projection.parameters().grad(),
+            Array::new(&[2.0, 1.0, 1.0, 2.0, 1.0, 1.0], dim4!(3, 2, 1, 1))
+        ));
+
+        let optim = SGD::new(&[layer.parameters(), projection.parameters()], 0.1);
+        assert_eq!(optim.parameters().len(), 1);
+    }
+}
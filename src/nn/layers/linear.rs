@@ -1,14 +1,23 @@
 use crate::{
-    graph::node::Node,
+    gen::engine,
+    graph::{
+        node::{Node, Tangent},
+        shared::Shared,
+    },
+    nn::{
+        init::Init,
+        quantize::{Calibration, QuantizedLinear},
+        Module,
+    },
+    profiler,
     tensor::{
         constant::Constant,
         traits::{Data, Pair, Tensed},
         variable::Variable,
-        Tensor,
+        Float, Tensor,
     },
 };
-use arrayfire::{seq, view, Array, MatProp};
-use std::rc::Rc;
+use arrayfire::{dim4, seq, view, Array, MatProp};
 
 /// A Linear (perceptron) neural network layer with `I` input size and `O` output size
 #[allow(clippy::cast_possible_truncation)]
@@ -23,16 +32,14 @@ where
 {
     /// Given an input computes the output
     #[inline]
-    pub fn forward<X: Tensed<CHANNELS = 1, HEIGHT = 1, WIDTH = { I }>>(
+    pub fn forward<const B: u64, X: Data + Pair<T>>(
         &self,
-        x: &X,
-    ) -> Tensor<{ X::BATCH }, 1, 1, O, <X::Data as Pair<T>>::Output>
-    where
-        <X as Tensed>::Data: Pair<T>,
-    {
-        let padded = arrayfire::join(1, &x.data(), &arrayfire::constant!(1.0; 1, 1, 1, X::BATCH));
+        x: &Tensor<B, 1, 1, I, X>,
+    ) -> Tensor<B, 1, 1, O, <X as Pair<T>>::Output> {
+        let _op = profiler::forward("linear");
+        let padded = arrayfire::join(1, &x.data(), &arrayfire::constant!(1.0; 1, 1, 1, B));
 
-        let reverse = |df: &Array<f32>, args: &[Array<f32>]| {
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
             let a = arrayfire::matmul(
                 df,
                 &args[1],
@@ -40,11 +47,15 @@ where
                 arrayfire::MatProp::TRANS,
             );
 
-            let b = arrayfire::matmul(
-                &args[0],
-                df,
-                arrayfire::MatProp::TRANS,
-                arrayfire::MatProp::NONE,
+            // The weights are shared by the whole batch, so are the contributions to their gradient
+            let b = arrayfire::sum(
+                &arrayfire::matmul(
+                    &args[0],
+                    df,
+                    arrayfire::MatProp::TRANS,
+                    arrayfire::MatProp::NONE,
+                ),
+                3,
             );
 
             let all = seq!();
@@ -55,8 +66,24 @@ where
             &self.0,
             arrayfire::matmul(&padded, &self.0.data(), MatProp::NONE, MatProp::NONE),
             reverse,
-            &[padded, self.0.data()],
+            vec![padded, self.0.data()],
         )
+        .with_tangent(Tangent::Binary(
+            |da, args| {
+                // The padding is constant, so its tangent is zero
+                let tangent = arrayfire::join(1, da, &arrayfire::constant!(0.0; 1, 1, 1, B));
+                arrayfire::matmul(&tangent, &args[1], MatProp::NONE, MatProp::NONE)
+            },
+            |db, args| arrayfire::matmul(&args[0], db, MatProp::NONE, MatProp::NONE),
+        ))
+    }
+
+    /// Returns the layer with its weights quantized to 8 bit integers, to take inputs in the
+    /// range observed by the given calibration, see the `quantize` module
+    #[must_use]
+    #[inline]
+    pub fn quantize(&self, calibration: &Calibration) -> QuantizedLinear<I, O> {
+        QuantizedLinear::new(&self.0.data(), calibration.scale())
     }
 }
 
@@ -73,6 +100,16 @@ where
         Self(crate::randn())
     }
 
+    /// Returns a builder to configure how the parameters of a new layer are initialized
+    #[must_use]
+    #[inline]
+    pub const fn builder() -> LinearBuilder<I, O> {
+        LinearBuilder {
+            init: Init::Normal,
+            seed: None,
+        }
+    }
+
     /// Consumes this layer and returns it with constant (not trainable) parameters
     #[must_use]
     #[inline]
@@ -83,11 +120,64 @@ where
     /// Get the layer's trainable parameters
     #[must_use]
     #[inline]
-    pub fn parameters(&self) -> Rc<Node> {
+    pub fn parameters(&self) -> Shared<Node> {
         self.0.inner().node()
     }
 }
 
+/// Configures a new `Linear` layer, see `Linear::builder`
+#[derive(Clone, Copy, Debug)]
+pub struct LinearBuilder<const I: u64, const O: u64> {
+    init: Init,
+    seed: Option<u64>,
+}
+
+impl<const I: u64, const O: u64> LinearBuilder<I, O> {
+    /// Initializes the parameters with the given scheme, a unit normal distribution by default
+    #[must_use]
+    #[inline]
+    pub const fn init(self, init: Init) -> Self {
+        Self { init, ..self }
+    }
+
+    /// Takes the initial parameters from a random number generator seeded with the given
+    /// value instead of the global one, see `mushin::seed`
+    #[must_use]
+    #[inline]
+    pub const fn seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Returns the configured layer
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn build(self) -> Linear<I, O, Variable>
+    where
+        [(); (I + 1) as usize]:,
+    {
+        let engine = self.seed.map(engine);
+        let weights = self.init.weights(dim4!(I, O), I, O, engine.as_ref());
+        let biases = self.init.biases(dim4!(1, O), engine.as_ref());
+        Linear(Variable::from(arrayfire::join(0, &weights, &biases)).into())
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+impl<const I: u64, const O: u64> Module for Linear<I, O, Variable>
+where
+    [(); (I + 1) as usize]:,
+{
+    /// The weights and biases are named `weights`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("weights"), self.parameters())]
+    }
+}
+
 #[allow(clippy::cast_possible_truncation)]
 impl<const I: u64, const O: u64> Linear<I, O, Constant>
 where
@@ -101,6 +191,31 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+#[allow(clippy::cast_possible_truncation)]
+impl<const I: u64, const O: u64, T: Data> serde::Serialize for Linear<I, O, T>
+where
+    [(); (I + 1) as usize]:,
+{
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[allow(clippy::cast_possible_truncation)]
+impl<'de, const I: u64, const O: u64, T: Data + From<Array<Float>>> serde::Deserialize<'de>
+    for Linear<I, O, T>
+where
+    [(); (I + 1) as usize]:,
+{
+    #[inline]
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Tensor::deserialize(deserializer).map(Self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Linear;
@@ -123,7 +238,7 @@ mod tests {
             arrayfire::constant!(5.0; 1, 3, 1, 1)
         ));
         assert!(equal_data(
-            linear.parameters().grad().clone(),
+            linear.parameters().grad(),
             Array::new(
                 &[
                     0.5, 0.5, 0.5, 1.0, 0.5, 0.5, 0.5, 1.0, 0.5, 0.5, 0.5, 1.0, 0.5, 0.5, 0.5, 1.0,
@@ -1,7 +1,17 @@
+mod attention;
+mod batchnorm;
 mod conv2d;
 mod dropout;
+mod embedding;
 mod linear;
+mod resnet;
+mod vq;
 
-pub use conv2d::Conv2D;
+pub use attention::{AttentionPool, LocalAttention};
+pub use batchnorm::BatchNorm;
+pub use conv2d::{Conv2D, Conv2DBuilder};
 pub use dropout::Dropout;
-pub use linear::Linear;
+pub use embedding::{BagMode, EmbeddingBag, TiedProjection};
+pub use linear::{Linear, LinearBuilder};
+pub use resnet::ResNetBlock;
+pub use vq::{Quantized, VectorQuantizer};
@@ -0,0 +1,228 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::{
+        activations::relu,
+        layers::{BatchNorm, Conv2D},
+        module::{scoped, scoped_buffers, Module},
+    },
+    ops::{add, reshape},
+    tensor::{
+        traits::{Data, Pair},
+        variable::Variable,
+        Tensor,
+    },
+};
+
+/// Momentum of the running statistics of the batch normalization layers of a block
+const MOMENTUM: crate::tensor::Float = 0.1;
+
+/// The projection shortcut of a `ResNetBlock`: a batch normalized 1x1 convolution with the
+/// stride of the block, matching the input to the channels and size of the output
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Downsample<const I: u64, const O: u64> {
+    conv: Conv2D<I, O, 1, 1>,
+    bn: BatchNorm<O>,
+}
+
+impl<const I: u64, const O: u64> Module for Downsample<I, O> {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [scoped("conv", &self.conv), scoped("bn", &self.bn)].concat()
+    }
+
+    #[inline]
+    fn named_buffers(&self) -> Vec<(String, Shared<Node>)> {
+        scoped_buffers("bn", &self.bn)
+    }
+}
+
+/// A residual basic block from `I` to `O` channels with a stride of `S`.
+///
+/// Two 3x3 padded convolutions, each followed by batch normalization, with a `ReLu` in
+/// between. The first one moves `S` values at a time, dividing the height and width of the
+/// input by `S`.
+///
+/// The input is added to the result before the final `ReLu`. When the block changes the
+/// channels or the size of the input, the input goes through a `Downsample` projection first
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResNetBlock<const I: u64, const O: u64, const S: u64 = 1> {
+    conv1: Conv2D<I, O, 3, 3>,
+    bn1: BatchNorm<O>,
+    conv2: Conv2D<O, O, 3, 3>,
+    bn2: BatchNorm<O>,
+    downsample: Option<Downsample<I, O>>,
+}
+
+impl<const I: u64, const O: u64, const S: u64> ResNetBlock<I, O, S> {
+    /// Returns a new `ResNetBlock` with its convolution kernels taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            conv1: Conv2D::randn(),
+            bn1: BatchNorm::new(MOMENTUM),
+            conv2: Conv2D::randn(),
+            bn2: BatchNorm::new(MOMENTUM),
+            downsample: (I != O || S != 1).then(|| Downsample {
+                conv: Conv2D::randn(),
+                bn: BatchNorm::new(MOMENTUM),
+            }),
+        }
+    }
+
+    /// Given an input computes the output, normalizing with the statistics of the batch
+    #[inline]
+    pub fn forward<
+        const B: u64,
+        const H: u64,
+        const W: u64,
+        X: Data + Pair<Variable, Output = Variable>,
+    >(
+        &self,
+        x: &Tensor<B, I, H, W, X>,
+    ) -> Tensor<B, O, { (H - 1) / S + 1 }, { (W - 1) / S + 1 }, Variable> {
+        self.apply(x, true)
+    }
+
+    /// Given an input computes the output, normalizing with the running statistics
+    #[inline]
+    pub fn infer<
+        const B: u64,
+        const H: u64,
+        const W: u64,
+        X: Data + Pair<Variable, Output = Variable>,
+    >(
+        &self,
+        x: &Tensor<B, I, H, W, X>,
+    ) -> Tensor<B, O, { (H - 1) / S + 1 }, { (W - 1) / S + 1 }, Variable> {
+        self.apply(x, false)
+    }
+
+    /// Returns the block's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        let mut params = vec![self.conv1.parameters()];
+        params.extend(self.bn1.parameters());
+        params.push(self.conv2.parameters());
+        params.extend(self.bn2.parameters());
+        if let Some(ref downsample) = self.downsample {
+            params.push(downsample.conv.parameters());
+            params.extend(downsample.bn.parameters());
+        }
+        params
+    }
+
+    /// Computes the output, normalizing with the statistics of the batch if `training`
+    fn apply<
+        const B: u64,
+        const H: u64,
+        const W: u64,
+        X: Data + Pair<Variable, Output = Variable>,
+    >(
+        &self,
+        x: &Tensor<B, I, H, W, X>,
+        training: bool,
+    ) -> Tensor<B, O, { (H - 1) / S + 1 }, { (W - 1) / S + 1 }, Variable> {
+        let norm =
+            |bn: &BatchNorm<O>,
+             y: &Tensor<B, O, { (H - 1) / S + 1 }, { (W - 1) / S + 1 }, Variable>| {
+                if training {
+                    bn.forward(y)
+                } else {
+                    bn.infer(y)
+                }
+            };
+
+        let residual = relu(&norm(
+            &self.bn1,
+            &self.conv1.forward_strided::<S, B, H, W, X>(x),
+        ));
+        let residual = norm(&self.bn2, &self.conv2.forward_same(&residual));
+        // Without a projection the block keeps the channels and size of the input
+        let output = self.downsample.as_ref().map_or_else(
+            || add(&reshape(x), &residual),
+            |downsample| {
+                let shortcut = downsample.conv.forward_strided::<S, B, H, W, X>(x);
+                add(&norm(&downsample.bn, &shortcut), &residual)
+            },
+        );
+        relu(&output)
+    }
+}
+
+impl<const I: u64, const O: u64, const S: u64> Module for ResNetBlock<I, O, S> {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        let mut params = [
+            scoped("conv1", &self.conv1),
+            scoped("bn1", &self.bn1),
+            scoped("conv2", &self.conv2),
+            scoped("bn2", &self.bn2),
+        ]
+        .concat();
+        if let Some(ref downsample) = self.downsample {
+            params.extend(scoped("downsample", downsample));
+        }
+        params
+    }
+
+    #[inline]
+    fn named_buffers(&self) -> Vec<(String, Shared<Node>)> {
+        let mut buffers = [
+            scoped_buffers("bn1", &self.bn1),
+            scoped_buffers("bn2", &self.bn2),
+        ]
+        .concat();
+        if let Some(ref downsample) = self.downsample {
+            buffers.extend(scoped_buffers("downsample", downsample));
+        }
+        buffers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResNetBlock;
+    use crate as mu;
+    use crate::nn::Module;
+    use crate::tensor::traits::Tensed;
+
+    #[test]
+    fn resnet_block_forward_backward() {
+        let block = ResNetBlock::<3, 3>::randn();
+        let x = mu::randn::<2, 3, 8, 8>();
+
+        let z = block.forward(&x);
+        assert_eq!(z.data().dims(), arrayfire::dim4!(8, 8, 3, 2));
+
+        z.backward();
+        assert_eq!(x.grad().data().dims(), arrayfire::dim4!(8, 8, 3, 2));
+        assert_eq!(block.parameters().len(), 6);
+        assert_eq!(block.named_parameters().len(), 6);
+        assert_eq!(block.named_buffers().len(), 4);
+    }
+
+    #[test]
+    fn resnet_block_downsample() {
+        let block = ResNetBlock::<3, 8, 2>::randn();
+        let x = mu::randn::<2, 3, 8, 8>();
+
+        let z = block.forward(&x);
+        assert_eq!(z.data().dims(), arrayfire::dim4!(4, 4, 8, 2));
+        assert_eq!(block.infer(&x).data().dims(), arrayfire::dim4!(4, 4, 8, 2));
+
+        z.backward();
+        assert_eq!(x.grad().data().dims(), arrayfire::dim4!(8, 8, 3, 2));
+        assert_eq!(block.parameters().len(), 9);
+        assert!(block
+            .named_parameters()
+            .iter()
+            .any(|param| param.0 == "downsample.conv.kernels"));
+        assert!(block
+            .named_buffers()
+            .iter()
+            .any(|buffer| buffer.0 == "downsample.bn.running_var"));
+    }
+}
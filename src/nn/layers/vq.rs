@@ -0,0 +1,184 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::Module,
+    profiler,
+    tensor::{
+        traits::{Data, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array, MatProp};
+
+/// A vector quantization layer, as in VQ-VAE, replacing every input by the nearest of a
+/// codebook of `K` vectors of size `D`.
+///
+/// The gradients of the quantized values are copied to the inputs (straight-through
+/// estimator), and the codebook is learnt from the loss terms returned along with them
+pub struct VectorQuantizer<const K: u64, const D: u64> {
+    codebook: Tensor<1, 1, K, D, Variable>,
+    commitment: Float,
+}
+
+/// The output of a `VectorQuantizer` for a batch of `B` inputs of size `D`
+pub struct Quantized<const B: u64, const D: u64, X: Data> {
+    /// The codebook vectors nearest to the inputs, whose gradients flow to the inputs
+    pub values: Tensor<B, 1, 1, D, X>,
+    /// The index in the codebook of the vector nearest to every input
+    pub indices: Vec<u64>,
+    /// The squared distance of every chosen vector to its input, averaged over its values.
+    /// Its gradients move the codebook towards the inputs only
+    pub codebook_loss: Tensor<B, 1, 1, 1, Variable>,
+    /// The squared distance of every input to its chosen vector, averaged over its values and
+    /// scaled by the commitment cost. Its gradients move the inputs towards the codebook only
+    pub commitment_loss: Tensor<B, 1, 1, 1, X>,
+}
+
+impl<const K: u64, const D: u64> VectorQuantizer<K, D> {
+    /// Returns a new `VectorQuantizer` with its codebook taken from a normal distribution
+    /// with mean 0 and standard deviation 1, and a commitment cost of 0.25
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            codebook: crate::randn(),
+            commitment: 0.25,
+        }
+    }
+
+    /// Sets the weight of the commitment loss, relative to the codebook loss
+    #[must_use]
+    #[inline]
+    pub fn commitment(self, cost: Float) -> Self {
+        Self {
+            commitment: cost,
+            ..self
+        }
+    }
+
+    /// Given an input returns its quantized values, along with the loss terms to train the
+    /// codebook and the layers computing the inputs
+    #[inline]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn forward<const B: u64, X: Data>(&self, x: &Tensor<B, 1, 1, D, X>) -> Quantized<B, D, X> {
+        let _op = profiler::forward("vector_quantizer");
+        // Every row holds an input or a codebook vector
+        let inputs = arrayfire::transpose(&arrayfire::moddims(&x.data(), dim4!(D, B)), false);
+        let codebook = arrayfire::moddims(&self.codebook.data(), dim4!(K, D));
+
+        // The squared distances between the inputs and the codebook vectors, without the norm
+        // of the inputs as it does not change which vector is the nearest
+        let norms = arrayfire::transpose(&arrayfire::sum(&(&codebook * &codebook), 1), false);
+        let distances = arrayfire::sub(
+            &norms,
+            &(arrayfire::matmul(&inputs, &codebook, MatProp::NONE, MatProp::TRANS)
+                * (2.0 as Float)),
+            true,
+        );
+        let (_, nearest) = arrayfire::imin(&distances, 1);
+        let one_hot =
+            arrayfire::eq(&arrayfire::range::<u32>(dim4!(B, K), 1), &nearest, true).cast::<Float>();
+        let quantized = arrayfire::matmul(&one_hot, &codebook, MatProp::NONE, MatProp::NONE);
+
+        let diff = &inputs - &quantized;
+        let errors = arrayfire::moddims(
+            &(arrayfire::sum(&(&diff * &diff), 1) / D as Float),
+            dim4!(1, 1, 1, B),
+        );
+        let mut indices = vec![0_u32; B as usize];
+        nearest.host(&mut indices);
+
+        let values = x.push_unary(
+            arrayfire::moddims(&arrayfire::transpose(&quantized, false), dim4!(1, D, 1, B)),
+            |df: &Array<Float>, _: &[Array<Float>]| df.clone(),
+            vec![],
+        );
+
+        let codebook_reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (assigned, scaled) = (&args[0], &args[1]);
+            let grad = arrayfire::mul(scaled, &arrayfire::moddims(df, dim4!(B, 1)), true);
+            arrayfire::matmul(assigned, &grad, MatProp::TRANS, MatProp::NONE)
+        };
+        let codebook_loss = self.codebook.push_unary(
+            errors.clone(),
+            codebook_reverse,
+            vec![one_hot, &diff * (-2.0 / D as Float)],
+        );
+
+        let commitment_reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let grad = arrayfire::mul(&args[0], &arrayfire::moddims(df, dim4!(B, 1)), true);
+            arrayfire::moddims(&arrayfire::transpose(&grad, false), dim4!(1, D, 1, B))
+        };
+        let commitment_loss = x.push_unary(
+            errors * self.commitment,
+            commitment_reverse,
+            vec![diff * (2.0 * self.commitment / D as Float)],
+        );
+
+        Quantized {
+            values,
+            indices: indices.into_iter().map(u64::from).collect(),
+            codebook_loss,
+            commitment_loss,
+        }
+    }
+
+    /// Returns the layer's trainable parameters, the codebook
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Shared<Node> {
+        self.codebook.inner().node()
+    }
+}
+
+impl<const K: u64, const D: u64> Module for VectorQuantizer<K, D> {
+    /// The codebook is named `codebook`
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        vec![(String::from("codebook"), self.parameters())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VectorQuantizer;
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn vector_quantizer_forward_backward() {
+        let vq = VectorQuantizer::<2, 2> {
+            // The vectors are (0, 0) and (4, 4)
+            codebook: mu::custom(&[0.0, 4.0, 0.0, 4.0]),
+            commitment: 0.5,
+        };
+        let x = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 4.0, 3.0]);
+        let out = vq.forward(&x);
+        assert_eq!(out.indices, [0, 1]);
+        assert!(equal_data(
+            out.values.data(),
+            Array::new(&[0.0, 0.0, 4.0, 4.0], dim4!(1, 2, 1, 2))
+        ));
+        assert!(equal_data(
+            out.codebook_loss.data(),
+            Array::new(&[0.5, 0.5], dim4!(1, 1, 1, 2))
+        ));
+
+        // Straight-through gradients of the values, plus the commitment ones
+        out.values.backward();
+        out.commitment_loss.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[1.5, 1.0, 1.0, 0.5], dim4!(1, 2, 1, 2))
+        ));
+
+        // The codebook moves towards the inputs nearest to every vector
+        out.codebook_loss.backward();
+        assert!(equal_data(
+            vq.parameters().grad(),
+            Array::new(&[-1.0, 0.0, 0.0, 1.0], dim4!(2, 2, 1, 1))
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+//! Losses of generative adversarial networks, computed from the scores (logits) the
+//! discriminator gives to every real and generated (fake) sample.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{layers::Linear, losses::{gan, Mean}};
+//!
+//! let discriminator = Linear::<4, 1>::randn();
+//! let real = mu::randn::<8, 1, 1, 4>().freeze();
+//! let fake = mu::randn::<8, 1, 1, 4>().freeze();
+//!
+//! let loss = gan::discriminator(
+//!     &discriminator.forward(&real),
+//!     &discriminator.forward(&fake),
+//!     Mean,
+//! );
+//! loss.backward();
+//!
+//! let penalty = gan::approx_r1_penalty(&[discriminator.parameters()], &real, 10.0, |x| {
+//!     discriminator.forward(x)
+//! });
+//! ```
+
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::losses::{Reduction, Sum},
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Pair, Tensed},
+        variable::Variable,
+        Float, Tensor,
+    },
+};
+use arrayfire::Array;
+
+/// Returns `log(1 + exp(x))`, computed without overflowing for large values
+fn softplus(x: &Array<Float>) -> Array<Float> {
+    let positive = arrayfire::maxof(x, &(0.0 as Float), false);
+    positive + arrayfire::log1p(&arrayfire::exp(&-arrayfire::abs(x)))
+}
+
+/// Calculates the non-saturating loss of the generator, `-log(sigmoid(fake))`, for each sample
+/// of the batch. Its gradients do not vanish while the discriminator rejects the fake samples
+#[inline]
+pub fn generator<const B: u64, X: Data, R: Reduction<B>>(
+    fake: &Tensor<B, 1, 1, 1, X>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("gan_generator");
+    let scores = fake.data();
+    let result = softplus(&-&scores);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        df * (arrayfire::sigmoid(&args[0]) - (1.0 as Float))
+    };
+
+    R::reduce(fake.push_unary(result, reverse, vec![scores]))
+}
+
+/// Calculates the standard loss of the discriminator, the binary cross entropy of classifying
+/// the real samples as real and the fake ones as fake, for each sample of the batch
+#[inline]
+pub fn discriminator<const B: u64, X: Data + Pair<Y>, Y: Data, R: Reduction<B>>(
+    real: &Tensor<B, 1, 1, 1, X>,
+    fake: &Tensor<B, 1, 1, 1, Y>,
+    _: R,
+) -> R::Output<<X as Pair<Y>>::Output> {
+    let _op = profiler::forward("gan_discriminator");
+    let (real_scores, fake_scores) = (real.data(), fake.data());
+    let result = softplus(&-&real_scores) + softplus(&fake_scores);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        (
+            df * (arrayfire::sigmoid(&args[0]) - (1.0 as Float)),
+            df * arrayfire::sigmoid(&args[1]),
+        )
+    };
+
+    R::reduce(real.push_binary(fake, result, reverse, vec![real_scores, fake_scores]))
+}
+
+/// Calculates the hinge loss of the discriminator, `relu(1 - real) + relu(1 + fake)`, for each
+/// sample of the batch
+#[inline]
+pub fn hinge_discriminator<const B: u64, X: Data + Pair<Y>, Y: Data, R: Reduction<B>>(
+    real: &Tensor<B, 1, 1, 1, X>,
+    fake: &Tensor<B, 1, 1, 1, Y>,
+    _: R,
+) -> R::Output<<X as Pair<Y>>::Output> {
+    let _op = profiler::forward("gan_hinge_discriminator");
+    let (real_scores, fake_scores) = (real.data(), fake.data());
+    let result = arrayfire::maxof(&((1.0 as Float) - &real_scores), &(0.0 as Float), false)
+        + arrayfire::maxof(&((1.0 as Float) + &fake_scores), &(0.0 as Float), false);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let real_margin = arrayfire::lt(&args[0], &(1.0 as Float), false);
+        let fake_margin = arrayfire::gt(&args[1], &(-1.0 as Float), false);
+        (-(df * real_margin), df * fake_margin)
+    };
+
+    R::reduce(real.push_binary(fake, result, reverse, vec![real_scores, fake_scores]))
+}
+
+/// Returns the R1 gradient penalty of the discriminator on the real samples, and accumulates
+/// an approximation of its gradients to those of the discriminator parameters.
+///
+/// The penalty is `gamma / 2 * |d discriminator / d real|^2`, averaged over the batch.
+/// The gradients of the penalty are second order derivatives of the discriminator, which are
+/// approximated with central finite differences of its exact gradients along the gradients of
+/// the real samples. This takes three backward passes, and the gradients the parameters
+/// accumulated before the call are kept
+#[must_use]
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn approx_r1_penalty<const B: u64, const C: u64, const H: u64, const W: u64, F>(
+    params: &[Shared<Node>],
+    real: &Tensor<B, C, H, W, Constant>,
+    gamma: Float,
+    discriminator: F,
+) -> Float
+where
+    F: Fn(&Tensor<B, C, H, W, Variable>) -> Tensor<B, 1, 1, 1, Variable>,
+{
+    let saved: Vec<_> = params.iter().map(|p| p.grad()).collect();
+    // Returns the gradients of the summed scores of the given samples with respect to them
+    // and to every parameter
+    let gradients = |samples: Array<Float>| {
+        for param in params {
+            param.zero_grad();
+        }
+        let input: Tensor<B, C, H, W, Variable> = Variable::from(samples).into();
+        Sum::reduce(discriminator(&input)).backward();
+        let grads: Vec<_> = params.iter().map(|p| p.grad()).collect();
+        (input.grad().data(), grads)
+    };
+
+    let point = real.data();
+    let (direction, _) = gradients(point.clone());
+    let penalty = arrayfire::sum_all(&(&direction * &direction)).0 * gamma / (2.0 * B as Float);
+
+    let largest = arrayfire::max_all(&arrayfire::abs(&direction)).0;
+    if largest > 0.0 {
+        // The step minimizing the sum of the truncation and rounding errors, scaled so that no
+        // sample moves further than the magnitude of its values
+        let scale = arrayfire::max_all(&arrayfire::abs(&point)).0.max(1.0);
+        let step = Float::EPSILON.cbrt() * scale / largest;
+        let (_, forward) = gradients(&point + &(&direction * step));
+        let (_, backward) = gradients(&point - &(&direction * step));
+
+        let weight = gamma / (B as Float * 2.0 * step);
+        for ((param, grad), (f, b)) in params.iter().zip(&saved).zip(forward.iter().zip(&backward))
+        {
+            param.set_grad(grad + (f - b) * weight);
+        }
+    } else {
+        for (param, grad) in params.iter().zip(saved) {
+            param.set_grad(grad);
+        }
+    }
+    penalty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{approx_r1_penalty, discriminator, generator, hinge_discriminator};
+    use crate as mu;
+    use crate::nn::losses::{Mean, Sum};
+    use crate::tensor::{traits::Tensed, Float};
+    use crate::tests::equal_data;
+    use arrayfire::{dim4, Array};
+
+    #[test]
+    fn adversarial_losses() {
+        let real = mu::custom::<2, 1, 1, 1>(&[0.0, 2.0]);
+        let fake = mu::custom::<2, 1, 1, 1>(&[0.0, -2.0]);
+        let softplus = |x: Float| (1.0 + x.exp()).ln();
+        let sigmoid = |x: Float| 1.0 / (1.0 + (-x).exp());
+
+        let z = generator(&fake, Sum);
+        assert!((z.to_scalar() - softplus(0.0) - softplus(2.0)).abs() < 1e-6);
+        z.backward();
+        assert!(equal_data(
+            fake.grad().data(),
+            Array::new(&[-0.5, sigmoid(-2.0) - 1.0], dim4!(1, 1, 1, 2))
+        ));
+
+        fake.reset();
+        let z = discriminator(&real, &fake, Mean);
+        assert!((z.to_scalar() - softplus(0.0) - softplus(-2.0)).abs() < 1e-6);
+        z.backward();
+        assert!(equal_data(
+            real.grad().data(),
+            Array::new(&[-0.25, (sigmoid(2.0) - 1.0) / 2.0], dim4!(1, 1, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn hinge_loss() {
+        let real = mu::custom::<2, 1, 1, 1>(&[0.5, 2.0]);
+        let fake = mu::custom::<2, 1, 1, 1>(&[0.0, -3.0]);
+        let z = hinge_discriminator(&real, &fake, Sum);
+        assert!((z.to_scalar() - 1.5).abs() < 1e-6);
+
+        z.backward();
+        assert!(equal_data(
+            real.grad().data(),
+            Array::new(&[-1.0, 0.0], dim4!(1, 1, 1, 2))
+        ));
+        assert!(equal_data(
+            fake.grad().data(),
+            Array::new(&[1.0, 0.0], dim4!(1, 1, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn r1_gradient_penalty() {
+        // The discriminator scores w * x^2, so its gradient is 2 * w * x and the penalty of a
+        // single sample is gamma / 2 * (2 * w * x)^2 = 2 * gamma * w^2 * x^2
+        let w = mu::custom::<1, 1, 1, 1>(&[3.0]);
+        let real = mu::custom::<1, 1, 1, 1>(&[2.0]).freeze();
+        let penalty = approx_r1_penalty(&[w.inner().node()], &real, 1.0, |x| {
+            mu::mul(&mu::mul(x, x), &w)
+        });
+        assert!((penalty - 72.0).abs() < 1e-3);
+
+        // d penalty / d w = 4 * gamma * w * x^2
+        assert!((w.grad().to_scalar() - 48.0).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,821 @@
+pub mod gan;
+
+use crate::profiler;
+use crate::tensor::{
+    constant::Constant,
+    traits::{Data, Pair, Tensed},
+    Float, Tensor,
+};
+use arrayfire::{dim4, Array, MatProp};
+
+/// How the per-sample losses of a batch are reduced into the final loss.
+///
+/// `Mean` and `Sum` return a scalar tensor, computed in the device without copying the losses
+/// to the host. `PerSample` keeps a loss for each sample, which can be reduced afterwards with
+/// `reduce`, i.e. once weighted or logged
+pub trait Reduction<const B: u64> {
+    /// The resulting tensor type after the reduction
+    type Output<X: Data>;
+
+    /// Reduces the per-sample losses, scaling the gradients accordingly
+    fn reduce<X: Data>(losses: Tensor<B, 1, 1, 1, X>) -> Self::Output<X>;
+}
+
+/// Averages the per-sample losses over the batch
+#[derive(Clone, Copy, Debug)]
+pub struct Mean;
+
+/// Sums the per-sample losses over the batch
+#[derive(Clone, Copy, Debug)]
+pub struct Sum;
+
+/// Keeps the per-sample losses, one for each element of the batch
+#[derive(Clone, Copy, Debug)]
+pub struct PerSample;
+
+impl<const B: u64> Reduction<B> for Mean {
+    type Output<X: Data> = Tensor<1, 1, 1, 1, X>;
+
+    #[inline]
+    fn reduce<X: Data>(losses: Tensor<B, 1, 1, 1, X>) -> Self::Output<X> {
+        let _op = profiler::forward("mean");
+        losses.push_unary(
+            arrayfire::div(&arrayfire::sum(&losses.data(), 3), &B, false),
+            |df: &Array<Float>, _: &[Array<Float>]| {
+                arrayfire::tile(&arrayfire::div(df, &B, false), dim4!(1, 1, 1, B))
+            },
+            vec![],
+        )
+    }
+}
+
+impl<const B: u64> Reduction<B> for Sum {
+    type Output<X: Data> = Tensor<1, 1, 1, 1, X>;
+
+    #[inline]
+    fn reduce<X: Data>(losses: Tensor<B, 1, 1, 1, X>) -> Self::Output<X> {
+        let _op = profiler::forward("sum");
+        losses.push_unary(
+            arrayfire::sum(&losses.data(), 3),
+            |df: &Array<Float>, _: &[Array<Float>]| arrayfire::tile(df, dim4!(1, 1, 1, B)),
+            vec![],
+        )
+    }
+}
+
+impl<const B: u64> Reduction<B> for PerSample {
+    type Output<X: Data> = Tensor<B, 1, 1, 1, X>;
+
+    #[inline]
+    fn reduce<X: Data>(losses: Tensor<B, 1, 1, 1, X>) -> Self::Output<X> {
+        losses
+    }
+}
+
+/// Calculates the Mean Squared Error between two row vectors, for each sample of the batch
+#[inline]
+pub fn mse<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("mse");
+    let diff = arrayfire::sub(&x.data(), &y.data(), false);
+    let result = arrayfire::div(
+        &arrayfire::sum(&arrayfire::pow(&diff, &(2.0 as Float), false), 1),
+        &W,
+        false,
+    );
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        arrayfire::mul(
+            df,
+            &arrayfire::div(&((2.0 as Float) * &args[0]), &W, false),
+            true,
+        )
+    };
+
+    R::reduce(x.push_unary(result, reverse, vec![diff]))
+}
+
+/// Same as `mse`, averaging the squared errors of each sample where the mask is 1 only, i.e. to
+/// ignore the padding of sequences. Fully masked samples have a loss of 0
+#[inline]
+pub fn mse_masked<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    mask: &Tensor<B, 1, 1, W, Constant>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("mse");
+    let mask = mask.data();
+    let counts = arrayfire::maxof(&arrayfire::sum(&mask, 1), &(1.0 as Float), false);
+    let diff = arrayfire::mul(&arrayfire::sub(&x.data(), &y.data(), false), &mask, false);
+    let result = arrayfire::div(&arrayfire::sum(&(&diff * &diff), 1), &counts, false);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| arrayfire::mul(df, &args[0], true);
+
+    R::reduce(x.push_unary(
+        result,
+        reverse,
+        vec![arrayfire::div(&((2.0 as Float) * diff), &counts, true)],
+    ))
+}
+
+/// Calculates the Negative Log Likelihood of the one-hot encoded targets given the probabilities
+/// of a set of classes, for each sample of the batch
+#[inline]
+pub fn nll<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    nll_by(x, y, &arrayfire::constant!(1.0 as Float; 1, W, 1, 1), r)
+}
+
+/// Same as `nll`, with the contribution of each class scaled by the given weights.
+///
+/// The weights are not normalized: `Mean` still divides the weighted losses by `B`, not by the
+/// sum of the weights of the targets, so scaling all the weights scales the loss alike
+#[inline]
+pub fn nll_weighted<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    weights: &Tensor<1, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    nll_by(x, y, &weights.data(), r)
+}
+
+/// Negative Log Likelihood with per-class weights
+fn nll_by<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    weights: &Array<Float>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("nll");
+    // Probabilities are shifted to avoid taking the log of zero
+    let probs = arrayfire::add(&x.data(), &(1e-7 as Float), false);
+    let targets = arrayfire::mul(weights, &y.data(), true);
+    let result = -arrayfire::sum(&arrayfire::mul(&targets, &arrayfire::log(&probs), false), 1);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| -arrayfire::mul(df, &args[0], true);
+
+    R::reduce(x.push_unary(
+        result,
+        reverse,
+        vec![arrayfire::div(&targets, &probs, false)],
+    ))
+}
+
+/// Calculates the Cross Entropy between the logits of a set of classes and the one-hot
+/// encoded targets, for each sample of the batch. Fuses `logsoftmax` and `nll` for numerical stability
+#[inline]
+pub fn cross_entropy<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    cross_entropy_by(x, y.data(), r)
+}
+
+/// Same as `cross_entropy`, with the loss of each sample scaled by the weight of its class
+#[inline]
+pub fn cross_entropy_weighted<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    weights: &Tensor<1, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    // Weighting the targets scales both the loss and its gradient by the class weights
+    cross_entropy_by(x, arrayfire::mul(&y.data(), &weights.data(), true), r)
+}
+
+/// Cross Entropy against the given, possibly weighted, targets
+fn cross_entropy_by<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    targets: Array<Float>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("cross_entropy");
+    // Shift each sample by its maximum logit, this is required for numerical stability
+    let shift = arrayfire::sub(&x.data(), &arrayfire::max(&x.data(), 1), true);
+    let exps = arrayfire::exp(&shift);
+    let sums = arrayfire::sum(&exps, 1);
+    let logsoftmax = arrayfire::sub(&shift, &arrayfire::log(&sums), true);
+    let softmax = arrayfire::div(&exps, &sums, true);
+
+    let result = -arrayfire::sum(&arrayfire::mul(&targets, &logsoftmax, false), 1);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (s, t) = (&args[0], &args[1]);
+        let grad = arrayfire::sub(&arrayfire::mul(s, &arrayfire::sum(t, 1), true), t, false);
+        arrayfire::mul(df, &grad, true)
+    };
+
+    R::reduce(x.push_unary(result, reverse, vec![softmax, targets]))
+}
+
+/// Calculates the Binary Cross Entropy between a set of probabilities and the binary targets,
+/// averaged over the elements of each sample. Probabilities are clamped to avoid taking the log of zero
+#[inline]
+pub fn bce<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    bce_by(x, y, arrayfire::constant!(1.0 as Float; 1, W, 1, 1), r)
+}
+
+/// Same as `bce`, with each element scaled by the weight of its column.
+///
+/// Each sample is still averaged over its `W` elements and `Mean` over the `B` samples, rather
+/// than over the sum of the weights
+#[inline]
+pub fn bce_weighted<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    weights: &Tensor<1, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    bce_by(x, y, weights.data(), r)
+}
+
+/// Same as `bce`, averaging over the elements of each sample where the mask is 1 only, i.e. to
+/// ignore the padding of sequences. Fully masked samples have a loss of 0
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn bce_masked<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    mask: &Tensor<B, 1, 1, W, Constant>,
+    r: R,
+) -> R::Output<X> {
+    // Weighting the elements by W over the number of them kept averages over those only
+    let mask = mask.data();
+    let counts = arrayfire::maxof(&arrayfire::sum(&mask, 1), &(1.0 as Float), false);
+    bce_by(x, y, arrayfire::div(&(mask * W as Float), &counts, true), r)
+}
+
+/// Binary Cross Entropy with per-column weights
+fn bce_by<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    weights: Array<Float>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("bce");
+    let probs = arrayfire::clamp(&x.data(), &(1e-7 as Float), &((1.0 as Float) - 1e-7), false);
+    let targets = y.data();
+
+    let likelihood = arrayfire::mul(
+        &weights,
+        &arrayfire::add(
+            &arrayfire::mul(&targets, &arrayfire::log(&probs), false),
+            &arrayfire::mul(
+                &((1.0 as Float) - &targets),
+                &arrayfire::log(&((1.0 as Float) - &probs)),
+                false,
+            ),
+            false,
+        ),
+        true,
+    );
+    let result = -arrayfire::div(&arrayfire::sum(&likelihood, 1), &W, false);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (p, t, scale) = (&args[0], &args[1], &args[2]);
+        let grad = arrayfire::mul(
+            scale,
+            &arrayfire::div(
+                &arrayfire::sub(p, t, false),
+                &arrayfire::mul(p, &((1.0 as Float) - p), false),
+                false,
+            ),
+            true,
+        );
+        arrayfire::mul(df, &arrayfire::div(&grad, &W, false), true)
+    };
+
+    R::reduce(x.push_unary(result, reverse, vec![probs, targets, weights]))
+}
+
+/// Calculates the Kullback-Leibler divergence between the target distributions and the
+/// distributions given by the log probabilities, summed over the classes of each sample
+#[inline]
+pub fn kl_div<const B: u64, const W: u64, X: Data, R: Reduction<B>>(
+    x: &Tensor<B, 1, 1, W, X>,
+    y: &Tensor<B, 1, 1, W, Constant>,
+    _: R,
+) -> R::Output<X> {
+    let _op = profiler::forward("kl_div");
+    let targets = y.data();
+    let divergence = arrayfire::mul(
+        &targets,
+        &arrayfire::sub(
+            &arrayfire::log(&arrayfire::maxof(&targets, &(1e-7 as Float), false)),
+            &x.data(),
+            false,
+        ),
+        false,
+    );
+    let result = arrayfire::sum(&divergence, 1);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| -arrayfire::mul(df, &args[0], true);
+
+    R::reduce(x.push_unary(result, reverse, vec![targets]))
+}
+
+/// Calculates the Kullback-Leibler divergence of the normal distributions with the given means
+/// and log variances from the standard normal distribution, for each sample of the batch.
+///
+/// The divergence is summed over the values of each sample, as in the regularization term of a
+/// variational autoencoder
+#[inline]
+pub fn kl_normal<const B: u64, const W: u64, X: Data + Pair<Y>, Y: Data, R: Reduction<B>>(
+    mean: &Tensor<B, 1, 1, W, X>,
+    logvar: &Tensor<B, 1, 1, W, Y>,
+    _: R,
+) -> R::Output<<X as Pair<Y>>::Output> {
+    let _op = profiler::forward("kl_normal");
+    let (mean_data, logvar_data) = (mean.data(), logvar.data());
+    let variance = arrayfire::exp(&logvar_data);
+    // -1/2 * sum(1 + log(var) - mean^2 - var)
+    let terms = arrayfire::sub(
+        &arrayfire::add(
+            &arrayfire::mul(&mean_data, &mean_data, false),
+            &variance,
+            false,
+        ),
+        &((1.0 as Float) + logvar_data),
+        false,
+    );
+    let result = arrayfire::sum(&terms, 1) * (0.5 as Float);
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (m, var) = (&args[0], &args[1]);
+        (
+            arrayfire::mul(df, m, true),
+            arrayfire::mul(df, &((var - (1.0 as Float)) * (0.5 as Float)), true),
+        )
+    };
+
+    R::reduce(mean.push_binary(logvar, result, reverse, vec![mean_data, variance]))
+}
+
+/// Returns the log likelihood of the targets under every one of `K` normal distributions
+/// with diagonal covariance, summed over the `D` values of each target
+fn component_log_likelihoods<
+    const B: u64,
+    const K: u64,
+    const D: u64,
+    Y: Data + Pair<Z>,
+    Z: Data,
+>(
+    means: &Tensor<B, 1, K, D, Y>,
+    log_sigmas: &Tensor<B, 1, K, D, Z>,
+    targets: &Tensor<B, 1, 1, D, Constant>,
+) -> Tensor<B, 1, 1, K, <Y as Pair<Z>>::Output> {
+    let inv_sigmas = arrayfire::exp(&-log_sigmas.data());
+    let standardized = arrayfire::mul(
+        &arrayfire::sub(&targets.data(), &means.data(), true),
+        &inv_sigmas,
+        false,
+    );
+    // log(sqrt(2 * pi))
+    let log_norm = 0.5 * (2.0 * std::f64::consts::PI).ln() as Float;
+    let terms = -(arrayfire::mul(&standardized, &standardized, false) * (0.5 as Float))
+        - log_sigmas.data()
+        - log_norm;
+    let result = arrayfire::moddims(&arrayfire::sum(&terms, 1), dim4!(1, K, 1, B));
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (z, inv_s) = (&args[0], &args[1]);
+        let df = arrayfire::moddims(df, dim4!(K, 1, 1, B));
+        // The derivatives are z / s for the mean and z^2 - 1 for the log deviation
+        (
+            arrayfire::mul(&df, &arrayfire::mul(z, inv_s, false), true),
+            arrayfire::mul(&df, &(arrayfire::mul(z, z, false) - (1.0 as Float)), true),
+        )
+    };
+
+    means.push_binary(log_sigmas, result, reverse, vec![standardized, inv_sigmas])
+}
+
+/// Calculates the negative log likelihood of the targets under a mixture of `K` normal
+/// distributions with diagonal covariance, as predicted by a mixture density network.
+///
+/// The mixture weights are the softmax of `pi_logits`, and the components have the given means
+/// and log standard deviations. The mixture is combined with a logsumexp for numerical
+/// stability, and the gradients flow to the three heads
+#[inline]
+pub fn mdn_nll<
+    const B: u64,
+    const K: u64,
+    const D: u64,
+    X: Data + Pair<<Y as Pair<Z>>::Output>,
+    Y: Data + Pair<Z>,
+    Z: Data,
+    R: Reduction<B>,
+>(
+    pi_logits: &Tensor<B, 1, 1, K, X>,
+    means: &Tensor<B, 1, K, D, Y>,
+    log_sigmas: &Tensor<B, 1, K, D, Z>,
+    targets: &Tensor<B, 1, 1, D, Constant>,
+    _: R,
+) -> R::Output<<X as Pair<<Y as Pair<Z>>::Output>>::Output> {
+    let _op = profiler::forward("mdn_nll");
+    let components = component_log_likelihoods(means, log_sigmas, targets);
+
+    let logits = pi_logits.data();
+    let shifted = arrayfire::sub(&logits, &arrayfire::max(&logits, 1), true);
+    let exps = arrayfire::exp(&shifted);
+    let total = arrayfire::sum(&exps, 1);
+    let weights = arrayfire::div(&exps, &total, true);
+    // The log joint probabilities of the targets and every component
+    let joint = arrayfire::add(
+        &arrayfire::sub(&shifted, &arrayfire::log(&total), true),
+        &components.data(),
+        false,
+    );
+    let max = arrayfire::max(&joint, 1);
+    let log_likelihood = max.clone()
+        + arrayfire::log(&arrayfire::sum(
+            &arrayfire::exp(&arrayfire::sub(&joint, &max, true)),
+            1,
+        ));
+    // The posterior probabilities of the components given the targets
+    let posteriors = arrayfire::exp(&arrayfire::sub(&joint, &log_likelihood, true));
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (w, r) = (&args[0], &args[1]);
+        (
+            arrayfire::mul(df, &(w - r), true),
+            -arrayfire::mul(df, r, true),
+        )
+    };
+
+    R::reduce(pi_logits.push_binary(
+        &components,
+        -log_likelihood,
+        reverse,
+        vec![weights, posteriors],
+    ))
+}
+
+/// Calculates the `InfoNCE` (NT-Xent) contrastive loss of every anchor, the cross entropy of
+/// picking its positive among the positives of the whole batch.
+///
+/// The scores are the dot products of the anchors and positives divided by the temperature,
+/// so the embeddings are normalized beforehand for the cosine similarity used by `SimCLR`
+#[inline]
+pub fn info_nce<const B: u64, const W: u64, X: Data + Pair<Y>, Y: Data, R: Reduction<B>>(
+    anchors: &Tensor<B, 1, 1, W, X>,
+    positives: &Tensor<B, 1, 1, W, Y>,
+    temperature: Float,
+    _: R,
+) -> R::Output<<X as Pair<Y>>::Output> {
+    let _op = profiler::forward("info_nce");
+    // Every column holds the embedding of a sample
+    let anchor_cols = arrayfire::moddims(&anchors.data(), dim4!(W, B));
+    let positive_cols = arrayfire::moddims(&positives.data(), dim4!(W, B));
+    let scores = arrayfire::matmul(&anchor_cols, &positive_cols, MatProp::TRANS, MatProp::NONE)
+        / temperature;
+
+    // Shift each row by its maximum score, this is required for numerical stability
+    let shift = arrayfire::sub(&scores, &arrayfire::max(&scores, 1), true);
+    let exps = arrayfire::exp(&shift);
+    let sums = arrayfire::sum(&exps, 1);
+    let softmax = arrayfire::div(&exps, &sums, true);
+    let positive = arrayfire::diag_extract(&shift, 0);
+    let result = arrayfire::moddims(
+        &arrayfire::sub(&arrayfire::log(&sums), &positive, false),
+        dim4!(1, 1, 1, B),
+    );
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let (a, p, s) = (&args[0], &args[1], &args[2]);
+        let grad = arrayfire::mul(
+            &(s - arrayfire::identity::<Float>(dim4!(B, B))),
+            &arrayfire::moddims(df, dim4!(B, 1)),
+            true,
+        );
+        (
+            arrayfire::moddims(
+                &arrayfire::matmul(p, &grad, MatProp::NONE, MatProp::TRANS),
+                dim4!(1, W, 1, B),
+            ),
+            arrayfire::moddims(
+                &arrayfire::matmul(a, &grad, MatProp::NONE, MatProp::NONE),
+                dim4!(1, W, 1, B),
+            ),
+        )
+    };
+
+    R::reduce(anchors.push_binary(
+        positives,
+        result,
+        reverse,
+        vec![
+            anchor_cols / temperature,
+            positive_cols / temperature,
+            softmax,
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bce, bce_masked, bce_weighted, cross_entropy, cross_entropy_weighted, info_nce, kl_div,
+        kl_normal, mdn_nll, mse, mse_masked, nll, nll_weighted, Mean, PerSample, Reduction, Sum,
+    };
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+    use arrayfire::Array;
+
+    #[test]
+    fn mse_forward_backward() {
+        let x = mu::fill::<1, 1, 1, 6>(2.0);
+        let y = mu::fill::<1, 1, 1, 6>(0.5).freeze();
+        let z = mse(&x, &y, Mean);
+        assert!(equal_data(z.data(), arrayfire::constant!(2.25; 1,1,1,1)));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(0.5; 1,6,1,1)
+        ));
+    }
+
+    #[test]
+    fn nll_forward_backward() {
+        let x = mu::custom::<1, 1, 1, 3>(&[0.5, 0.2, 0.3]);
+        let y = mu::custom::<1, 1, 1, 3>(&[1.0, 0.0, 0.0]).freeze();
+        let z = nll(&x, &y, Mean);
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(0.6931470; 1,1,1,1)
+        ));
+
+        // Only the probability of the target class is pushed up
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(&[-1.9999996, 0.0, 0.0], arrayfire::dim4!(1, 3, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn cross_entropy_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 3>(&[1.0, 2.0, 3.0, 1.0, 1.0, 1.0]);
+        let y = mu::custom::<2, 1, 1, 3>(&[0.0, 0.0, 1.0, 1.0, 0.0, 0.0]).freeze();
+        let z = cross_entropy(&x, &y, Mean);
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(0.7531091; 1,1,1,1)
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(
+                &[
+                    0.04501529,
+                    0.12236424,
+                    -0.16737953,
+                    -0.33333334,
+                    0.16666667,
+                    0.16666667
+                ],
+                arrayfire::dim4!(1, 3, 1, 2)
+            )
+        ));
+    }
+
+    #[test]
+    fn bce_forward_backward() {
+        let x = mu::custom::<1, 1, 1, 2>(&[0.8, 0.4]);
+        let y = mu::custom::<1, 1, 1, 2>(&[1.0, 0.0]).freeze();
+        let z = bce(&x, &y, Mean);
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(0.36698458; 1,1,1,1)
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(&[-0.625, 0.8333333], arrayfire::dim4!(1, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn kl_div_forward_backward() {
+        let x = mu::custom::<1, 1, 1, 2>(&[-0.6931472, -0.6931472]);
+        let y = mu::custom::<1, 1, 1, 2>(&[0.25, 0.75]).freeze();
+        let z = kl_div(&x, &y, Mean);
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(0.13081203; 1,1,1,1)
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(&[-0.25, -0.75], arrayfire::dim4!(1, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn kl_normal_forward_backward() {
+        let mean = mu::custom::<1, 1, 1, 2>(&[1.0, 0.0]);
+        let logvar = mu::custom::<1, 1, 1, 2>(&[0.0, (2.0 as Float).ln()]);
+        let z = kl_normal(&mean, &logvar, Sum);
+        assert!((z.to_scalar() - (1.0 - (2.0 as Float).ln() / 2.0)).abs() < 1e-6);
+
+        z.backward();
+        assert!(equal_data(
+            mean.grad().data(),
+            Array::<Float>::new(&[1.0, 0.0], arrayfire::dim4!(1, 2, 1, 1))
+        ));
+        assert!(equal_data(
+            logvar.grad().data(),
+            Array::<Float>::new(&[0.0, 0.5], arrayfire::dim4!(1, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn mdn_nll_forward_backward() {
+        let pi_logits = mu::custom::<1, 1, 1, 2>(&[0.0, 0.0]);
+        let means = mu::custom::<1, 1, 2, 1>(&[0.0, 2.0]);
+        let log_sigmas = mu::custom::<1, 1, 2, 1>(&[0.0, 0.0]);
+        let targets = mu::custom::<1, 1, 1, 1>(&[2.0]).freeze();
+        let z = mdn_nll(&pi_logits, &means, &log_sigmas, &targets, Sum);
+
+        // The target is two deviations away from the first component and at the second
+        let (near, far) = (1.0 as Float, (-2.0 as Float).exp());
+        let log_norm = 0.5 * (2.0 * std::f64::consts::PI).ln() as Float;
+        assert!((z.to_scalar() - (log_norm - (0.5 * (near + far)).ln())).abs() < 1e-5);
+
+        z.backward();
+        let posteriors = [far / (near + far), near / (near + far)];
+        assert!(equal_data(
+            pi_logits.grad().data(),
+            Array::<Float>::new(
+                &[0.5 - posteriors[0], 0.5 - posteriors[1]],
+                arrayfire::dim4!(1, 2, 1, 1)
+            )
+        ));
+        assert!(equal_data(
+            means.grad().data(),
+            Array::<Float>::new(&[-2.0 * posteriors[0], 0.0], arrayfire::dim4!(2, 1, 1, 1))
+        ));
+        assert!(equal_data(
+            log_sigmas.grad().data(),
+            Array::<Float>::new(
+                &[-3.0 * posteriors[0], posteriors[1]],
+                arrayfire::dim4!(2, 1, 1, 1)
+            )
+        ));
+    }
+
+    #[test]
+    fn reductions_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 2>(&[1.0, 2.0, 3.0, 4.0]);
+        let y = mu::fill::<2, 1, 1, 2>(0.0).freeze();
+
+        let z = mse(&x, &y, PerSample);
+        assert!(equal_data(
+            z.data(),
+            Array::<Float>::new(&[2.5, 12.5], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+
+        let z = <Mean as Reduction<2>>::reduce(z);
+        assert!(equal_data(z.data(), arrayfire::constant!(7.5; 1,1,1,1)));
+
+        let z = mse(&x, &y, Sum);
+        assert!(equal_data(z.data(), arrayfire::constant!(15.0; 1,1,1,1)));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(&[1.0, 2.0, 3.0, 4.0], arrayfire::dim4!(1, 2, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn cross_entropy_weighted_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 2>(&[0.0, 0.0, 0.0, 0.0]);
+        let y = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]).freeze();
+        let w = mu::custom::<1, 1, 1, 2>(&[1.0, 3.0]).freeze();
+        let z = cross_entropy_weighted(&x, &y, &w, PerSample);
+        assert!(equal_data(
+            z.data(),
+            Array::<Float>::new(&[0.6931472, 2.0794415], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(&[-0.5, 0.5, 1.5, -1.5], arrayfire::dim4!(1, 2, 1, 2))
+        ));
+    }
+
+    #[test]
+    fn nll_weighted_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 3>(&[0.5, 0.2, 0.3, 0.5, 0.2, 0.3]);
+        let y = mu::custom::<2, 1, 1, 3>(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0]).freeze();
+        let w = mu::custom::<1, 1, 1, 3>(&[1.0, 2.0, 1.0]).freeze();
+        let z = nll_weighted(&x, &y, &w, PerSample);
+        assert!(equal_data(
+            z.data(),
+            Array::<Float>::new(&[0.6931470, 3.2188748], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+
+        let z = nll_weighted(&x, &y, &w, Mean);
+        // The mean is over the batch, whatever the weights of the targets
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(1.9560109; 1,1,1,1)
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(
+                &[-0.9999998, 0.0, 0.0, 0.0, -4.9999975, 0.0],
+                arrayfire::dim4!(1, 3, 1, 2)
+            )
+        ));
+    }
+
+    #[test]
+    fn bce_weighted_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 2>(&[0.8, 0.4, 0.5, 0.5]);
+        let y = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]).freeze();
+        let w = mu::custom::<1, 1, 1, 2>(&[2.0, 1.0]).freeze();
+        let z = bce_weighted(&x, &y, &w, PerSample);
+        assert!(equal_data(
+            z.data(),
+            Array::<Float>::new(&[0.47855636, 1.0397208], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+
+        let z = bce_weighted(&x, &y, &w, Mean);
+        assert!(equal_data(
+            z.data(),
+            arrayfire::constant!(0.75913857; 1,1,1,1)
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::<Float>::new(
+                &[-0.625, 0.41666666, 1.0, -0.5],
+                arrayfire::dim4!(1, 2, 1, 2)
+            )
+        ));
+    }
+
+    #[test]
+    fn info_nce_forward_backward() {
+        let anchors = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]);
+        let positives = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]);
+        let z = info_nce(&anchors, &positives, 1.0, Sum);
+        let e = (1.0 as Float).exp();
+        assert!((z.to_scalar() - 2.0 * (1.0 + 1.0 / e).ln()).abs() < 1e-6);
+
+        // Every anchor is pulled towards its positive and pushed from the other one
+        z.backward();
+        let q = 1.0 / (1.0 + e);
+        let expected = Array::new(&[-q, q, q, -q], arrayfire::dim4!(1, 2, 1, 2));
+        assert!(equal_data(anchors.grad().data(), expected.clone()));
+        assert!(equal_data(positives.grad().data(), expected));
+    }
+
+    #[test]
+    fn masked_losses() {
+        let x = mu::custom::<2, 1, 1, 2>(&[2.0, 7.0, 0.5, 0.9]);
+        let y = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 1.0]).freeze();
+        let mask = mu::custom::<2, 1, 1, 2>(&[1.0, 0.0, 0.0, 0.0]).freeze();
+
+        let z = mse_masked(&x, &y, &mask, PerSample);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.0, 0.0], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[2.0, 0.0, 0.0, 0.0], arrayfire::dim4!(1, 2, 1, 2))
+        ));
+
+        // Only the first element of the first sample counts
+        let x = mu::custom::<2, 1, 1, 2>(&[0.8, 0.1, 0.5, 0.9]);
+        let z = bce_masked(&x, &y, &mask, Sum);
+        assert!((z.to_scalar() + (0.8 as Float).ln()).abs() < 1e-6);
+    }
+}
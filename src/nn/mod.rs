@@ -5,7 +5,7 @@
 //! #![feature(generic_const_exprs)]
 //!
 //! use mushin as mu;
-//! use mu::nn::{layers::Linear, activations::relu, losses::mse, optimizers::SGD};
+//! use mu::nn::{layers::Linear, activations::relu, losses::{mse, Mean}, optimizers::{Optimizer, SGD}};
 //!
 //! let x = mu::eye::<16, 1, 1, 3>(1.0).freeze();
 //! let y = mu::eye::<16, 1, 1, 5>(3.0).freeze();
@@ -15,7 +15,7 @@
 //!
 //! for _ in 0..5 {
 //!     let z = relu(&linear.forward(&x));
-//!     let loss = mse(&z, &y);
+//!     let loss = mse(&z, &y, Mean);
 //!
 //!     loss.backward();
 //!     optim.step();
@@ -24,7 +24,29 @@
 //! ```
 
 pub mod activations;
+pub mod bptt;
+pub mod callbacks;
+pub mod decode;
+pub mod functional;
+pub mod init;
+pub mod io;
 pub mod layers;
 pub mod losses;
+pub mod models;
+#[cfg(feature = "onnx")]
+pub mod onnx;
 pub mod ops;
 pub mod optimizers;
+pub mod prune;
+pub mod quantize;
+#[doc(hidden)]
+pub mod sequential;
+pub mod tune;
+#[cfg(feature = "zoo")]
+pub mod zoo;
+
+mod module;
+mod trainer;
+
+pub use module::{Module, StateError};
+pub use trainer::Trainer;
@@ -0,0 +1,251 @@
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::{
+        activations::relu,
+        layers::{Conv2D, Linear},
+        module::{scoped, Module},
+        ops::{flatten, maxpool2d},
+    },
+    tensor::{
+        traits::{Data, Pair},
+        variable::Variable,
+        Tensor,
+    },
+};
+
+/// A Multi-Layer Perceptron with `I` inputs, a `ReLu` activated hidden layer of size `H`
+/// and `O` outputs
+#[allow(clippy::cast_possible_truncation, clippy::upper_case_acronyms)]
+pub struct MLP<const I: u64, const H: u64, const O: u64>
+where
+    [(); (I + 1) as usize]:,
+    [(); (H + 1) as usize]:,
+{
+    hidden: Linear<I, H>,
+    output: Linear<H, O>,
+}
+
+#[allow(clippy::cast_possible_truncation)]
+impl<const I: u64, const H: u64, const O: u64> MLP<I, H, O>
+where
+    [(); (I + 1) as usize]:,
+    [(); (H + 1) as usize]:,
+{
+    /// Returns a new `MLP` with all its parameters taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            hidden: Linear::randn(),
+            output: Linear::randn(),
+        }
+    }
+
+    /// Given an input computes the output
+    #[inline]
+    pub fn forward<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, 1, 1, I, D>,
+    ) -> Tensor<B, 1, 1, O, Variable> {
+        self.output.forward(&relu(&self.hidden.forward(x)))
+    }
+
+    /// Returns the model's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        vec![self.hidden.parameters(), self.output.parameters()]
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+impl<const I: u64, const H: u64, const O: u64> Module for MLP<I, H, O>
+where
+    [(); (I + 1) as usize]:,
+    [(); (H + 1) as usize]:,
+{
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped("hidden", &self.hidden),
+            scoped("output", &self.output),
+        ]
+        .concat()
+    }
+}
+
+/// The `LeNet-5` convolutional network, taking single channel 32x32 images and
+/// returning the scores for 10 classes
+pub struct LeNet5 {
+    conv1: Conv2D<1, 6, 5, 5>,
+    conv2: Conv2D<6, 16, 5, 5>,
+    fc1: Linear<400, 120>,
+    fc2: Linear<120, 84>,
+    fc3: Linear<84, 10>,
+}
+
+impl LeNet5 {
+    /// Returns a new `LeNet5` with all its parameters taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            conv1: Conv2D::randn(),
+            conv2: Conv2D::randn(),
+            fc1: Linear::randn(),
+            fc2: Linear::randn(),
+            fc3: Linear::randn(),
+        }
+    }
+
+    /// Given an input computes the output
+    #[inline]
+    pub fn forward<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, 1, 32, 32, D>,
+    ) -> Tensor<B, 1, 1, 10, Variable> {
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv1.forward(x)));
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv2.forward(&x)));
+        let x = relu(&self.fc1.forward(&flatten(&x)));
+        let x = relu(&self.fc2.forward(&x));
+        self.fc3.forward(&x)
+    }
+
+    /// Returns the model's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        vec![
+            self.conv1.parameters(),
+            self.conv2.parameters(),
+            self.fc1.parameters(),
+            self.fc2.parameters(),
+            self.fc3.parameters(),
+        ]
+    }
+}
+
+impl Module for LeNet5 {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped("conv1", &self.conv1),
+            scoped("conv2", &self.conv2),
+            scoped("fc1", &self.fc1),
+            scoped("fc2", &self.fc2),
+            scoped("fc3", &self.fc3),
+        ]
+        .concat()
+    }
+}
+
+/// A small convolutional network taking 32x32 images with `C` channels and
+/// returning the scores for `O` classes
+pub struct ConvNet<const C: u64, const O: u64> {
+    conv1: Conv2D<C, 16, 3, 3>,
+    conv2: Conv2D<16, 32, 3, 3>,
+    fc: Linear<1152, O>,
+}
+
+impl<const C: u64, const O: u64> ConvNet<C, O> {
+    /// Returns a new `ConvNet` with all its parameters taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            conv1: Conv2D::randn(),
+            conv2: Conv2D::randn(),
+            fc: Linear::randn(),
+        }
+    }
+
+    /// Given an input computes the output
+    #[inline]
+    pub fn forward<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, C, 32, 32, D>,
+    ) -> Tensor<B, 1, 1, O, Variable> {
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv1.forward(x)));
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv2.forward(&x)));
+        self.fc.forward(&flatten(&x))
+    }
+
+    /// Returns the model's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        vec![
+            self.conv1.parameters(),
+            self.conv2.parameters(),
+            self.fc.parameters(),
+        ]
+    }
+}
+
+impl<const C: u64, const O: u64> Module for ConvNet<C, O> {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped("conv1", &self.conv1),
+            scoped("conv2", &self.conv2),
+            scoped("fc", &self.fc),
+        ]
+        .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConvNet, LeNet5, MLP};
+    use crate as mu;
+    use crate::nn::optimizers::{Optimizer, SGD};
+    use crate::tensor::traits::Tensed;
+
+    #[test]
+    fn mlp_forward_backward() {
+        let mlp = MLP::<3, 4, 2>::randn();
+        let x = mu::fill::<2, 1, 1, 3>(1.0).freeze();
+
+        let z = mlp.forward(&x);
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 2, 1, 2));
+
+        z.backward();
+        let params = mlp.parameters();
+        assert_eq!(params.len(), 2);
+        // The gradients of the whole batch are summed into a single one per weight matrix
+        assert_eq!(params[0].grad().dims(), arrayfire::dim4!(4, 4, 1, 1));
+        assert_eq!(params[1].grad().dims(), arrayfire::dim4!(5, 2, 1, 1));
+
+        let optim = SGD::new(&params, 0.01);
+        optim.step();
+        assert_eq!(params[0].data().dims(), arrayfire::dim4!(4, 4, 1, 1));
+        assert_eq!(params[1].data().dims(), arrayfire::dim4!(5, 2, 1, 1));
+    }
+
+    #[test]
+    fn lenet5_forward_backward() {
+        let lenet = LeNet5::randn();
+        let x = mu::randn::<2, 1, 32, 32>().freeze();
+
+        let z = lenet.forward(&x);
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 10, 1, 2));
+
+        z.backward();
+        assert_eq!(lenet.parameters().len(), 5);
+    }
+
+    #[test]
+    fn convnet_forward_backward() {
+        let convnet = ConvNet::<3, 10>::randn();
+        let x = mu::randn::<2, 3, 32, 32>().freeze();
+
+        let z = convnet.forward(&x);
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 10, 1, 2));
+
+        z.backward();
+        assert_eq!(convnet.parameters().len(), 3);
+    }
+}
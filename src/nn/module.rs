@@ -0,0 +1,138 @@
+use crate::graph::{node::Node, shared::Shared};
+use crate::tensor::Float;
+use arrayfire::Array;
+use std::{collections::HashMap, error::Error, fmt};
+
+/// A layer or model holding trainable parameters, each identified by a stable name.
+/// Nested modules prefix the names of their children with the field name, i.e. `hidden.weights`.
+///
+/// Modules may also hold buffers, values that are not trained but are part of their state,
+/// i.e. the running statistics of a batch normalization
+pub trait Module {
+    /// Returns the trainable parameters along with their names
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)>;
+
+    /// Returns the buffers along with their names. Modules have none unless they override it
+    #[inline]
+    fn named_buffers(&self) -> Vec<(String, Shared<Node>)> {
+        Vec::new()
+    }
+
+    /// Returns the parameters followed by the buffers, the whole state that is stored and
+    /// loaded, i.e. by `io::save` and `io::load`
+    #[inline]
+    fn named_state(&self) -> Vec<(String, Shared<Node>)> {
+        [self.named_parameters(), self.named_buffers()].concat()
+    }
+
+    /// Returns a copy of the values of every parameter and buffer keyed by its name
+    #[inline]
+    fn state_dict(&self) -> HashMap<String, Array<Float>> {
+        self.named_state()
+            .into_iter()
+            .map(|(name, node)| (name, node.data().clone()))
+            .collect()
+    }
+
+    /// Overwrites the values of every parameter and buffer with the ones under its name in
+    /// `state`. Entries not matching any of them are ignored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any parameter or buffer is missing from `state` or its shape does not
+    /// match, in which case none is modified
+    #[inline]
+    fn load_state_dict(&self, state: &HashMap<String, Array<Float>>) -> Result<(), StateError> {
+        let params = self.named_state();
+
+        for param in &params {
+            let (name, node) = (&param.0, &param.1);
+            let values = state
+                .get(name)
+                .ok_or_else(|| StateError::Missing(name.clone()))?;
+            if values.dims() != node.data().dims() {
+                return Err(StateError::Shape(name.clone()));
+            }
+        }
+
+        for (name, node) in params {
+            node.set_data(state[&name].clone());
+        }
+        Ok(())
+    }
+}
+
+/// Prefixes the parameter names of a child module with the given field name
+pub fn scoped<M: Module>(name: &str, module: &M) -> Vec<(String, Shared<Node>)> {
+    module
+        .named_parameters()
+        .into_iter()
+        .map(|(param, node)| (format!("{name}.{param}"), node))
+        .collect()
+}
+
+/// Prefixes the buffer names of a child module with the given field name
+pub fn scoped_buffers<M: Module>(name: &str, module: &M) -> Vec<(String, Shared<Node>)> {
+    module
+        .named_buffers()
+        .into_iter()
+        .map(|(buffer, node)| (format!("{name}.{buffer}"), node))
+        .collect()
+}
+
+/// The reasons a state dictionary can not be loaded into a `Module`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The named parameter is not in the state dictionary
+    Missing(String),
+    /// The shape of the named parameter does not match the one in the state dictionary
+    Shape(String),
+}
+
+impl fmt::Display for StateError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Missing(ref name) => write!(f, "missing parameter {name}"),
+            Self::Shape(ref name) => write!(f, "shape mismatch for parameter {name}"),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Module, StateError};
+    use crate::nn::models::MLP;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn state_dict_roundtrip() {
+        let source = MLP::<3, 4, 2>::randn();
+        let target = MLP::<3, 4, 2>::randn();
+
+        let names: Vec<String> = source
+            .named_parameters()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["hidden.weights", "output.weights"]);
+
+        let mut state = source.state_dict();
+        target.load_state_dict(&state).unwrap();
+        for ((_, x), (_, y)) in source
+            .named_parameters()
+            .iter()
+            .zip(target.named_parameters())
+        {
+            assert!(equal_data(x.data().clone(), y.data().clone()));
+        }
+
+        state.remove("output.weights");
+        assert_eq!(
+            target.load_state_dict(&state),
+            Err(StateError::Missing(String::from("output.weights")))
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Verification of models exported to [ONNX](https://onnx.ai).
+//!
+//! Exported models are run with [tract](https://github.com/sonos/tract) on the same input as
+//! the mushin model and their outputs compared, to trust the export before deployment.
+//!
+//! ## Usage
+//! ```no_run
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{layers::Linear, onnx::divergence};
+//!
+//! let model = Linear::<3, 2>::randn();
+//! let x = mu::randn::<4, 1, 1, 3>().freeze();
+//!
+//! let max = divergence("linear.onnx", &x, &model.forward(&x))?;
+//! assert!(max < 1e-5);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::tensor::{
+    traits::{Data, Tensed},
+    Float, Tensor,
+};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use tract_onnx::prelude::{
+    tract_ndarray::{ArrayD, IxDyn},
+    tvec, Datum, Framework, InferenceFact, InferenceModel, InferenceModelExt, IntoTensor,
+    TypedModel,
+};
+use tract_onnx::tract_hir::infer::Factoid;
+
+/// Returns an invalid data error with the message of the given error
+#[allow(clippy::needless_pass_by_value)]
+fn invalid<E: ToString>(error: E) -> Error {
+    Error::new(ErrorKind::InvalidData, error.to_string())
+}
+
+/// Copies the values of a tensor to the host, row-major with shape `[B, C, H, W]` as in
+/// `nn::io`, converted to the element type of ONNX models
+#[allow(clippy::unnecessary_cast, clippy::cast_possible_truncation)]
+fn row_major<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    tensor: &Tensor<B, C, H, W, X>,
+) -> Vec<f32> {
+    // Arrayfire arrays are column-major, transposing rows and columns makes them row-major
+    let values = arrayfire::transpose(&tensor.data(), false);
+    let mut host = vec![0.0 as Float; values.elements()];
+    values.host(&mut host);
+    host.into_iter().map(|value| value as f32).collect()
+}
+
+/// Runs the ONNX model at the given path on the input, and returns the maximum absolute
+/// difference between its first output and the expected one, i.e. computed by the model
+/// that was exported.
+///
+/// The input is given with shape `[B, C, H, W]`, dropping the channel and height dimensions
+/// when the ONNX model takes less dimensions, i.e. `[B, W]` for linear layers. Outputs are
+/// compared value by value in row-major order, so they only have to match in size
+///
+/// # Errors
+///
+/// Returns an error if the model can not be loaded or run on the input, or if its output
+/// has a different number of values than the expected one
+#[inline]
+pub fn divergence<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const OB: u64,
+    const OC: u64,
+    const OH: u64,
+    const OW: u64,
+    X: Data,
+    Y: Data,
+>(
+    path: impl AsRef<Path>,
+    input: &Tensor<B, C, H, W, X>,
+    expected: &Tensor<OB, OC, OH, OW, Y>,
+) -> Result<Float> {
+    let model = tract_onnx::onnx().model_for_path(path).map_err(invalid)?;
+    compare(model, input, expected)
+}
+
+/// Same as `divergence`, with the model already loaded
+#[allow(clippy::cast_possible_truncation, clippy::useless_conversion)]
+fn compare<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const OB: u64,
+    const OC: u64,
+    const OH: u64,
+    const OW: u64,
+    X: Data,
+    Y: Data,
+>(
+    model: InferenceModel,
+    input: &Tensor<B, C, H, W, X>,
+    expected: &Tensor<OB, OC, OH, OW, Y>,
+) -> Result<Float> {
+    let rank = model
+        .input_fact(0)
+        .map_err(invalid)?
+        .shape
+        .rank()
+        .concretize()
+        .and_then(|rank| usize::try_from(rank).ok())
+        .unwrap_or(4);
+    let mut shape = vec![B as usize, C as usize, H as usize, W as usize];
+    while shape.len() > rank.max(2) {
+        shape.remove(1);
+    }
+
+    let runnable = model
+        .with_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), &shape))
+        .and_then(InferenceModelExt::into_optimized)
+        .and_then(TypedModel::into_runnable)
+        .map_err(invalid)?;
+    let values = ArrayD::from_shape_vec(IxDyn(&shape), row_major(input)).map_err(invalid)?;
+    let outputs = runnable
+        .run(tvec!(values.into_tensor().into()))
+        .map_err(invalid)?;
+    let output = outputs
+        .first()
+        .ok_or_else(|| invalid("the model has no outputs"))?
+        .to_array_view::<f32>()
+        .map_err(invalid)?;
+
+    let expected = row_major(expected);
+    if output.len() != expected.len() {
+        return Err(invalid(format!(
+            "the model output has {} values, {} were expected",
+            output.len(),
+            expected.len()
+        )));
+    }
+    let max = output
+        .iter()
+        .zip(expected)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+    Ok(Float::from(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, divergence};
+    use crate as mu;
+    use crate::nn::activations::relu;
+    use tract_onnx::pb::{
+        type_proto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TypeProto,
+        ValueInfoProto,
+    };
+    use tract_onnx::prelude::Framework;
+
+    /// Returns a float tensor graph input or output with the given name
+    fn value(name: &str) -> ValueInfoProto {
+        ValueInfoProto {
+            name: name.into(),
+            r#type: Some(TypeProto {
+                value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                    elem_type: 1,
+                    shape: None,
+                })),
+                ..TypeProto::default()
+            }),
+            ..ValueInfoProto::default()
+        }
+    }
+
+    #[test]
+    fn onnx_divergence() {
+        let proto = ModelProto {
+            ir_version: 7,
+            opset_import: vec![OperatorSetIdProto {
+                domain: String::new(),
+                version: 13,
+            }],
+            graph: Some(GraphProto {
+                node: vec![NodeProto {
+                    input: vec!["x".into()],
+                    output: vec!["y".into()],
+                    op_type: "Relu".into(),
+                    ..NodeProto::default()
+                }],
+                input: vec![value("x")],
+                output: vec![value("y")],
+                ..GraphProto::default()
+            }),
+            ..ModelProto::default()
+        };
+        let model = || tract_onnx::onnx().model_for_proto_model(&proto).unwrap();
+
+        let x = mu::custom::<2, 1, 1, 3>(&[-1.0, 2.0, 0.5, -3.0, 1.0, 4.0]).freeze();
+        assert!(compare(model(), &x, &relu(&x)).unwrap() < 1e-6);
+        assert!((compare(model(), &x, &x).unwrap() - 3.0).abs() < 1e-6);
+        assert!(compare(model(), &x, &mu::fill::<1, 1, 1, 1>(0.0)).is_err());
+
+        assert!(divergence("missing.onnx", &x, &x).is_err());
+    }
+}
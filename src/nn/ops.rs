@@ -1,82 +1,216 @@
 use crate::{
     ops::reshape,
-    tensor::{traits::Tensed, Tensor},
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Tensed},
+        Float, Tensor,
+    },
 };
 use arrayfire::{dim4, view, Array, Seq};
 
 // Given an input tensor, returns a tensor that keeps the same batch size, but with the rest
 // of the dimensions flattened to a vector.
 #[inline]
-pub fn flatten<X: Tensed>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, 1, 1, { X::CHANNELS * X::HEIGHT * X::WIDTH }, X::Data> {
+pub fn flatten<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, 1, 1, { C * H * W }, X> {
+    let _op = profiler::forward("flatten");
     reshape(x)
 }
 
-// Performs the 2-dimensional max pooling operation on a given tensor.
-#[allow(clippy::cast_possible_truncation)]
+// Performs the 2-dimensional max pooling operation on a given tensor, with a `H` x `W` window
+// moved by `S` values at a time.
+// When windows overlap (`S` smaller than the kernel), the gradient of an input value that is the
+// maximum of several windows is routed from the last of them only.
 #[inline]
-pub fn maxpool2d<const H: u64, const W: u64, const S: u64, X: Tensed>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { (X::HEIGHT - H) / S }, { (X::WIDTH - W) / S }, X::Data>
+pub fn maxpool2d<const H: u64, const W: u64, const S: u64, X: Pool<H, W, S>>(x: &X) -> X::Output {
+    x.maxpool2d()
+}
+
+/// Tensors that can be max pooled with a `H` x `W` window and stride `S`, see `maxpool2d`.
+/// The pooled shape is an associated type, so that only the window has to be given explicitly
+pub trait Pool<const H: u64, const W: u64, const S: u64> {
+    /// The pooled tensor
+    type Output;
+
+    /// Returns the maximum of every window
+    fn maxpool2d(&self) -> Self::Output;
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+impl<
+        const H: u64,
+        const W: u64,
+        const S: u64,
+        const B: u64,
+        const C: u64,
+        const XH: u64,
+        const XW: u64,
+        X: Data,
+    > Pool<H, W, S> for Tensor<B, C, XH, XW, X>
 where
-    [(); (X::BATCH * X::CHANNELS * (X::HEIGHT - H + 2) / S * (X::WIDTH - W + 2) / S) as usize]:,
-    [(); ({ X::BATCH } * { X::CHANNELS } * { X::HEIGHT } * { X::WIDTH }) as usize]:,
+    [(); { (XH - H) / S + 1 } as usize]:,
+    [(); { (XW - W) / S + 1 } as usize]:,
 {
-    let input = x.data();
-    let values = &mut [0.0; (X::BATCH * X::CHANNELS * (X::HEIGHT - H + 2) / S * (X::WIDTH - W + 2)
-        / S) as usize];
-    let indices =
-        &mut [0.0; ({ X::BATCH } * { X::CHANNELS } * { X::HEIGHT } * { X::WIDTH }) as usize];
-    let mut count = 0;
-
-    for b in 0..X::BATCH {
-        for c in 0..X::CHANNELS {
-            for w in (0..X::WIDTH).step_by(S as usize) {
-                for h in (0..X::HEIGHT).step_by(S as usize) {
-                    let batch = Seq::new(b as i32, b as i32, 1);
-                    let channel = Seq::new(c as i32, c as i32, 1);
-                    let rows = Seq::new(h as i32, (h + H - 1) as i32, 1);
-                    let cols = Seq::new(w as i32, (w + W - 1) as i32, 1);
-                    let (v, _, i) = arrayfire::imax_all(&view!(input[rows, cols, channel, batch]));
-
-                    let index = i as usize
-                        + b as usize * (X::CHANNELS * X::HEIGHT * X::WIDTH) as usize
-                        + c as usize * (X::HEIGHT * X::WIDTH) as usize
-                        + w as usize * X::HEIGHT as usize
-                        + h as usize;
-
-                    values[count] = v;
-                    indices[index] = 1.0;
-                    count += 1;
+    type Output = Tensor<B, C, { (XH - H) / S + 1 }, { (XW - W) / S + 1 }, X>;
+
+    #[inline]
+    fn maxpool2d(&self) -> Self::Output {
+        let _op = profiler::forward("maxpool2d");
+        let input = self.data();
+        let (out_h, out_w) = ((XH - H) / S + 1, (XW - W) / S + 1);
+        let mut values = vec![0.0; (B * C * out_h * out_w) as usize];
+        let mut mask = vec![0.0; (B * C * XH * XW) as usize];
+        // For every input value, the index of the output value it was pooled into
+        let mut owners = mask.clone();
+        let mut count = 0;
+
+        for b in 0..B {
+            for c in 0..C {
+                for w in (0..=XW - W).step_by(S as usize) {
+                    for h in (0..=XH - H).step_by(S as usize) {
+                        let batch = Seq::new(b as i32, b as i32, 1);
+                        let channel = Seq::new(c as i32, c as i32, 1);
+                        let rows = Seq::new(h as i32, (h + H - 1) as i32, 1);
+                        let cols = Seq::new(w as i32, (w + W - 1) as i32, 1);
+                        let (v, _, i) =
+                            arrayfire::imax_all(&view!(input[rows, cols, channel, batch]));
+
+                        let index = b * (C * XH * XW)
+                            + c * (XH * XW)
+                            + (w + u64::from(i) / H) * XH
+                            + h
+                            + u64::from(i) % H;
+
+                        values[count] = v;
+                        mask[index as usize] = 1.0;
+                        owners[index as usize] = count as Float;
+                        count += 1;
+                    }
                 }
             }
         }
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            let (m, o) = (&args[0], &args[1]);
+            let routed =
+                arrayfire::lookup(&arrayfire::flat(df), &arrayfire::flat(&o.cast::<u32>()), 0);
+            m * arrayfire::moddims(&routed, m.dims())
+        };
+
+        self.push_unary(
+            Array::new(&values, dim4!(out_h, out_w, C, B)),
+            reverse,
+            vec![
+                Array::new(&mask, dim4!(XH, XW, C, B)),
+                Array::new(&owners, dim4!(XH, XW, C, B)),
+            ],
+        )
     }
+}
+
+/// Averages the values of every sample of the batch where the mask is 1, i.e. to ignore the
+/// padding of sequences. Fully masked samples average to 0
+#[inline]
+pub fn masked_mean<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+    mask: &Tensor<B, C, H, W, Constant>,
+) -> Tensor<B, 1, 1, 1, X> {
+    let _op = profiler::forward("masked_mean");
+    let mask = mask.data();
+    let per_sample =
+        |a: &Array<Float>| arrayfire::sum(&arrayfire::sum(&arrayfire::sum(a, 0), 1), 2);
+    let counts = arrayfire::maxof(&per_sample(&mask), &(1.0 as Float), false);
+    let scale = arrayfire::div(&mask, &counts, true);
+    let result = per_sample(&(x.data() * &scale));
 
-    let reverse = |_: &Array<f32>, args: &[Array<f32>]| args[0].clone();
-    x.push_unary(
-        Array::new(
-            values,
-            dim4!(
-                { (X::HEIGHT - H + 2) / S },
-                { (X::WIDTH - W + 2) / S },
-                { X::CHANNELS },
-                { X::BATCH }
-            ),
-        ),
-        reverse,
-        &[Array::new(
-            indices,
-            dim4!({ X::HEIGHT }, { X::WIDTH }, { X::CHANNELS }, { X::BATCH }),
-        )],
-    )
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| arrayfire::mul(&args[0], df, true);
+
+    x.push_unary(result, reverse, vec![scale])
+}
+
+/// Splits a dimension of the given size into windows of the given size overlapping by twice
+/// the margin. Returns, for every window, where it starts and the start and length of the part
+/// of the output it computes, which is at least `margin` values away from its inner borders
+#[allow(clippy::cast_possible_truncation)]
+fn tile_spans(size: u64, tile: u64, margin: u64) -> Vec<(u64, u64, u64)> {
+    let step = tile - 2 * margin;
+    (0..size)
+        .step_by(step as usize)
+        .map(|start| {
+            let window = start.saturating_sub(margin).min(size - tile);
+            (window, start, step.min(size - start))
+        })
+        .collect()
+}
+
+/// Computes the output of a module that keeps the height and width of its input, one tile of
+/// `TH` x `TW` values at a time.
+///
+/// The module is i.e. a stack of `Conv2D::forward_same` layers, and only the activations of a
+/// single tile are held in device memory at once. Tiles overlap by twice
+/// the given margin, which must cover the receptive field of the module beyond the tile values
+/// it keeps, i.e. one value per 3x3 convolution. The computation graph of every tile is dropped
+/// once its output is stitched into the result.
+///
+/// # Panics
+///
+/// Panics if the tiles are larger than the input or twice the margin does not fit in a tile
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+#[inline]
+pub fn tiled<
+    const TH: u64,
+    const TW: u64,
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    const O: u64,
+    Y: Data,
+    F,
+>(
+    x: &Tensor<B, C, H, W, Constant>,
+    margin: u64,
+    module: F,
+) -> Tensor<B, O, H, W, Constant>
+where
+    F: Fn(&Tensor<B, C, TH, TW, Constant>) -> Tensor<B, O, TH, TW, Y>,
+{
+    assert!(
+        TH <= H && TW <= W && 2 * margin < TH.min(TW),
+        "tiles of {TH}x{TW} with a margin of {margin} do not fit an input of {H}x{W}"
+    );
+
+    let input = x.data();
+    let mut output = arrayfire::constant(0.0 as Float, dim4!(H, W, O, B));
+    let (all, seq) = (Seq::default(), |start: u64, len: u64| {
+        Seq::new(start as i32, (start + len - 1) as i32, 1)
+    });
+    for (row, out_row, rows) in tile_spans(H, TH, margin) {
+        for (col, out_col, cols) in tile_spans(W, TW, margin) {
+            let tile = arrayfire::index(&input, &[seq(row, TH), seq(col, TW), all, all]);
+            let result = module(&Constant::new(tile).into()).data();
+            let kept = arrayfire::index(
+                &result,
+                &[seq(out_row - row, rows), seq(out_col - col, cols), all, all],
+            );
+            arrayfire::assign_seq(
+                &mut output,
+                &[seq(out_row, rows), seq(out_col, cols), all, all],
+                &kept,
+            );
+        }
+    }
+    Constant::new(output).into()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{flatten, maxpool2d, Tensed};
+    use super::{flatten, masked_mean, maxpool2d, tile_spans, tiled, Tensed};
     use crate as mu;
+    use crate::nn::layers::Conv2D;
+    use crate::tensor::{variable::Variable, Tensor};
     use crate::tests::equal_data;
     use arrayfire::Array;
 
@@ -93,6 +227,28 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn maxpool2d_overlapping_windows() {
+        // Every window of a 3x3 input has its maximum at a different corner
+        let x = mu::custom::<1, 1, 3, 3>(&[9.0, 1.0, 8.0, 2.0, 0.0, 3.0, 7.0, 4.0, 6.0]);
+        let z: Tensor<1, 1, 2, 2, Variable> = maxpool2d::<2, 2, 1, _>(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[9.0, 8.0, 7.0, 6.0], arrayfire::dim4!(2, 2, 1, 1))
+        ));
+
+        // The incoming gradients are routed to the maximum of every window
+        let weights = mu::custom::<1, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0]).freeze();
+        mu::mul(&z, &weights).backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(
+                &[1.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0, 0.0, 4.0],
+                arrayfire::dim4!(3, 3, 1, 1)
+            )
+        ));
+    }
+
     #[test]
     fn maxpool2d_forward_backward() {
         let x = mu::custom::<1, 1, 4, 4>(&[
@@ -113,4 +269,41 @@ mod tests {
             )
         ));
     }
+
+    #[test]
+    fn tile_spans_cover_input() {
+        assert_eq!(tile_spans(10, 6, 1), vec![(0, 0, 4), (3, 4, 4), (4, 8, 2)]);
+        assert_eq!(tile_spans(4, 4, 0), vec![(0, 0, 4)]);
+    }
+
+    #[test]
+    fn tiled_matches_whole_input() {
+        let conv = Conv2D::<2, 3, 3, 3>::randn().freeze();
+        let x = mu::randn::<2, 2, 10, 9>().freeze();
+
+        let z = tiled::<6, 5, 2, 2, 10, 9, 3, _, _>(&x, 1, |tile| conv.forward_same(tile));
+        let expected = conv.forward_same(&x).data();
+        let error = arrayfire::max_all(&arrayfire::abs(&(expected - z.data()))).0;
+        assert!(error < 1e-5);
+    }
+
+    #[test]
+    fn masked_mean_forward_backward() {
+        let x = mu::custom::<2, 1, 1, 3>(&[1.0, 2.0, 9.0, 4.0, 5.0, 6.0]);
+        let mask = mu::custom::<2, 1, 1, 3>(&[1.0, 1.0, 0.0, 0.0, 0.0, 0.0]).freeze();
+        let z = masked_mean(&x, &mask);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.5, 0.0], arrayfire::dim4!(1, 1, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(
+                &[0.5, 0.5, 0.0, 0.0, 0.0, 0.0],
+                arrayfire::dim4!(1, 3, 1, 2)
+            )
+        ));
+    }
 }
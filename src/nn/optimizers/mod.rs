@@ -0,0 +1,618 @@
+pub mod schedulers;
+
+use crate::graph::{node::Node, shared::Shared};
+use crate::{gen::engine, tensor::Float};
+use arrayfire::{Array, RandomEngine};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+
+/// Common methods for all the optimizers
+pub trait Optimizer {
+    /// Updates the parameters with their current gradients
+    fn step(&self);
+
+    /// Returns the current learning rate
+    fn lr(&self) -> Float;
+
+    /// Sets a new learning rate, to be used from the next step onwards
+    fn set_lr(&mut self, lr: Float);
+
+    /// Returns the parameters being optimized
+    fn parameters(&self) -> &[Shared<Node>];
+
+    /// Sets the gradients of the optimized parameters to zero, leaving the rest of the graph untouched
+    #[inline]
+    fn zero_grad(&self) {
+        for node in self.parameters() {
+            node.zero_grad();
+        }
+    }
+}
+
+/// Numerical stability term added to the denominator of adaptive updates
+const EPSILON: Float = 1e-8;
+
+/// Keeps only the parameters that are variable declarations, the ones that can be optimized.
+/// Parameters shared by several layers, i.e. tied weights, are kept once. They are told apart
+/// by address, as the ids of dropped nodes are reused
+fn declarations<'n, P>(params: P) -> Vec<Shared<Node>>
+where
+    P: IntoIterator<Item = &'n Shared<Node>>,
+{
+    let mut seen = HashSet::new();
+    params
+        .into_iter()
+        .filter_map(|n| {
+            if n.is_declaration() && seen.insert(Shared::as_ptr(n)) {
+                Some(n.clone())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the euclidean norm of the given array
+fn norm(a: &Array<Float>) -> Float {
+    arrayfire::sum_all(&(a * a)).0.sqrt()
+}
+
+/// Returns the ratio between the parameter and update norms, or one if any of them is zero
+fn trust_ratio(param: &Array<Float>, update: &Array<Float>) -> Float {
+    let (p, u) = (norm(param), norm(update));
+    if p > 0.0 && u > 0.0 {
+        p / u
+    } else {
+        1.0
+    }
+}
+
+/// Returns a zero filled buffer for each of the parameters, with their same dimensions
+fn zeros(params: &[Shared<Node>]) -> Vec<Array<Float>> {
+    params
+        .iter()
+        .map(|n| arrayfire::constant(0.0 as Float, n.data().dims()))
+        .collect()
+}
+
+/// Stochastic Gradient Descent, optionally with classical or Nesterov momentum and weight decay
+pub struct SGD {
+    lr: Float,
+    momentum: Float,
+    nesterov: bool,
+    weight_decay: Float,
+    params: Vec<Shared<Node>>,
+    velocities: RefCell<Vec<Array<Float>>>,
+}
+
+impl SGD {
+    #[inline]
+    pub fn new<'n, P>(params: P, lr: Float) -> Self
+    where
+        P: IntoIterator<Item = &'n Shared<Node>>,
+    {
+        let params = declarations(params);
+        let velocities = zeros(&params);
+
+        Self {
+            lr,
+            momentum: 0.0,
+            nesterov: false,
+            weight_decay: 0.0,
+            params,
+            velocities: RefCell::new(velocities),
+        }
+    }
+
+    /// Consumes this optimizer and returns a copy accumulating a velocity for each
+    /// parameter, decayed by the given momentum factor
+    #[must_use]
+    #[inline]
+    pub const fn momentum(mut self, momentum: Float) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    /// Consumes this optimizer and returns a copy using Nesterov momentum,
+    /// which evaluates the gradient step ahead along the velocity
+    #[must_use]
+    #[inline]
+    pub const fn nesterov(mut self) -> Self {
+        self.nesterov = true;
+        self
+    }
+
+    /// Consumes this optimizer and returns a copy applying L2 regularization to the
+    /// parameters, by adding `weight_decay * w` to their gradients
+    #[must_use]
+    #[inline]
+    pub const fn weight_decay(mut self, weight_decay: Float) -> Self {
+        self.weight_decay = weight_decay;
+        self
+    }
+}
+
+impl Optimizer for SGD {
+    #[inline]
+    fn step(&self) {
+        for (node, velocity) in self
+            .params
+            .iter()
+            .zip(self.velocities.borrow_mut().iter_mut())
+        {
+            let grad = node.grad() + self.weight_decay * &node.data().clone();
+            *velocity = self.momentum * &*velocity + &grad;
+
+            let direction = if self.nesterov {
+                grad + self.momentum * &*velocity
+            } else {
+                velocity.clone()
+            };
+
+            let step = arrayfire::sub(&node.data().clone(), &(self.lr * &direction), true);
+            node.set_data(step);
+        }
+    }
+
+    #[inline]
+    fn lr(&self) -> Float {
+        self.lr
+    }
+
+    #[inline]
+    fn set_lr(&mut self, lr: Float) {
+        self.lr = lr;
+    }
+
+    #[inline]
+    fn parameters(&self) -> &[Shared<Node>] {
+        &self.params
+    }
+}
+
+/// Adam with decoupled weight decay
+pub struct AdamW {
+    lr: Float,
+    betas: (Float, Float),
+    weight_decay: Float,
+    params: Vec<Shared<Node>>,
+    moments: RefCell<Vec<(Array<Float>, Array<Float>)>>,
+    steps: Cell<i32>,
+}
+
+impl AdamW {
+    /// Returns a new `AdamW` optimizer with the given learning rate, coefficients for the
+    /// running averages of the gradient and its square, and weight decay
+    #[inline]
+    pub fn new<'n, P>(params: P, lr: Float, betas: (Float, Float), weight_decay: Float) -> Self
+    where
+        P: IntoIterator<Item = &'n Shared<Node>>,
+    {
+        let params = declarations(params);
+        let moments = zeros(&params).into_iter().zip(zeros(&params)).collect();
+
+        Self {
+            lr,
+            betas,
+            weight_decay,
+            params,
+            moments: RefCell::new(moments),
+            steps: Cell::new(0),
+        }
+    }
+}
+
+impl Optimizer for AdamW {
+    #[inline]
+    fn step(&self) {
+        let (beta1, beta2) = self.betas;
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+
+        let correction1 = 1.0 - beta1.powi(steps);
+        let correction2 = 1.0 - beta2.powi(steps);
+
+        for (node, &mut (ref mut m, ref mut v)) in
+            self.params.iter().zip(self.moments.borrow_mut().iter_mut())
+        {
+            let grad = node.grad();
+            *m = beta1 * &*m + (1.0 - beta1) * &grad;
+            *v = beta2 * &*v + (1.0 - beta2) * &(&grad * &grad);
+
+            let update = arrayfire::div(
+                &(&*m / correction1),
+                &(arrayfire::sqrt(&(&*v / correction2)) + EPSILON),
+                false,
+            );
+            let decayed = self.lr.mul_add(-self.weight_decay, 1.0) * &node.data().clone();
+            node.set_data(decayed - self.lr * &update);
+        }
+    }
+
+    #[inline]
+    fn lr(&self) -> Float {
+        self.lr
+    }
+
+    #[inline]
+    fn set_lr(&mut self, lr: Float) {
+        self.lr = lr;
+    }
+
+    #[inline]
+    fn parameters(&self) -> &[Shared<Node>] {
+        &self.params
+    }
+}
+
+/// Layer-wise Adaptive Rate Scaling, SGD with momentum where each parameter update is
+/// scaled by the ratio between the parameter and gradient norms
+#[allow(clippy::upper_case_acronyms)]
+pub struct LARS {
+    lr: Float,
+    momentum: Float,
+    weight_decay: Float,
+    params: Vec<Shared<Node>>,
+    velocities: RefCell<Vec<Array<Float>>>,
+}
+
+impl LARS {
+    /// Coefficient applied to the trust ratio of each parameter
+    const TRUST: Float = 0.001;
+
+    /// Returns a new `LARS` optimizer with the given learning rate, momentum and weight decay
+    #[inline]
+    pub fn new<'n, P>(params: P, lr: Float, momentum: Float, weight_decay: Float) -> Self
+    where
+        P: IntoIterator<Item = &'n Shared<Node>>,
+    {
+        let params = declarations(params);
+        let velocities = zeros(&params);
+
+        Self {
+            lr,
+            momentum,
+            weight_decay,
+            params,
+            velocities: RefCell::new(velocities),
+        }
+    }
+}
+
+impl Optimizer for LARS {
+    #[inline]
+    fn step(&self) {
+        for (node, velocity) in self
+            .params
+            .iter()
+            .zip(self.velocities.borrow_mut().iter_mut())
+        {
+            let data = node.data().clone();
+            let grad = node.grad() + self.weight_decay * &data;
+            let local_lr = self.lr * Self::TRUST * trust_ratio(&data, &grad);
+
+            *velocity = self.momentum * &*velocity + local_lr * &grad;
+            node.set_data(data - &*velocity);
+        }
+    }
+
+    #[inline]
+    fn lr(&self) -> Float {
+        self.lr
+    }
+
+    #[inline]
+    fn set_lr(&mut self, lr: Float) {
+        self.lr = lr;
+    }
+
+    #[inline]
+    fn parameters(&self) -> &[Shared<Node>] {
+        &self.params
+    }
+}
+
+/// Layer-wise Adaptive Moments for Batch training, `AdamW` where each parameter update is
+/// scaled by the ratio between the parameter and update norms
+#[allow(clippy::upper_case_acronyms)]
+pub struct LAMB {
+    lr: Float,
+    betas: (Float, Float),
+    weight_decay: Float,
+    params: Vec<Shared<Node>>,
+    moments: RefCell<Vec<(Array<Float>, Array<Float>)>>,
+    steps: Cell<i32>,
+}
+
+impl LAMB {
+    /// Returns a new `LAMB` optimizer with the given learning rate, coefficients for the
+    /// running averages of the gradient and its square, and weight decay
+    #[inline]
+    pub fn new<'n, P>(params: P, lr: Float, betas: (Float, Float), weight_decay: Float) -> Self
+    where
+        P: IntoIterator<Item = &'n Shared<Node>>,
+    {
+        let params = declarations(params);
+        let moments = zeros(&params).into_iter().zip(zeros(&params)).collect();
+
+        Self {
+            lr,
+            betas,
+            weight_decay,
+            params,
+            moments: RefCell::new(moments),
+            steps: Cell::new(0),
+        }
+    }
+}
+
+impl Optimizer for LAMB {
+    #[inline]
+    fn step(&self) {
+        let (beta1, beta2) = self.betas;
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+
+        let correction1 = 1.0 - beta1.powi(steps);
+        let correction2 = 1.0 - beta2.powi(steps);
+
+        for (node, &mut (ref mut m, ref mut v)) in
+            self.params.iter().zip(self.moments.borrow_mut().iter_mut())
+        {
+            let data = node.data().clone();
+            let grad = node.grad();
+            *m = beta1 * &*m + (1.0 - beta1) * &grad;
+            *v = beta2 * &*v + (1.0 - beta2) * &(&grad * &grad);
+
+            let update = arrayfire::div(
+                &(&*m / correction1),
+                &(arrayfire::sqrt(&(&*v / correction2)) + EPSILON),
+                false,
+            ) + self.weight_decay * &data;
+            let local_lr = self.lr * trust_ratio(&data, &update);
+            node.set_data(data - local_lr * &update);
+        }
+    }
+
+    #[inline]
+    fn lr(&self) -> Float {
+        self.lr
+    }
+
+    #[inline]
+    fn set_lr(&mut self, lr: Float) {
+        self.lr = lr;
+    }
+
+    #[inline]
+    fn parameters(&self) -> &[Shared<Node>] {
+        &self.params
+    }
+}
+
+/// Wraps an optimizer to clip the gradients of every parameter and add Gaussian noise to them
+/// before every update.
+///
+/// This is the building block of differentially private training (DP-SGD). The gradients are
+/// clipped to a maximum euclidean norm, and the noise has a standard deviation of
+/// `noise_multiplier * max_norm`. For losses averaged over a batch, divide the noise multiplier
+/// by the batch size
+pub struct GradientNoise<O: Optimizer> {
+    optimizer: O,
+    max_norm: Float,
+    noise_multiplier: Float,
+    engine: Option<RandomEngine>,
+}
+
+impl<O: Optimizer> GradientNoise<O> {
+    /// Returns a new `GradientNoise` wrapping the given optimizer, with the given maximum norm
+    /// of the gradients and ratio of the standard deviation of the noise to it
+    #[inline]
+    pub const fn new(optimizer: O, max_norm: Float, noise_multiplier: Float) -> Self {
+        Self {
+            optimizer,
+            max_norm,
+            noise_multiplier,
+            engine: None,
+        }
+    }
+
+    /// Consumes this optimizer and returns a copy taking the noise from a random number
+    /// generator seeded with the given value, instead of the global one
+    #[must_use]
+    #[inline]
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            engine: Some(engine(seed)),
+            ..self
+        }
+    }
+
+    /// Returns the wrapped optimizer
+    #[inline]
+    pub const fn inner(&self) -> &O {
+        &self.optimizer
+    }
+}
+
+impl<O: Optimizer> Optimizer for GradientNoise<O> {
+    #[inline]
+    fn step(&self) {
+        for node in self.optimizer.parameters() {
+            let grad = node.grad();
+            let norm = norm(&grad);
+            let clipped = if norm > self.max_norm {
+                grad * (self.max_norm / norm)
+            } else {
+                grad
+            };
+
+            let dims = clipped.dims();
+            let noise = self.engine.as_ref().map_or_else(
+                || arrayfire::randn::<Float>(dims),
+                |engine| arrayfire::random_normal::<Float>(dims, engine),
+            );
+            node.set_grad(clipped + noise * (self.noise_multiplier * self.max_norm));
+        }
+        self.optimizer.step();
+    }
+
+    #[inline]
+    fn lr(&self) -> Float {
+        self.optimizer.lr()
+    }
+
+    #[inline]
+    fn set_lr(&mut self, lr: Float) {
+        self.optimizer.set_lr(lr);
+    }
+
+    #[inline]
+    fn parameters(&self) -> &[Shared<Node>] {
+        self.optimizer.parameters()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdamW, GradientNoise, Optimizer, LAMB, LARS, SGD};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn shared_parameters_kept_once() {
+        // The id of the dropped node is reused by the next one
+        let dropped = mu::fill::<1, 1, 1, 1>(1.0);
+        let first = mu::fill::<1, 1, 1, 1>(1.0);
+        drop(dropped);
+        let second = mu::fill::<1, 1, 1, 1>(1.0);
+
+        let node = first.inner().node();
+        let optim = SGD::new(&[node.clone(), second.inner().node(), node], 0.1);
+        assert_eq!(optim.parameters().len(), 2);
+
+        first.backward();
+        second.backward();
+        optim.step();
+        assert!(equal_data(
+            first.data(),
+            arrayfire::constant!(0.9; 1, 1, 1, 1)
+        ));
+        assert!(equal_data(
+            second.data(),
+            arrayfire::constant!(0.9; 1, 1, 1, 1)
+        ));
+    }
+
+    #[test]
+    fn sgd_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = SGD::new(&[x.inner().node()], 0.1);
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.9; 1,1,1,1)));
+    }
+
+    #[test]
+    fn adamw_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = AdamW::new(&[x.inner().node()], 0.1, (0.9, 0.999), 0.01);
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.899; 1,1,1,1)));
+    }
+
+    #[test]
+    fn sgd_momentum_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = SGD::new(&[x.inner().node()], 0.1).momentum(0.9);
+
+        x.backward();
+        optim.step();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.71; 1,1,1,1)));
+    }
+
+    #[test]
+    fn sgd_nesterov_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = SGD::new(&[x.inner().node()], 0.1).momentum(0.9).nesterov();
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.81; 1,1,1,1)));
+    }
+
+    #[test]
+    fn sgd_weight_decay_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = SGD::new(&[x.inner().node()], 0.1).weight_decay(0.1);
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.89; 1,1,1,1)));
+    }
+
+    #[test]
+    fn lars_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = LARS::new(&[x.inner().node()], 0.1, 0.9, 0.0);
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.9999; 1,1,1,1)));
+    }
+
+    #[test]
+    fn lamb_step() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = LAMB::new(&[x.inner().node()], 0.1, (0.9, 0.999), 0.0);
+
+        x.backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.9; 1,1,1,1)));
+    }
+
+    #[test]
+    fn optimizer_zero_grad() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let optim = SGD::new(&[x.inner().node()], 0.1);
+
+        x.backward();
+        optim.zero_grad();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(0.0; 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn gradient_noise_step() {
+        // The gradients (6, 8) are clipped to (3, 4)
+        let x = mu::custom::<1, 1, 1, 2>(&[3.0, 4.0]);
+        let optim = GradientNoise::new(SGD::new(&[x.inner().node()], 1.0), 5.0, 0.0);
+        mu::mul(&x, &x).backward();
+        optim.step();
+        assert!(equal_data(x.data(), arrayfire::constant!(0.0; 1,2,1,1)));
+
+        // The noise is reproducible with a seed
+        let steps: Vec<_> = (0..2)
+            .map(|_| {
+                let x = mu::fill::<1, 1, 1, 2>(0.0);
+                let optim =
+                    GradientNoise::new(SGD::new(&[x.inner().node()], 1.0), 1.0, 1.0).seed(7);
+                x.backward();
+                optim.step();
+                x.data()
+            })
+            .collect();
+        assert!(equal_data(steps[0].clone(), steps[1].clone()));
+        assert!(!equal_data(
+            steps[0].clone(),
+            arrayfire::constant!(-1.0; 1,2,1,1)
+        ));
+    }
+}
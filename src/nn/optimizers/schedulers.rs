@@ -0,0 +1,229 @@
+use crate::nn::optimizers::Optimizer;
+use crate::tensor::Float;
+
+/// Common methods for all the learning rate schedulers
+pub trait Scheduler {
+    /// Updates the learning rate of the given optimizer, to be called once per epoch (or step)
+    fn step<O: Optimizer + ?Sized>(&mut self, optim: &mut O);
+}
+
+/// Decays the learning rate by `gamma` every `step_size` calls to `step`
+pub struct StepLR {
+    step_size: usize,
+    gamma: Float,
+    steps: usize,
+}
+
+impl StepLR {
+    /// Returns a new `StepLR` scheduler with the given period and decay factor
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step_size` is zero
+    #[must_use]
+    #[inline]
+    pub const fn new(step_size: usize, gamma: Float) -> Self {
+        assert!(step_size > 0, "the step size must not be zero");
+        Self {
+            step_size,
+            gamma,
+            steps: 0,
+        }
+    }
+}
+
+impl Scheduler for StepLR {
+    #[inline]
+    fn step<O: Optimizer + ?Sized>(&mut self, optim: &mut O) {
+        self.steps += 1;
+        if self.steps % self.step_size == 0 {
+            optim.set_lr(optim.lr() * self.gamma);
+        }
+    }
+}
+
+/// Decays the learning rate by `gamma` on every call to `step`
+pub struct ExponentialLR {
+    gamma: Float,
+}
+
+impl ExponentialLR {
+    /// Returns a new `ExponentialLR` scheduler with the given decay factor
+    #[must_use]
+    #[inline]
+    pub const fn new(gamma: Float) -> Self {
+        Self { gamma }
+    }
+}
+
+impl Scheduler for ExponentialLR {
+    #[inline]
+    fn step<O: Optimizer + ?Sized>(&mut self, optim: &mut O) {
+        optim.set_lr(optim.lr() * self.gamma);
+    }
+}
+
+/// Decays the learning rate by `factor` once the observed metric (i.e. the validation loss)
+/// has not improved for more than `patience` calls to `step`
+pub struct ReduceLROnPlateau {
+    factor: Float,
+    patience: usize,
+    best: Float,
+    last: Float,
+    bad_steps: usize,
+}
+
+impl ReduceLROnPlateau {
+    /// Returns a new `ReduceLROnPlateau` scheduler with the given decay factor and patience
+    #[must_use]
+    #[inline]
+    pub const fn new(factor: Float, patience: usize) -> Self {
+        Self {
+            factor,
+            patience,
+            best: Float::INFINITY,
+            last: Float::INFINITY,
+            bad_steps: 0,
+        }
+    }
+
+    /// Records the latest value of the metric to minimize, to be called before `step`
+    #[inline]
+    pub fn observe(&mut self, metric: Float) {
+        self.last = metric;
+    }
+}
+
+impl Scheduler for ReduceLROnPlateau {
+    #[inline]
+    fn step<O: Optimizer + ?Sized>(&mut self, optim: &mut O) {
+        if self.last < self.best {
+            self.best = self.last;
+            self.bad_steps = 0;
+        } else {
+            self.bad_steps += 1;
+        }
+
+        if self.bad_steps > self.patience {
+            optim.set_lr(optim.lr() * self.factor);
+            self.bad_steps = 0;
+        }
+    }
+}
+
+/// Increases the learning rate linearly from zero to its initial value during `warmup` calls
+/// to `step`, then anneals it following a cosine down to `min_lr` at `total` calls to `step`
+pub struct CosineWithWarmup {
+    warmup: usize,
+    total: usize,
+    min_lr: Float,
+    base_lr: Option<Float>,
+    steps: usize,
+}
+
+impl CosineWithWarmup {
+    /// Returns a new `CosineWithWarmup` scheduler with the given number of warmup and total steps
+    /// and final learning rate
+    #[must_use]
+    #[inline]
+    pub const fn new(warmup: usize, total: usize, min_lr: Float) -> Self {
+        Self {
+            warmup,
+            total,
+            min_lr,
+            base_lr: None,
+            steps: 0,
+        }
+    }
+}
+
+impl Scheduler for CosineWithWarmup {
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    fn step<O: Optimizer + ?Sized>(&mut self, optim: &mut O) {
+        let base_lr = *self.base_lr.get_or_insert_with(|| optim.lr());
+        self.steps += 1;
+
+        if self.steps < self.warmup {
+            optim.set_lr(base_lr * self.steps as Float / self.warmup as Float);
+        } else {
+            let progress = (self.steps - self.warmup) as Float
+                / self.total.saturating_sub(self.warmup).max(1) as Float;
+            let cosine = 0.5 * (1.0 + (std::f64::consts::PI as Float * progress.min(1.0)).cos());
+            optim.set_lr((base_lr - self.min_lr).mul_add(cosine, self.min_lr));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CosineWithWarmup, ExponentialLR, ReduceLROnPlateau, Scheduler, StepLR};
+    use crate as mu;
+    use crate::nn::optimizers::{Optimizer, SGD};
+    use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
+
+    #[test]
+    fn step_lr() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut optim = SGD::new(&[x.inner().node()], 1.0);
+        let mut scheduler = StepLR::new(2, 0.5);
+
+        scheduler.step(&mut optim);
+        assert!((optim.lr() - 1.0).abs() < Float::EPSILON);
+        scheduler.step(&mut optim);
+        assert!((optim.lr() - 0.5).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    #[should_panic(expected = "the step size must not be zero")]
+    fn step_lr_zero_step_size() {
+        let _ = StepLR::new(0, 0.5);
+    }
+
+    #[test]
+    fn exponential_lr() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut optim = SGD::new(&[x.inner().node()], 1.0);
+        let mut scheduler = ExponentialLR::new(0.5);
+
+        scheduler.step(&mut optim);
+        scheduler.step(&mut optim);
+        assert!((optim.lr() - 0.25).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn reduce_lr_on_plateau() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut optim = SGD::new(&[x.inner().node()], 1.0);
+        let mut scheduler = ReduceLROnPlateau::new(0.1, 1);
+
+        for metric in [1.0, 0.5, 0.6] {
+            scheduler.observe(metric);
+            scheduler.step(&mut optim);
+        }
+        assert!((optim.lr() - 1.0).abs() < Float::EPSILON);
+
+        scheduler.observe(0.7);
+        scheduler.step(&mut optim);
+        assert!((optim.lr() - 0.1).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn cosine_with_warmup() {
+        let x = mu::fill::<1, 1, 1, 1>(1.0);
+        let mut optim = SGD::new(&[x.inner().node()], 1.0);
+        let mut scheduler = CosineWithWarmup::new(2, 4, 0.0);
+
+        let lrs: Vec<Float> = (0..5)
+            .map(|_| {
+                scheduler.step(&mut optim);
+                optim.lr()
+            })
+            .collect();
+
+        for (lr, expected) in lrs.iter().zip([0.5, 1.0, 0.5, 0.0, 0.0]) {
+            assert!((lr - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Magnitude based pruning of the parameters of the `Linear` and `Conv2D` layers.
+//!
+//! Pruning zeroes the parameters of a layer with the smallest magnitude, either individually
+//! with `magnitude` or as whole output channels with `channels`, and returns the `Mask` of the
+//! parameters kept. The mask persists in the layer parameters: the gradients of the pruned
+//! parameters are masked during every backward pass, so that they stay zero while the rest
+//! of the layer is trained. Once done, `Mask::make_permanent` rewrites the parameters and
+//! stops masking their gradients.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{layers::Linear, prune};
+//!
+//! let linear = Linear::<8, 4>::randn();
+//! let mask = prune::magnitude(&linear, 0.5);
+//! assert!(mask.sparsity() > 0.4);
+//!
+//! // ...fine-tune the pruned layer...
+//!
+//! mask.make_permanent();
+//! ```
+
+use crate::{
+    graph::{
+        node::Node,
+        shared::{Lock, Shared},
+    },
+    nn::layers::{Conv2D, Linear},
+    tensor::{variable::Variable, Float},
+};
+use arrayfire::{Array, Dim4};
+
+/// Layers whose parameters can be pruned
+pub trait Prunable {
+    /// Arrayfire dimension along which the output channels of the parameters are laid out
+    const CHANNELS: usize;
+
+    /// Whether the last row of the parameters holds biases, which are only pruned along with
+    /// their channel
+    const BIASES: bool;
+
+    /// Returns the parameters to prune
+    fn pruned_parameters(&self) -> Shared<Node>;
+}
+
+#[allow(clippy::cast_possible_truncation)]
+impl<const I: u64, const O: u64> Prunable for Linear<I, O, Variable>
+where
+    [(); (I + 1) as usize]:,
+{
+    const CHANNELS: usize = 1;
+    const BIASES: bool = true;
+
+    #[inline]
+    fn pruned_parameters(&self) -> Shared<Node> {
+        self.parameters()
+    }
+}
+
+impl<const I: u64, const O: u64, const H: u64, const W: u64> Prunable
+    for Conv2D<I, O, H, W, Variable>
+{
+    const CHANNELS: usize = 3;
+    const BIASES: bool = false;
+
+    #[inline]
+    fn pruned_parameters(&self) -> Shared<Node> {
+        self.parameters()
+    }
+}
+
+/// Returns the `k`th smallest of the given scores, or minus infinity if `k` is zero so that
+/// no score is below it
+fn threshold(scores: &Array<Float>, k: usize) -> Float {
+    if k == 0 {
+        return Float::NEG_INFINITY;
+    }
+    let mut host = vec![0.0; scores.elements()];
+    scores.host(&mut host);
+    host.sort_unstable_by(Float::total_cmp);
+    host[k.min(host.len()) - 1]
+}
+
+/// Returns how many of `n` values make up the given fraction of them
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn fraction(amount: Float, n: usize) -> usize {
+    (amount.clamp(0.0, 1.0) * n as Float).floor() as usize
+}
+
+/// Prunes the given fraction of the weights of a layer with the smallest absolute value. Ties
+/// at the threshold are pruned together, so slightly more weights may be pruned. Biases are
+/// never pruned
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+pub fn magnitude<P: Prunable>(layer: &P, amount: Float) -> Mask {
+    let node = layer.pruned_parameters();
+    let data = node.data().clone();
+    let dims = data.dims();
+
+    let (scores, weights) = if P::BIASES {
+        let biases = Dim4::new(&[1, dims[1], dims[2], dims[3]]);
+        let weights = arrayfire::rows(&arrayfire::abs(&data), 0, dims[0] as i64 - 2);
+        let scores = arrayfire::join(0, &weights, &arrayfire::constant(Float::INFINITY, biases));
+        (scores, weights)
+    } else {
+        let scores = arrayfire::abs(&data);
+        (scores.clone(), scores)
+    };
+
+    let threshold = threshold(&weights, fraction(amount, weights.elements()));
+    let mask = arrayfire::gt(&scores, &threshold, false).cast::<Float>();
+    Mask::new(node, mask)
+}
+
+/// Prunes the given fraction of the output channels of a layer with the smallest euclidean
+/// norm, including their biases. Ties at the threshold are pruned together
+#[must_use]
+#[inline]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub fn channels<P: Prunable>(layer: &P, amount: Float) -> Mask {
+    let node = layer.pruned_parameters();
+    let data = node.data().clone();
+    let dims = data.dims();
+
+    let mut norms = arrayfire::mul(&data, &data, false);
+    for dim in (0..4).filter(|&dim| dim != P::CHANNELS) {
+        norms = arrayfire::sum(&norms, dim as i32);
+    }
+    let norms = arrayfire::sqrt(&norms);
+
+    let threshold = threshold(&norms, fraction(amount, norms.elements()));
+    let kept = arrayfire::gt(&norms, &threshold, false).cast::<Float>();
+    let mut repeats = *dims.get();
+    repeats[P::CHANNELS] = 1;
+    Mask::new(node, arrayfire::tile(&kept, Dim4::new(&repeats)))
+}
+
+/// The parameters of a layer kept by pruning, see the module documentation
+pub struct Mask {
+    node: Shared<Node>,
+    kept: Array<Float>,
+    /// The mask applied to the gradients, released once pruning is made permanent
+    active: Shared<Lock<Option<Array<Float>>>>,
+}
+
+impl Mask {
+    /// Zeroes the pruned parameters of the given node and masks their gradients from now on
+    fn new(node: Shared<Node>, kept: Array<Float>) -> Self {
+        let active = Shared::new(Lock::new(Some(kept.clone())));
+        let masked = active.clone();
+        node.register_grad_transform(move |partial| {
+            masked.borrow().as_ref().map_or_else(
+                || partial.clone(),
+                |mask| arrayfire::mul(partial, mask, true),
+            )
+        });
+
+        let pruned = Self { node, kept, active };
+        pruned.apply();
+        pruned
+    }
+
+    /// Returns the mask, one for the parameters kept and zero for those pruned
+    #[must_use]
+    #[inline]
+    pub fn mask(&self) -> Array<Float> {
+        self.kept.clone()
+    }
+
+    /// Zeroes the pruned parameters again, i.e. after loading new values into the layer
+    #[inline]
+    pub fn apply(&self) {
+        let data = arrayfire::mul(&*self.node.data(), &self.kept, false);
+        self.node.set_data(data);
+    }
+
+    /// Returns the fraction of the parameters that are pruned
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sparsity(&self) -> Float {
+        1.0 - arrayfire::sum_all(&self.kept).0 / self.kept.elements() as Float
+    }
+
+    /// Rewrites the parameters with the pruned ones zeroed and stops masking their gradients,
+    /// so that the layer no longer depends on the mask
+    #[inline]
+    pub fn make_permanent(self) {
+        self.apply();
+        *self.active.borrow_mut() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channels, magnitude};
+    use crate as mu;
+    use crate::nn::layers::{Conv2D, Linear};
+    use crate::tensor::{traits::Tensed, Float};
+    use crate::tests::equal_data;
+    use arrayfire::Array;
+
+    #[test]
+    fn magnitude_pruning() {
+        let linear = Linear::<2, 2>::randn();
+        linear.parameters().set_data(Array::new(
+            &[0.1, -0.4, 5.0, 0.3, -0.2, 6.0],
+            arrayfire::dim4!(3, 2),
+        ));
+
+        let mask = magnitude(&linear, 0.5);
+        assert!(equal_data(
+            mask.mask(),
+            Array::new(&[0.0, 1.0, 1.0, 1.0, 0.0, 1.0], arrayfire::dim4!(3, 2))
+        ));
+        assert!((mask.sparsity() - 1.0 / 3.0).abs() < 1e-6);
+
+        // Gradients of the pruned weights are masked
+        let x = mu::fill::<1, 1, 1, 2>(1.0).freeze();
+        linear.forward(&x).backward();
+        let grad: Vec<Float> = {
+            let grad = linear.parameters().grad();
+            let mut host = vec![0.0; grad.elements()];
+            grad.host(&mut host);
+            host
+        };
+        assert_eq!(grad, vec![0.0, 1.0, 1.0, 1.0, 0.0, 1.0]);
+
+        mask.make_permanent();
+        linear.parameters().zero_grad();
+        linear.forward(&x).backward();
+        assert!(equal_data(
+            linear.parameters().grad(),
+            arrayfire::constant!(1.0; 3, 2, 1, 1)
+        ));
+    }
+
+    #[test]
+    fn channel_pruning() {
+        let conv = Conv2D::<1, 4, 2, 2>::randn();
+        let mask = channels(&conv, 0.5);
+        assert!((mask.sparsity() - 0.5).abs() < 1e-6);
+
+        let z = conv.forward(&mu::randn::<1, 1, 3, 3>().freeze());
+        let zeros = arrayfire::eq(&z.data(), &(0.0 as Float), false).cast::<Float>();
+        assert!((arrayfire::sum_all(&zeros).0 - 8.0).abs() < Float::EPSILON);
+    }
+}
@@ -0,0 +1,269 @@
+//! Post-training quantization of the `Linear` and `Conv2D` layers to 8 bit integers, for
+//! smaller and faster inference.
+//!
+//! Weights are quantized symmetrically per output channel: each channel is scaled so that its
+//! largest magnitude maps to 127 and rounded to the closest integer. The inputs of a quantized
+//! layer are quantized in the same way with a single scale, calibrated beforehand by observing
+//! the range of the inputs the layer takes on representative data. The integer products are
+//! then rescaled, so quantized layers take and return regular constant tensors.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::{layers::Linear, quantize::Calibration};
+//!
+//! let linear = Linear::<3, 5>::randn();
+//! let x = mu::randn::<16, 1, 1, 3>().freeze();
+//!
+//! let mut calibration = Calibration::new();
+//! calibration.observe(&x);
+//!
+//! let quantized = linear.quantize(&calibration);
+//! let z = quantized.forward(&x);
+//! ```
+
+use crate::{
+    profiler,
+    tensor::{
+        constant::Constant,
+        traits::{Data, Tensed},
+        Float, Tensor,
+    },
+};
+use arrayfire::{dim4, Array};
+
+/// Largest magnitude of the quantized values, which are symmetric around zero
+const LEVELS: Float = 127.0;
+
+/// Offset added to the quantized weights to store them unsigned, as arrayfire has no 8 bit
+/// signed integers
+const ZERO_POINT: Float = 128.0;
+
+/// Observes the range of the values a layer takes as input, from which the scale to quantize
+/// them is computed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Calibration {
+    max: Float,
+}
+
+impl Calibration {
+    /// Returns a calibration that observed no values yet
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self { max: 0.0 }
+    }
+
+    /// Widens the observed range to include the values of the given tensor
+    #[inline]
+    pub fn observe<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+        &mut self,
+        x: &Tensor<B, C, H, W, X>,
+    ) {
+        let max = arrayfire::max_all(&arrayfire::abs(&x.data())).0;
+        self.max = self.max.max(max);
+    }
+
+    /// Returns the scale mapping the largest observed magnitude to the largest quantized value,
+    /// or one if only zeros were observed
+    #[must_use]
+    #[inline]
+    pub fn scale(&self) -> Float {
+        if self.max > 0.0 {
+            self.max / LEVELS
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Returns the given weights quantized per output channel, one per column, and the scale of
+/// every channel
+fn quantize_weights(weights: &Array<Float>) -> (Array<u8>, Array<Float>) {
+    let max = arrayfire::max(&arrayfire::abs(weights), 0);
+    let scales = arrayfire::select(
+        &arrayfire::div(&max, &LEVELS, false),
+        &arrayfire::gt(&max, &(0.0 as Float), false),
+        &arrayfire::constant(1.0 as Float, max.dims()),
+    );
+    let quantized = arrayfire::add(
+        &arrayfire::round(&arrayfire::div(weights, &scales, true)),
+        &ZERO_POINT,
+        false,
+    );
+    (quantized.cast::<u8>(), scales)
+}
+
+/// Returns the integer values of the given quantized weights
+fn integers(weights: &Array<u8>) -> Array<Float> {
+    arrayfire::sub(&weights.cast::<Float>(), &ZERO_POINT, false)
+}
+
+/// Returns the given values quantized with the given scale, as integers
+fn quantize_inputs(x: &Array<Float>, scale: Float) -> Array<Float> {
+    arrayfire::clamp(
+        &arrayfire::round(&arrayfire::div(x, &scale, false)),
+        &-LEVELS,
+        &LEVELS,
+        false,
+    )
+}
+
+/// A `Linear` layer with 8 bit integer weights, see `Linear::quantize`
+pub struct QuantizedLinear<const I: u64, const O: u64> {
+    weights: Array<u8>,
+    scales: Array<Float>,
+    biases: Array<Float>,
+    input_scale: Float,
+}
+
+impl<const I: u64, const O: u64> QuantizedLinear<I, O> {
+    /// Quantizes the weights and biases of a `Linear` layer, the latter stored as its last row,
+    /// to take inputs quantized with the given scale
+    #[allow(clippy::cast_possible_wrap)]
+    pub(crate) fn new(parameters: &Array<Float>, input_scale: Float) -> Self {
+        let weights = arrayfire::rows(parameters, 0, I as i64 - 1);
+        let (weights, scales) = quantize_weights(&weights);
+        Self {
+            weights,
+            scales,
+            biases: arrayfire::row(parameters, I as i64),
+            input_scale,
+        }
+    }
+
+    /// Given an input computes the output, with the same shape as the original layer
+    #[must_use]
+    #[inline]
+    pub fn forward<const B: u64, X: Data>(
+        &self,
+        x: &Tensor<B, 1, 1, I, X>,
+    ) -> Tensor<B, 1, 1, O, Constant> {
+        let _op = profiler::forward("quantized_linear");
+        let products = arrayfire::matmul(
+            &quantize_inputs(&x.data(), self.input_scale),
+            &integers(&self.weights),
+            arrayfire::MatProp::NONE,
+            arrayfire::MatProp::NONE,
+        );
+        let scales = self.input_scale * &self.scales;
+        let result = arrayfire::add(
+            &arrayfire::mul(&products, &scales, true),
+            &self.biases,
+            true,
+        );
+        Constant::new(result).into()
+    }
+
+    /// Returns the weights and biases of the layer dequantized, i.e. to check the error
+    /// introduced by the quantization
+    #[must_use]
+    #[inline]
+    pub fn dequantize(&self) -> Array<Float> {
+        let weights = arrayfire::mul(&integers(&self.weights), &self.scales, true);
+        arrayfire::join(0, &weights, &self.biases)
+    }
+}
+
+/// A `Conv2D` layer with 8 bit integer weights, see `Conv2D::quantize`
+pub struct QuantizedConv2D<const I: u64, const O: u64, const H: u64, const W: u64> {
+    kernel: Array<u8>,
+    scales: Array<Float>,
+    input_scale: Float,
+}
+
+impl<const I: u64, const O: u64, const H: u64, const W: u64> QuantizedConv2D<I, O, H, W> {
+    /// Quantizes the kernel of a `Conv2D` layer to take inputs quantized with the given scale
+    pub(crate) fn new(kernel: &Array<Float>, input_scale: Float) -> Self {
+        // Every output channel is laid out along the last dimension of the kernel
+        let flat = arrayfire::moddims(kernel, dim4!(H * W * I, O));
+        let (quantized, scales) = quantize_weights(&flat);
+        Self {
+            kernel: arrayfire::moddims(&quantized, kernel.dims()),
+            scales: arrayfire::moddims(&scales, dim4!(1, 1, O)),
+            input_scale,
+        }
+    }
+
+    /// Given an input computes the output, with the same shape as the original layer
+    #[must_use]
+    #[inline]
+    pub fn forward<const B: u64, const XH: u64, const XW: u64, X: Data>(
+        &self,
+        x: &Tensor<B, I, XH, XW, X>,
+    ) -> Tensor<B, O, { XH - H + 1 }, { XW - W + 1 }, Constant> {
+        let _op = profiler::forward("quantized_conv2d");
+        let products = arrayfire::convolve2_nn(
+            &quantize_inputs(&x.data(), self.input_scale),
+            &integers(&self.kernel),
+            dim4!(1, 1),
+            dim4!(0, 0),
+            dim4!(1, 1),
+        );
+        let scales = self.input_scale * &self.scales;
+        Constant::new(arrayfire::mul(&products, &scales, true)).into()
+    }
+
+    /// Returns the kernel of the layer dequantized, i.e. to check the error introduced by the
+    /// quantization
+    #[must_use]
+    #[inline]
+    pub fn dequantize(&self) -> Array<Float> {
+        let flat = arrayfire::moddims(&integers(&self.kernel), dim4!(H * W * I, O));
+        let scales = arrayfire::moddims(&self.scales, dim4!(1, O));
+        arrayfire::moddims(&arrayfire::mul(&flat, &scales, true), self.kernel.dims())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Calibration;
+    use crate as mu;
+    use crate::nn::layers::{Conv2D, Linear};
+    use crate::tensor::{traits::Tensed, Float};
+
+    /// Returns the largest absolute difference between the values of two arrays
+    fn max_error(x: &arrayfire::Array<Float>, y: &arrayfire::Array<Float>) -> Float {
+        arrayfire::max_all(&arrayfire::abs(&arrayfire::sub(x, y, false))).0
+    }
+
+    #[test]
+    fn calibration_scale() {
+        let mut calibration = Calibration::new();
+        assert!((calibration.scale() - 1.0).abs() < Float::EPSILON);
+
+        calibration.observe(&mu::custom::<1, 1, 1, 3>(&[-2.54, 1.0, 0.5]));
+        calibration.observe(&mu::custom::<1, 1, 1, 2>(&[1.27, 0.0]));
+        assert!((calibration.scale() - 0.02).abs() < 1e-6);
+    }
+
+    #[test]
+    fn quantized_linear() {
+        let linear = Linear::<8, 4>::randn();
+        let x = mu::randn::<16, 1, 1, 8>().freeze();
+        let mut calibration = Calibration::new();
+        calibration.observe(&x);
+
+        let quantized = linear.quantize(&calibration);
+        let (expected, z) = (linear.forward(&x).data(), quantized.forward(&x).data());
+        let range = arrayfire::max_all(&arrayfire::abs(&expected)).0;
+        assert!(max_error(&expected, &z) < 0.05 * range);
+        assert!(max_error(&linear.parameters().data(), &quantized.dequantize()) < 0.05);
+    }
+
+    #[test]
+    fn quantized_conv2d() {
+        let conv = Conv2D::<2, 3, 3, 3>::randn();
+        let x = mu::randn::<4, 2, 8, 8>().freeze();
+        let mut calibration = Calibration::new();
+        calibration.observe(&x);
+
+        let quantized = conv.quantize(&calibration);
+        let (expected, z) = (conv.forward(&x).data(), quantized.forward(&x).data());
+        let range = arrayfire::max_all(&arrayfire::abs(&expected)).0;
+        assert!(max_error(&expected, &z) < 0.05 * range);
+        assert!(max_error(&conv.parameters().data(), &quantized.dequantize()) < 0.05);
+    }
+}
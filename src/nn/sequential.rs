@@ -0,0 +1,243 @@
+//! The `sequential!` macro, to define models that chain layers one after another.
+
+use crate::{
+    graph::{node::Node, shared::Shared},
+    tensor::{
+        traits::{Data, Pair},
+        variable::Variable,
+        Tensor,
+    },
+};
+
+// The items below name the types the `sequential!` macro expands to from other crates
+
+#[doc(hidden)]
+pub type Input<const B: u64, const C: u64, const H: u64, const W: u64, X> = Tensor<B, C, H, W, X>;
+
+#[doc(hidden)]
+pub type Output<const B: u64, const C: u64, const H: u64, const W: u64> =
+    Tensor<B, C, H, W, Variable>;
+
+#[doc(hidden)]
+pub type Parameter = Shared<Node>;
+
+/// Data of the inputs a sequential model takes, which results in variable outputs
+#[doc(hidden)]
+pub trait InputData: Data + Pair<Variable, Output = Variable> {}
+
+impl<X: Data + Pair<Variable, Output = Variable>> InputData for X {}
+
+/// Defines a model that feeds its input through a list of layers, in order.
+///
+/// Every layer is given as a field with its type and the expression initializing it, which
+/// can be followed by functions applied to its output, such as activations. The model takes
+/// and returns tensors of the declared `[C, H, W]` shapes for any batch size, and the shapes
+/// between the layers are checked at compile time: the model does not compile unless the
+/// output of every layer fits the input of the next one.
+///
+/// The generated struct has a `new` constructor, a `forward` method chaining the layers and
+/// a `parameters` method returning the trainable parameters of all of them, and implements
+/// `Module` naming each parameter and buffer after the field of its layer, i.e. `hidden.weights`.
+///
+/// ## Usage
+/// ```rust
+/// #![feature(generic_const_exprs)]
+///
+/// use mushin as mu;
+/// use mu::nn::{activations::relu, layers::{Conv2D, Linear}, ops::flatten};
+///
+/// mu::sequential! {
+///     /// Scores 10 classes of single channel 8x8 images
+///     pub struct Classifier: [1, 8, 8] -> [1, 1, 10] {
+///         conv: Conv2D<1, 4, 3, 3> = Conv2D::randn() => relu => flatten,
+///         hidden: Linear<144, 32> = Linear::randn() => relu,
+///         output: Linear<32, 10> = Linear::randn(),
+///     }
+/// }
+///
+/// let model = Classifier::new();
+/// let z = model.forward(&mu::randn::<4, 1, 8, 8>().freeze());
+/// assert_eq!(model.parameters().len(), 3);
+/// ```
+#[macro_export]
+macro_rules! sequential {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident: [$c:expr, $h:expr, $w:expr] -> [$oc:expr, $oh:expr, $ow:expr] {
+            $($field:ident: $layer:ty = $init:expr $(=> $f:expr)*),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($field: $layer),+
+        }
+
+        impl $name {
+            /// Returns a new model with its layers initialized
+            #[must_use]
+            #[inline]
+            pub fn new() -> Self {
+                Self {
+                    $($field: $init),+
+                }
+            }
+
+            /// Given an input computes the output
+            #[inline]
+            pub fn forward<const B: u64, X: $crate::nn::sequential::InputData>(
+                &self,
+                x: &$crate::nn::sequential::Input<B, { $c }, { $h }, { $w }, X>,
+            ) -> $crate::nn::sequential::Output<B, { $oc }, { $oh }, { $ow }> {
+                $(
+                    let x = &self.$field.forward(x);
+                    $(let x = &$f(x);)*
+                )+
+                x.clone()
+            }
+
+            /// Returns the model's trainable parameters, without the buffers of its layers
+            #[must_use]
+            #[inline]
+            pub fn parameters(
+                &self,
+            ) -> Vec<$crate::nn::sequential::Parameter> {
+                $crate::nn::Module::named_parameters(self)
+                    .into_iter()
+                    .map(|(_, node)| node)
+                    .collect()
+            }
+        }
+
+        impl Default for $name {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl $crate::nn::Module for $name {
+            #[inline]
+            fn named_parameters(
+                &self,
+            ) -> Vec<(String, $crate::nn::sequential::Parameter)> {
+                let mut params = Vec::new();
+                $(
+                    params.extend(
+                        $crate::nn::Module::named_parameters(&self.$field)
+                            .into_iter()
+                            .map(|(param, node)| {
+                                (format!("{}.{param}", stringify!($field)), node)
+                            }),
+                    );
+                )+
+                params
+            }
+
+            #[inline]
+            fn named_buffers(
+                &self,
+            ) -> Vec<(String, $crate::nn::sequential::Parameter)> {
+                let mut buffers = Vec::new();
+                $(
+                    buffers.extend(
+                        $crate::nn::Module::named_buffers(&self.$field)
+                            .into_iter()
+                            .map(|(buffer, node)| {
+                                (format!("{}.{buffer}", stringify!($field)), node)
+                            }),
+                    );
+                )+
+                buffers
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as mu;
+    use crate::nn::{
+        activations::relu,
+        layers::{BatchNorm, Conv2D, Linear},
+        ops::flatten,
+        optimizers::{Optimizer, SGD},
+        Module,
+    };
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+
+    crate::sequential! {
+        /// A small classifier of single channel images
+        struct Classifier: [1, 4, 4] -> [1, 1, 3] {
+            conv: Conv2D<1, 2, 3, 3> = Conv2D::randn() => relu => flatten,
+            output: Linear<8, 3> = Linear::randn(),
+        }
+    }
+
+    crate::sequential! {
+        /// A small classifier of single channel images, normalizing its features
+        struct Normalized: [1, 4, 4] -> [1, 1, 3] {
+            conv: Conv2D<1, 2, 3, 3> = Conv2D::randn(),
+            bn: BatchNorm<2> = BatchNorm::new(0.1) => relu => flatten,
+            output: Linear<8, 3> = Linear::randn(),
+        }
+    }
+
+    #[test]
+    fn sequential_buffers() {
+        let model = Normalized::new();
+        let names: Vec<String> = model
+            .named_parameters()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            ["conv.kernels", "bn.gamma", "bn.beta", "output.weights"]
+        );
+        let names: Vec<String> = model
+            .named_buffers()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["bn.running_mean", "bn.running_var"]);
+
+        model
+            .forward(&mu::randn::<2, 1, 4, 4>().freeze())
+            .backward();
+        let state = model.state_dict();
+
+        // Weight decay would shrink the running statistics if they reached the optimizer
+        let optim = SGD::new(&model.parameters(), 0.1).weight_decay(0.5);
+        assert_eq!(optim.parameters().len(), 4);
+        optim.step();
+        for (name, node) in model.named_buffers() {
+            assert!(equal_data(node.data().clone(), state[&name].clone()));
+        }
+    }
+
+    #[test]
+    fn sequential_layers() {
+        let model = Classifier::new();
+        let names: Vec<String> = model
+            .named_parameters()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["conv.kernels", "output.weights"]);
+        assert_eq!(model.parameters().len(), 2);
+
+        let x = mu::randn::<2, 1, 4, 4>().freeze();
+        let expected = model
+            .output
+            .forward(&flatten(&relu(&model.conv.forward(&x))));
+        let z = model.forward(&x);
+        assert!(equal_data(z.data(), expected.data()));
+
+        z.backward();
+        assert!(model
+            .parameters()
+            .iter()
+            .all(|node| { arrayfire::sum_all(&arrayfire::abs(&node.grad())).0 > 0.0 }));
+    }
+}
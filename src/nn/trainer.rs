@@ -0,0 +1,210 @@
+use crate::{
+    data::{DataLoader, Dataset},
+    nn::{
+        callbacks::{Callback, Metrics},
+        optimizers::Optimizer,
+    },
+    ops::mul,
+    tensor::{constant::Constant, traits::Tensed, variable::Variable, Float, Tensor},
+};
+
+/// Runs the training loop of a model: for every batch computes the loss, back-propagates it
+/// and updates the parameters, tracking the mean losses of every epoch and invoking the callbacks
+pub struct Trainer<'t, O: Optimizer> {
+    optim: &'t mut O,
+    epochs: usize,
+    accumulation: usize,
+    callbacks: Vec<&'t mut dyn Callback>,
+}
+
+impl<'t, O: Optimizer> Trainer<'t, O> {
+    /// Returns a new `Trainer` running the given number of epochs with the given optimizer
+    #[must_use]
+    #[inline]
+    pub fn new(optim: &'t mut O, epochs: usize) -> Self {
+        Self {
+            optim,
+            epochs,
+            accumulation: 1,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Consumes this trainer and returns a copy accumulating the gradients of the given number
+    /// of batches before every optimization step. Each batch loss is scaled by the number of
+    /// accumulated batches, so the step matches that of a single batch as large as all of them.
+    /// The last group of an epoch may hold fewer batches, and is scaled by its own size
+    #[must_use]
+    #[inline]
+    pub fn accumulate(mut self, batches: usize) -> Self {
+        self.accumulation = batches.max(1);
+        self
+    }
+
+    /// Consumes this trainer and returns a copy also invoking the given callback
+    #[must_use]
+    #[inline]
+    pub fn callback(mut self, callback: &'t mut dyn Callback) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    /// Fits the model given by `forward` to the training data, minimizing `loss`.
+    /// If validation data is given, its mean loss is evaluated at the end of every epoch.
+    /// Returns the metrics of every epoch run, which can be less than requested if a callback
+    /// halted the training
+    #[inline]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fit<D, F, L, const B: u64, const C: u64, const H: u64, const W: u64, const T: u64>(
+        &mut self,
+        train: &DataLoader<'_, D, B, C, H, W, T>,
+        valid: Option<&DataLoader<'_, D, B, C, H, W, T>>,
+        forward: F,
+        loss: L,
+    ) -> Vec<Metrics>
+    where
+        D: Dataset<C, H, W, T>,
+        F: Fn(&Tensor<B, C, H, W, Constant>) -> Tensor<B, 1, 1, T, Variable>,
+        L: Fn(
+            &Tensor<B, 1, 1, T, Variable>,
+            &Tensor<B, 1, 1, T, Constant>,
+        ) -> Tensor<1, 1, 1, 1, Variable>,
+    {
+        let mut history = Vec::with_capacity(self.epochs);
+
+        for callback in &mut self.callbacks {
+            callback.on_train_begin(self.epochs);
+        }
+
+        for epoch in 0..self.epochs {
+            let mut train_loss = 0.0;
+            for (batch, (x, y)) in train.iter().enumerate() {
+                let l = loss(&forward(&x), &y);
+                let first = batch - batch % self.accumulation;
+                let group = self.accumulation.min(train.len() - first);
+                mul(
+                    &l,
+                    &crate::fill::<1, 1, 1, 1>(1.0 / group as Float).freeze(),
+                )
+                .backward();
+                for callback in &mut self.callbacks {
+                    callback.on_backward_end();
+                }
+                if (batch + 1) % self.accumulation == 0 || batch + 1 == train.len() {
+                    self.optim.step();
+                    self.optim.zero_grad();
+                }
+
+                let value = arrayfire::sum_all(&l.data()).0;
+                train_loss += value;
+                for callback in &mut self.callbacks {
+                    callback.on_batch_end(batch, value);
+                }
+            }
+
+            let valid_loss = valid.map(|loader| {
+                let total: Float = loader
+                    .iter()
+                    .map(|(x, y)| arrayfire::sum_all(&loss(&forward(&x), &y).data()).0)
+                    .sum();
+                total / loader.len().max(1) as Float
+            });
+
+            let metrics = Metrics {
+                epoch,
+                train_loss: train_loss / train.len().max(1) as Float,
+                valid_loss,
+            };
+            history.push(metrics);
+
+            for callback in &mut self.callbacks {
+                callback.on_epoch_end(&metrics, self.optim);
+            }
+            if self.callbacks.iter().any(|c| c.should_stop()) {
+                break;
+            }
+        }
+
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trainer;
+    use crate::data::{DataLoader, Dataset};
+    use crate::nn::{
+        layers::Linear,
+        losses::{mse, Mean},
+        optimizers::{schedulers::ExponentialLR, Optimizer, SGD},
+    };
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+
+    struct Identity;
+
+    impl Dataset<1, 1, 2, 2> for Identity {
+        fn len(&self) -> usize {
+            4
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+            let x = vec![index as Float, 1.0];
+            (x.clone(), x)
+        }
+    }
+
+    #[test]
+    fn trainer_fit() {
+        let linear = Linear::<2, 2>::randn();
+        let mut optim = SGD::new(&[linear.parameters()], 0.01);
+        let mut scheduler = ExponentialLR::new(0.5);
+        let loader = DataLoader::<_, 2, 1, 1, 2, 2>::new(&Identity, true, false);
+
+        let history = Trainer::new(&mut optim, 2)
+            .accumulate(2)
+            .callback(&mut scheduler)
+            .fit(
+                &loader,
+                Some(&loader),
+                |x| linear.forward(x),
+                |z, y| mse(z, y, Mean),
+            );
+
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|m| m.valid_loss.is_some()));
+        assert!((optim.lr() - 0.0025).abs() < Float::EPSILON);
+
+        // Every epoch makes a single step over the whole dataset, so the losses must decrease
+        assert!(history[1].train_loss < history[0].train_loss);
+        assert!(history[1].valid_loss < history[0].valid_loss);
+        assert_eq!(
+            linear.parameters().data().dims(),
+            arrayfire::dim4!(3, 2, 1, 1)
+        );
+    }
+
+    #[test]
+    fn trainer_partial_accumulation() {
+        let linear = Linear::<2, 2>::randn();
+        let initial = linear.parameters().data().clone();
+        let loader = DataLoader::<_, 2, 1, 1, 2, 2>::new(&Identity, false, false);
+        let fit = |accumulation| {
+            linear.parameters().set_data(initial.clone());
+            let mut optim = SGD::new(&[linear.parameters()], 0.1);
+            Trainer::new(&mut optim, 1).accumulate(accumulation).fit(
+                &loader,
+                None,
+                |x| linear.forward(x),
+                |z, y| mse(z, y, Mean),
+            );
+            let trained = linear.parameters().data().clone();
+            assert_eq!(trained.dims(), arrayfire::dim4!(3, 2, 1, 1));
+            trained
+        };
+
+        // Both epochs make a single step over the two batches, whether or not the group is full
+        assert!(equal_data(fit(2), fit(3)));
+    }
+}
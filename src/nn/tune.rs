@@ -0,0 +1,180 @@
+//! Tuning of the training hyperparameters.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::data::{DataLoader, Dataset};
+//! use mu::nn::{layers::Linear, losses::{mse, Mean}, optimizers::SGD, tune::{lr_finder, steepest}};
+//!
+//! struct Identity;
+//!
+//! impl Dataset<1, 1, 2, 2> for Identity {
+//!     fn len(&self) -> usize {
+//!         8
+//!     }
+//!
+//!     fn get(&self, index: usize) -> (Vec<mu::Float>, Vec<mu::Float>) {
+//!         (vec![index as mu::Float, 1.0], vec![index as mu::Float, 1.0])
+//!     }
+//! }
+//!
+//! let linear = Linear::<2, 2>::randn();
+//! let mut optim = SGD::new(&[linear.parameters()], 0.01);
+//! let loader = DataLoader::<_, 4, 1, 1, 2, 2>::new(&Identity, true, false);
+//!
+//! let curve = lr_finder(&mut optim, &loader, (1e-6, 1.0), 50, |x| linear.forward(x), |z, y| {
+//!     mse(z, y, Mean)
+//! });
+//! let lr = steepest(&curve);
+//! ```
+
+use crate::{
+    data::{DataLoader, Dataset},
+    nn::optimizers::Optimizer,
+    tensor::{constant::Constant, variable::Variable, Float, Tensor},
+};
+
+/// Weight of the previous losses in the smoothed loss of the curve
+const SMOOTHING: Float = 0.98;
+
+/// Runs a learning rate range test, training with a learning rate growing exponentially
+/// from the first to the last of the range.
+///
+/// The model is trained for the given number of steps on the batches of the loader, cycling
+/// through them. Returns the curve of the smoothed loss at every learning rate, which stops
+/// once the loss diverges to four times its minimum, or is empty if the loader is.
+///
+/// The parameters and learning rate of the optimizer are restored afterwards, but not its
+/// state, i.e. momentum, so training is best started with a new optimizer. See `steepest` to
+/// pick a learning rate from the curve
+#[inline]
+#[allow(clippy::cast_precision_loss)]
+pub fn lr_finder<O, D, F, L, const B: u64, const C: u64, const H: u64, const W: u64, const T: u64>(
+    optim: &mut O,
+    loader: &DataLoader<'_, D, B, C, H, W, T>,
+    range: (Float, Float),
+    steps: usize,
+    forward: F,
+    loss: L,
+) -> Vec<(Float, Float)>
+where
+    O: Optimizer,
+    D: Dataset<C, H, W, T>,
+    F: Fn(&Tensor<B, C, H, W, Constant>) -> Tensor<B, 1, 1, T, Variable>,
+    L: Fn(
+        &Tensor<B, 1, 1, T, Variable>,
+        &Tensor<B, 1, 1, T, Constant>,
+    ) -> Tensor<1, 1, 1, 1, Variable>,
+{
+    let (start, end) = range;
+    let initial_lr = optim.lr();
+    let initial: Vec<_> = optim
+        .parameters()
+        .iter()
+        .map(|node| node.data().clone())
+        .collect();
+
+    let growth = (end / start).powf(1.0 / steps.saturating_sub(1).max(1) as Float);
+    let mut curve = Vec::with_capacity(steps);
+    let (mut average, mut best) = (0.0, Float::INFINITY);
+    // An empty loader would be cycled forever
+    let cycles = if loader.is_empty() { 0 } else { steps };
+    let batches = std::iter::repeat_with(|| loader.iter())
+        .take(cycles)
+        .flatten();
+
+    for (step, (x, y)) in batches.take(steps).enumerate() {
+        let lr = start * growth.powf(step as Float);
+        optim.set_lr(lr);
+        let l = loss(&forward(&x), &y);
+        l.backward();
+        optim.step();
+        optim.zero_grad();
+
+        // The bias of the exponential average towards zero is corrected
+        average = SMOOTHING.mul_add(average, (1.0 - SMOOTHING) * l.to_scalar());
+        let smoothed = average / (1.0 - SMOOTHING.powf(step as Float + 1.0));
+        curve.push((lr, smoothed));
+        best = best.min(smoothed);
+        if !smoothed.is_finite() || smoothed > 4.0 * best {
+            break;
+        }
+    }
+
+    for (node, data) in optim.parameters().iter().zip(initial) {
+        node.set_data(data);
+    }
+    optim.set_lr(initial_lr);
+    curve
+}
+
+/// Returns the learning rate of the curve of `lr_finder` at which the loss decreases the
+/// fastest, or `None` if the curve has less than two points
+#[must_use]
+#[inline]
+pub fn steepest(curve: &[(Float, Float)]) -> Option<Float> {
+    curve
+        .windows(2)
+        .map(|pair| {
+            let ((lr, a), (next_lr, b)) = (pair[0], pair[1]);
+            (lr, (b - a) / (next_lr.ln() - lr.ln()))
+        })
+        .min_by(|&(_, a), &(_, b)| a.total_cmp(&b))
+        .map(|(lr, _)| lr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lr_finder, steepest};
+    use crate::data::{DataLoader, Dataset};
+    use crate::nn::{
+        layers::Linear,
+        losses::{mse, Mean},
+        optimizers::{Optimizer, SGD},
+    };
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+
+    struct Identity;
+
+    impl Dataset<1, 1, 2, 2> for Identity {
+        fn len(&self) -> usize {
+            4
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        fn get(&self, index: usize) -> (Vec<Float>, Vec<Float>) {
+            let x = vec![index as Float, 1.0];
+            (x.clone(), x)
+        }
+    }
+
+    #[test]
+    fn lr_range_test() {
+        let linear = Linear::<2, 2>::randn();
+        let weights = linear.parameters().data().clone();
+        let mut optim = SGD::new(&[linear.parameters()], 0.01);
+        let loader = DataLoader::<_, 2, 1, 1, 2, 2>::new(&Identity, true, false);
+
+        let curve = lr_finder(
+            &mut optim,
+            &loader,
+            (1e-4, 10.0),
+            20,
+            |x| linear.forward(x),
+            |z, y| mse(z, y, Mean),
+        );
+
+        // The learning rate grows exponentially, until the loss diverges
+        assert!(!curve.is_empty() && curve.len() <= 20);
+        assert!((curve[0].0 - 1e-4).abs() < 1e-9);
+        assert!(curve.windows(2).all(|pair| pair[1].0 > pair[0].0));
+        assert!(steepest(&curve).is_some() || curve.len() < 2);
+
+        // The optimizer is left as it was
+        assert!((optim.lr() - 0.01).abs() < Float::EPSILON);
+        assert!(equal_data(linear.parameters().data().clone(), weights));
+    }
+}
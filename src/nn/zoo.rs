@@ -0,0 +1,306 @@
+//! Small models with pretrained weights, to try out inference or fine-tuning without
+//! training from scratch.
+//!
+//! The weights are safetensors files as written by `nn::io::save`, downloaded once into the
+//! directory given by the `MUSHIN_CACHE` environment variable, or `~/.cache/mushin` otherwise.
+//!
+//! ## Usage
+//! ```no_run
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//! use mu::nn::zoo::MnistCnn;
+//!
+//! let model = MnistCnn::pretrained("https://example.com/weights/mnist_cnn.safetensors")?;
+//! let scores = model.forward(&mu::randu::<1, 1, 28, 28>().freeze());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::{
+    graph::{node::Node, shared::Shared},
+    nn::{
+        activations::relu,
+        io,
+        layers::{Conv2D, Linear, ResNetBlock},
+        module::{scoped, scoped_buffers, Module},
+        ops::{flatten, maxpool2d},
+    },
+    tensor::{
+        traits::{Data, Pair},
+        variable::Variable,
+        Tensor,
+    },
+};
+use std::{
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+/// Returns the directory the downloaded weights are cached in
+#[must_use]
+#[inline]
+pub fn cache_dir() -> PathBuf {
+    env::var_os("MUSHIN_CACHE").map_or_else(
+        || {
+            env::var_os("HOME")
+                .map_or_else(env::temp_dir, PathBuf::from)
+                .join(".cache")
+                .join("mushin")
+        },
+        PathBuf::from,
+    )
+}
+
+/// Returns the path of the file at the given URL in the cache directory, downloading it
+/// first unless it was already
+///
+/// # Errors
+///
+/// Returns an error if the URL does not name a file, or the file can not be downloaded or
+/// written to the cache directory
+#[inline]
+pub fn fetch(url: &str) -> Result<PathBuf> {
+    cached(url, &cache_dir())
+}
+
+/// Same as `fetch`, with the given cache directory
+fn cached(url: &str, dir: &Path) -> Result<PathBuf> {
+    let name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "the URL does not name a file"))?;
+    let path = dir.join(name);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let bytes = attohttpc::get(url).send()?.error_for_status()?.bytes()?;
+    fs::create_dir_all(dir)?;
+    // Downloads are written aside and renamed, so that interrupted ones are not cached
+    let partial = dir.join(format!("{name}.part"));
+    fs::write(&partial, bytes)?;
+    fs::rename(partial, &path)?;
+    Ok(path)
+}
+
+/// A convolutional network for the MNIST digits, taking single channel 28x28 images and
+/// returning the scores for 10 classes
+pub struct MnistCnn {
+    conv1: Conv2D<1, 8, 3, 3>,
+    conv2: Conv2D<8, 16, 3, 3>,
+    output: Linear<784, 10>,
+}
+
+impl MnistCnn {
+    /// Returns a new `MnistCnn` with all its parameters taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            conv1: Conv2D::randn(),
+            conv2: Conv2D::randn(),
+            output: Linear::randn(),
+        }
+    }
+
+    /// Returns a new `MnistCnn` with the pretrained weights at the given URL, see `fetch`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the weights can not be fetched or do not fit the model
+    #[inline]
+    pub fn pretrained(url: &str) -> Result<Self> {
+        let model = Self::randn();
+        io::load(fetch(url)?, &model.named_state())?;
+        Ok(model)
+    }
+
+    /// Given an input computes the output
+    #[inline]
+    pub fn forward<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, 1, 28, 28, D>,
+    ) -> Tensor<B, 1, 1, 10, Variable> {
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv1.forward_same(x)));
+        let x = maxpool2d::<2, 2, 2, _>(&relu(&self.conv2.forward_same(&x)));
+        self.output.forward(&flatten(&x))
+    }
+
+    /// Returns the model's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        vec![
+            self.conv1.parameters(),
+            self.conv2.parameters(),
+            self.output.parameters(),
+        ]
+    }
+}
+
+impl Module for MnistCnn {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped("conv1", &self.conv1),
+            scoped("conv2", &self.conv2),
+            scoped("output", &self.output),
+        ]
+        .concat()
+    }
+}
+
+/// A residual network of 8 layers for the CIFAR-10 images, taking three channel 32x32 images
+/// and returning the scores for 10 classes.
+///
+/// A convolution is followed by three residual blocks, each halving the size of the images
+/// with a max pooling, and a linear layer
+pub struct CifarResNet8 {
+    stem: Conv2D<3, 16, 3, 3>,
+    block1: ResNetBlock<16, 16>,
+    block2: ResNetBlock<16, 16>,
+    block3: ResNetBlock<16, 16>,
+    output: Linear<256, 10>,
+}
+
+impl CifarResNet8 {
+    /// Returns a new `CifarResNet8` with all its parameters taken from a normal distribution
+    /// with mean 0 and standard deviation 1
+    #[must_use]
+    #[inline]
+    pub fn randn() -> Self {
+        Self {
+            stem: Conv2D::randn(),
+            block1: ResNetBlock::randn(),
+            block2: ResNetBlock::randn(),
+            block3: ResNetBlock::randn(),
+            output: Linear::randn(),
+        }
+    }
+
+    /// Returns a new `CifarResNet8` with the pretrained weights at the given URL, see `fetch`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the weights can not be fetched or do not fit the model
+    #[inline]
+    pub fn pretrained(url: &str) -> Result<Self> {
+        let model = Self::randn();
+        io::load(fetch(url)?, &model.named_state())?;
+        Ok(model)
+    }
+
+    /// Given an input computes the output, normalizing with the statistics of the batch
+    #[inline]
+    pub fn forward<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, 3, 32, 32, D>,
+    ) -> Tensor<B, 1, 1, 10, Variable> {
+        let x = relu(&self.stem.forward_same(x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block1.forward(&x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block2.forward(&x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block3.forward(&x));
+        self.output.forward(&flatten(&x))
+    }
+
+    /// Given an input computes the output as `forward` does, normalizing with the running
+    /// statistics of the residual blocks, i.e. to evaluate the pretrained weights
+    #[inline]
+    pub fn infer<const B: u64, D: Data + Pair<Variable, Output = Variable>>(
+        &self,
+        x: &Tensor<B, 3, 32, 32, D>,
+    ) -> Tensor<B, 1, 1, 10, Variable> {
+        let x = relu(&self.stem.forward_same(x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block1.infer(&x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block2.infer(&x));
+        let x = maxpool2d::<2, 2, 2, _>(&self.block3.infer(&x));
+        self.output.forward(&flatten(&x))
+    }
+
+    /// Returns the model's trainable parameters
+    #[must_use]
+    #[inline]
+    pub fn parameters(&self) -> Vec<Shared<Node>> {
+        [
+            vec![self.stem.parameters()],
+            self.block1.parameters(),
+            self.block2.parameters(),
+            self.block3.parameters(),
+            vec![self.output.parameters()],
+        ]
+        .concat()
+    }
+}
+
+impl Module for CifarResNet8 {
+    #[inline]
+    fn named_parameters(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped("stem", &self.stem),
+            scoped("block1", &self.block1),
+            scoped("block2", &self.block2),
+            scoped("block3", &self.block3),
+            scoped("output", &self.output),
+        ]
+        .concat()
+    }
+
+    #[inline]
+    fn named_buffers(&self) -> Vec<(String, Shared<Node>)> {
+        [
+            scoped_buffers("block1", &self.block1),
+            scoped_buffers("block2", &self.block2),
+            scoped_buffers("block3", &self.block3),
+        ]
+        .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cached, CifarResNet8, MnistCnn};
+    use crate as mu;
+    use crate::nn::{io, Module};
+    use crate::tensor::traits::Tensed;
+    use crate::tests::equal_data;
+    use std::fs;
+
+    #[test]
+    fn zoo_models() {
+        let mnist = MnistCnn::randn();
+        let z = mnist.forward(&mu::randu::<2, 1, 28, 28>().freeze());
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 10, 1, 2));
+        assert_eq!(mnist.named_parameters().len(), 3);
+
+        let cifar = CifarResNet8::randn();
+        let z = cifar.forward(&mu::randu::<2, 3, 32, 32>().freeze());
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 10, 1, 2));
+        let z = cifar.infer(&mu::randu::<2, 3, 32, 32>().freeze());
+        assert_eq!(z.data().dims(), arrayfire::dim4!(1, 10, 1, 2));
+        assert_eq!(cifar.parameters().len(), 20);
+        assert_eq!(cifar.named_parameters().len(), 20);
+        assert_eq!(cifar.named_state().len(), 32);
+    }
+
+    #[test]
+    fn cached_weights() {
+        let dir = std::env::temp_dir().join("mushin-zoo");
+        fs::create_dir_all(&dir).unwrap();
+        let trained = MnistCnn::randn();
+        io::save(dir.join("mnist.safetensors"), &trained.named_state()).unwrap();
+
+        // Files already in the cache are not downloaded again
+        let path = cached("https://localhost/weights/mnist.safetensors", &dir).unwrap();
+        let model = MnistCnn::randn();
+        io::load(path, &model.named_state()).unwrap();
+        for (x, y) in trained.parameters().iter().zip(model.parameters()) {
+            assert!(equal_data(x.data().clone(), y.data().clone()));
+        }
+
+        assert!(cached("https://localhost/weights/", &dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,139 +1,444 @@
-use crate::tensor::{
-    traits::{Data, Pair, Tensed},
-    Tensor,
+use crate::{
+    graph::node::Tangent,
+    profiler,
+    tensor::{
+        traits::{Data, Pair, Tensed},
+        Float, Tensor,
+    },
 };
-use arrayfire::Array;
+use arrayfire::{Array, Dim4, Indexer, Seq};
 
-/// Changes the shape of the tensor to the given dimensions
+/// Changes the shape of the tensor to the given dimensions, without copying its values
 #[inline]
 pub fn reshape<const B: u64, const C: u64, const H: u64, const W: u64, X: Tensed>(
     x: &X,
 ) -> Tensor<B, C, H, W, X::Data> {
+    let _op = profiler::forward("reshape");
     x.push_unary(
         arrayfire::moddims(&x.data(), arrayfire::dim4!(H, W, C, B)),
-        |df: &Array<f32>, _: &[Array<f32>]| {
+        |df: &Array<Float>, _: &[Array<Float>]| {
             arrayfire::moddims(
                 df,
                 arrayfire::dim4!(X::HEIGHT, X::WIDTH, X::CHANNELS, X::BATCH),
             )
         },
-        &[],
+        vec![],
     )
+    .with_tangent(Tangent::Unary(|dx, _| {
+        arrayfire::moddims(dx, arrayfire::dim4!(H, W, C, B))
+    }))
 }
 
-/// Sine operation
+/// Returns the sequences indexing a slice of the given shape, with the given offsets and
+/// strides along every dimension
+#[allow(clippy::cast_possible_truncation)]
+fn slice_seqs(offsets: [u64; 4], strides: [u64; 4], dims: Dim4) -> [Seq<i32>; 4] {
+    std::array::from_fn(|i| {
+        let (offset, stride) = (offsets[i] as i32, strides[i] as i32);
+        Seq::new(offset, offset + (dims[i] as i32 - 1) * stride, stride)
+    })
+}
+
+/// Indexes a slice with the indices of its values along every dimension, as kept by its
+/// operation in the device
+#[allow(clippy::cast_possible_truncation)]
+fn slice_indexer(indices: &[Array<u32>]) -> Indexer<'_> {
+    let mut indexer = Indexer::default();
+    for (dim, index) in indices.iter().enumerate() {
+        indexer.set_index(index, dim as u32, None);
+    }
+    indexer
+}
+
+/// Returns a slice of the tensor of the given shape, with the values taken every `strides`
+/// values along each dimension starting at `offsets`, both in `[B, C, H, W]` order.
+///
+/// The values of the slice are copied. The indices they are taken from are kept in the device,
+/// so that computing the derivatives of the slice does not wait for it
+///
+/// # Panics
+///
+/// Panics if a stride is zero or the slice exceeds the shape of the tensor
 #[inline]
-pub fn sin<X: Tensed>(
+#[allow(clippy::cast_precision_loss)]
+pub fn slice<const B: u64, const C: u64, const H: u64, const W: u64, X: Tensed>(
     x: &X,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, X::Data> {
+    offsets: [u64; 4],
+    strides: [u64; 4],
+) -> Tensor<B, C, H, W, X::Data> {
+    let _op = profiler::forward("slice");
+    let shape = [X::BATCH, X::CHANNELS, X::HEIGHT, X::WIDTH];
+    for (((size, n), offset), stride) in shape
+        .into_iter()
+        .zip([B, C, H, W])
+        .zip(offsets)
+        .zip(strides)
+    {
+        assert!(
+            stride > 0 && offset + (n - 1) * stride < size,
+            "slice of shape {:?} with offsets {offsets:?} and strides {strides:?} exceeds shape {shape:?}",
+            [B, C, H, W]
+        );
+    }
+
+    // Arrayfire lays out the dimensions as [H, W, C, B]
+    let af = |v: [u64; 4]| [v[2], v[3], v[1], v[0]];
+    let (offsets, strides) = (af(offsets), af(strides));
+    let dims = arrayfire::dim4!(H, W, C, B);
+    let values = arrayfire::index(&x.data(), &slice_seqs(offsets, strides, dims));
+    let indices = (0..4)
+        .map(|i| {
+            let index: Vec<Float> = (0..dims[i])
+                .map(|n| (offsets[i] + n * strides[i]) as Float)
+                .collect();
+            Array::new(&index, arrayfire::dim4!(dims[i]))
+        })
+        .collect();
+
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+        let input = arrayfire::dim4!(X::HEIGHT, X::WIDTH, X::CHANNELS, X::BATCH);
+        let positions: Vec<_> = args.iter().map(Array::cast::<u32>).collect();
+        let mut grad = arrayfire::constant(0.0 as Float, input);
+        arrayfire::assign_gen(&mut grad, &slice_indexer(&positions), df);
+        grad
+    };
+    x.push_unary(values, reverse, indices)
+        .with_tangent(Tangent::Unary(|dx, args| {
+            let positions: Vec<_> = args.iter().map(Array::cast::<u32>).collect();
+            arrayfire::index_gen(dx, slice_indexer(&positions))
+        }))
+}
+
+/// Returns the size of dimension `dim`, in `[B, C, H, W]` order, of the chunks of a tensor of
+/// the given size along it split into `n` chunks along `axis`
+pub const fn chunk(size: u64, n: u64, axis: u64, dim: u64) -> u64 {
+    if axis == dim {
+        size / n
+    } else {
+        size
+    }
+}
+
+/// Splits the tensor into `N` chunks of the same shape along the dimension `AXIS`, in
+/// `[B, C, H, W]` order, i.e. the heads of a multi-head projection.
+///
+/// The chunks are slices of the tensor, so their gradients are put back together in the
+/// gradients of the tensor
+///
+/// # Panics
+///
+/// Panics if `AXIS` is not a dimension or its size is not a multiple of `N`
+#[cfg(feature = "nightly")]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn split<
+    const N: u64,
+    const AXIS: u64,
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+) -> [Tensor<
+    { chunk(B, N, AXIS, 0) },
+    { chunk(C, N, AXIS, 1) },
+    { chunk(H, N, AXIS, 2) },
+    { chunk(W, N, AXIS, 3) },
+    X,
+>; N as usize] {
+    let _op = profiler::forward("split");
+    let shape = [B, C, H, W];
+    assert!(
+        AXIS < 4 && N > 0 && shape[AXIS as usize] % N == 0,
+        "can not split shape {shape:?} into {N} chunks along dimension {AXIS}"
+    );
+
+    let size = shape[AXIS as usize] / N;
+    std::array::from_fn(|i| {
+        let mut offsets = [0; 4];
+        offsets[AXIS as usize] = i as u64 * size;
+        slice(x, offsets, [1; 4])
+    })
+}
+
+/// Returns the shifts of a roll by `shift` values along the dimension `axis`, in `[B, C, H, W]`
+/// order, laid out as arrayfire dimensions
+#[allow(clippy::cast_possible_truncation)]
+const fn roll_shifts(shift: i64, axis: u64) -> [i32; 4] {
+    // Arrayfire lays out the dimensions as [H, W, C, B]
+    let mut shifts = [0; 4];
+    shifts[[3, 2, 0, 1][axis as usize]] = shift as i32;
+    shifts
+}
+
+/// Shifts the values of the tensor by `SHIFT` positions along the dimension `AXIS`, in
+/// `[B, C, H, W]` order, wrapping around the values shifted past the end
+///
+/// # Panics
+///
+/// Panics if `AXIS` is not a dimension
+#[inline]
+pub fn roll<
+    const SHIFT: i64,
+    const AXIS: u64,
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("roll");
+    assert!(AXIS < 4, "can not roll along dimension {AXIS}");
     x.push_unary(
-        arrayfire::sin(&x.data()),
-        |df: &Array<f32>, args: &[Array<f32>]| df * arrayfire::cos(&args[0]),
-        &[x.data()],
+        arrayfire::shift(&x.data(), &roll_shifts(SHIFT, AXIS)),
+        |df: &Array<Float>, _: &[Array<Float>]| arrayfire::shift(df, &roll_shifts(-SHIFT, AXIS)),
+        vec![],
     )
+    .with_tangent(Tangent::Unary(|dx, _| {
+        arrayfire::shift(dx, &roll_shifts(SHIFT, AXIS))
+    }))
 }
 
-/// Cosine operation
+/// Replaces the `NaN`, positive infinite and negative infinite values of the tensor with the
+/// given finite values, i.e. to handle missing data. The replaced values do not propagate
+/// gradients
 #[inline]
-pub fn cos<X: Tensed>(
-    x: &X,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, X::Data> {
+pub fn nan_to_num<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+    nan: Float,
+    posinf: Float,
+    neginf: Float,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("nan_to_num");
+    let (input, dims) = (x.data(), arrayfire::dim4!(H, W, C, B));
+    let (nans, infs) = (arrayfire::isnan(&input), arrayfire::isinf(&input));
+    let finite = arrayfire::not(&arrayfire::or(&nans, &infs, false));
+
+    let infinities = arrayfire::select(
+        &arrayfire::constant(posinf, dims),
+        &arrayfire::gt(&input, &(0.0 as Float), false),
+        &arrayfire::constant(neginf, dims),
+    );
+    let fills = arrayfire::select(&arrayfire::constant(nan, dims), &nans, &infinities);
+    let masked = |df: &Array<Float>, args: &[Array<Float>]| df * &args[0];
     x.push_unary(
-        arrayfire::cos(&x.data()),
-        |df: &Array<f32>, args: &[Array<f32>]| df * -arrayfire::sin(&args[0]),
-        &[x.data()],
+        arrayfire::select(&input, &finite, &fills),
+        masked,
+        vec![finite.cast::<Float>()],
     )
+    .with_tangent(Tangent::Unary(masked))
 }
 
-/// Element-wise addition
+/// Transposes the height and width of every channel of the tensor. Unlike `reshape` and
+/// `slice`, the values are copied
 #[inline]
-pub fn add<X: Tensed, Y: Data>(
-    x: &X,
-    y: &Tensor<{ X::BATCH | 1 }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, Y>,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, <X::Data as Pair<Y>>::Output>
-where
-    X::Data: Pair<Y>,
-{
+pub fn transpose<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, W, H, X> {
+    let _op = profiler::forward("transpose");
+    // Transposing is linear, so the reverse and forward derivatives are the same
+    let derivative = |df: &Array<Float>, _: &[Array<Float>]| arrayfire::transpose(df, false);
+    x.push_unary(arrayfire::transpose(&x.data(), false), derivative, vec![])
+        .with_tangent(Tangent::Unary(derivative))
+}
+
+/// Sine operation
+#[inline]
+pub fn sin<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("sin");
+    // The jacobian is diagonal, so the reverse and forward derivatives are the same
+    let derivative = |df: &Array<Float>, args: &[Array<Float>]| df * arrayfire::cos(&args[0]);
+    x.push_unary(arrayfire::sin(&x.data()), derivative, vec![x.data()])
+        .with_tangent(Tangent::Unary(derivative))
+}
+
+/// Cosine operation
+#[inline]
+pub fn cos<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("cos");
+    let derivative = |df: &Array<Float>, args: &[Array<Float>]| df * -arrayfire::sin(&args[0]);
+    x.push_unary(arrayfire::cos(&x.data()), derivative, vec![x.data()])
+        .with_tangent(Tangent::Unary(derivative))
+}
+
+/// Batch sizes of the output and the right operand of an element-wise binary operation
+struct Broadcast<const B: u64, const YB: u64>;
+
+impl<const B: u64, const YB: u64> Broadcast<B, YB> {
+    /// Fails to compile unless the right operand has the batch of the output or a batch of one
+    const VALID: () = assert!(
+        YB == B || YB == 1,
+        "the right operand must have a batch of B or 1"
+    );
+}
+
+/// Sums the gradients of a right operand with a batch of `YB` along the batch of the output,
+/// undoing its broadcasting
+fn unbroadcast<const YB: u64>(df: &Array<Float>) -> Array<Float> {
+    if YB == 1 {
+        arrayfire::sum(df, 3)
+    } else {
+        df.clone()
+    }
+}
+
+/// Repeats the tangents of a right operand with a batch of `YB` along the `B` samples of the output
+fn broadcast<const B: u64, const YB: u64>(dy: &Array<Float>) -> Array<Float> {
+    arrayfire::tile(dy, arrayfire::dim4!(1, 1, 1, B / YB))
+}
+
+/// Element-wise addition. The right operand may have a batch of one, broadcasted to every sample
+#[inline]
+pub fn add<
+    const B: u64,
+    const YB: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, C, H, W, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let () = Broadcast::<B, YB>::VALID;
+    let _op = profiler::forward("add");
     x.push_binary(
         y,
         arrayfire::add(&x.data(), &y.data(), true),
-        |df: &Array<f32>, _: &[Array<f32>]| (df.clone(), df.clone()),
-        &[],
+        |df: &Array<Float>, _: &[Array<Float>]| (df.clone(), unbroadcast::<YB>(df)),
+        vec![],
     )
+    .with_tangent(Tangent::Binary(
+        |da, _| da.clone(),
+        |db, _| broadcast::<B, YB>(db),
+    ))
 }
 
-/// Element-wise substraction
+/// Element-wise substraction. The right operand may have a batch of one, broadcasted to every
+/// sample
 #[inline]
-pub fn sub<X: Tensed, Y: Data>(
-    x: &X,
-    y: &Tensor<{ X::BATCH | 1 }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, Y>,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, <X::Data as Pair<Y>>::Output>
-where
-    X::Data: Pair<Y>,
-{
+pub fn sub<
+    const B: u64,
+    const YB: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, C, H, W, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let () = Broadcast::<B, YB>::VALID;
+    let _op = profiler::forward("sub");
     x.push_binary(
         y,
         arrayfire::sub(&x.data(), &y.data(), true),
-        |df: &Array<f32>, _: &[Array<f32>]| (df.clone(), -df.clone()),
-        &[],
+        |df: &Array<Float>, _: &[Array<Float>]| (df.clone(), -unbroadcast::<YB>(df)),
+        vec![],
     )
+    .with_tangent(Tangent::Binary(
+        |da, _| da.clone(),
+        |db, _| -broadcast::<B, YB>(db),
+    ))
 }
 
-/// Element-wise multiplication
+/// Element-wise multiplication. The right operand may have a batch of one, broadcasted to every
+/// sample
 #[inline]
-pub fn mul<X: Tensed, Y: Data>(
-    x: &X,
-    y: &Tensor<{ X::BATCH | 1 }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, Y>,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, <X::Data as Pair<Y>>::Output>
-where
-    X::Data: Pair<Y>,
-{
+pub fn mul<
+    const B: u64,
+    const YB: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, C, H, W, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let () = Broadcast::<B, YB>::VALID;
+    let _op = profiler::forward("mul");
     x.push_binary(
         y,
         arrayfire::mul(&x.data(), &y.data(), true),
-        |df: &Array<f32>, args: &[Array<f32>]| (df * &args[1], df * &args[0]),
-        &[x.data(), y.data()],
+        |df: &Array<Float>, args: &[Array<Float>]| {
+            (
+                arrayfire::mul(df, &args[1], true),
+                unbroadcast::<YB>(&(df * &args[0])),
+            )
+        },
+        vec![x.data(), y.data()],
     )
+    .with_tangent(Tangent::Binary(
+        |da, args| arrayfire::mul(da, &args[1], true),
+        |db, args| broadcast::<B, YB>(db) * &args[0],
+    ))
 }
 
-/// Element-wise division
+/// Element-wise division. The right operand may have a batch of one, broadcasted to every sample
 #[inline]
-pub fn div<X: Tensed, Y: Data>(
-    x: &X,
-    y: &Tensor<{ X::BATCH | 1 }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, Y>,
-) -> Tensor<{ X::BATCH }, { X::CHANNELS }, { X::HEIGHT }, { X::WIDTH }, <X::Data as Pair<Y>>::Output>
-where
-    X::Data: Pair<Y>,
-{
+pub fn div<
+    const B: u64,
+    const YB: u64,
+    const C: u64,
+    const H: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, W, X>,
+    y: &Tensor<YB, C, H, W, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let () = Broadcast::<B, YB>::VALID;
+    let _op = profiler::forward("div");
     x.push_binary(
         y,
-        arrayfire::div(&x.data(), &y.data(), false),
-        |df: &Array<f32>, args: &[Array<f32>]| {
+        arrayfire::div(&x.data(), &y.data(), true),
+        |df: &Array<Float>, args: &[Array<Float>]| {
             let (a, b) = (&args[0], &args[1]);
-            (df / b, -(df * a / b / b))
+            (
+                arrayfire::div(df, b, true),
+                -unbroadcast::<YB>(&arrayfire::div(&(df * a), &(b * b), true)),
+            )
         },
-        &[x.data(), y.data()],
+        vec![x.data(), y.data()],
     )
+    .with_tangent(Tangent::Binary(
+        |da, args| arrayfire::div(da, &args[1], true),
+        |db, args| {
+            let (a, b) = (&args[0], &args[1]);
+            -arrayfire::div(&(broadcast::<B, YB>(db) * a), &(b * b), true)
+        },
+    ))
 }
 
 /// Common matrix multiplication
 #[inline]
-pub fn mm<X, Y>(
-    x: &X,
-    y: &Y,
-) -> Tensor<
-    { X::BATCH },
-    { X::CHANNELS },
-    { X::HEIGHT },
-    { Y::WIDTH },
-    <X::Data as Pair<Y::Data>>::Output,
->
-where
-    X: Tensed,
-    Y: Tensed<BATCH = 1, CHANNELS = 1, HEIGHT = { X::WIDTH }>,
-    X::Data: Pair<Y::Data>,
-{
-    let reverse = |df: &Array<f32>, args: &[Array<f32>]| {
+pub fn mm<
+    const B: u64,
+    const C: u64,
+    const H: u64,
+    const K: u64,
+    const W: u64,
+    X: Data + Pair<Y>,
+    Y: Data,
+>(
+    x: &Tensor<B, C, H, K, X>,
+    y: &Tensor<1, 1, K, W, Y>,
+) -> Tensor<B, C, H, W, <X as Pair<Y>>::Output> {
+    let _op = profiler::forward("mm");
+    let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
         (
             arrayfire::matmul(
                 df,
@@ -141,11 +446,18 @@ where
                 arrayfire::MatProp::NONE,
                 arrayfire::MatProp::TRANS,
             ),
-            arrayfire::matmul(
-                &args[0],
-                df,
-                arrayfire::MatProp::TRANS,
-                arrayfire::MatProp::NONE,
+            // `y` is shared by all the channels and the batch, so are the contributions to its gradient
+            arrayfire::sum(
+                &arrayfire::sum(
+                    &arrayfire::matmul(
+                        &args[0],
+                        df,
+                        arrayfire::MatProp::TRANS,
+                        arrayfire::MatProp::NONE,
+                    ),
+                    2,
+                ),
+                3,
             ),
         )
     };
@@ -159,14 +471,95 @@ where
             arrayfire::MatProp::NONE,
         ),
         reverse,
-        &[x.data(), y.data()],
+        vec![x.data(), y.data()],
     )
+    .with_tangent(Tangent::Binary(
+        |da, args| {
+            arrayfire::matmul(
+                da,
+                &args[1],
+                arrayfire::MatProp::NONE,
+                arrayfire::MatProp::NONE,
+            )
+        },
+        |db, args| {
+            arrayfire::matmul(
+                &args[0],
+                db,
+                arrayfire::MatProp::NONE,
+                arrayfire::MatProp::NONE,
+            )
+        },
+    ))
+}
+
+/// Rounds the values to the nearest of a floating point format with the given number of
+/// significant bits, smallest normal exponent and largest finite value. Values beyond the
+/// largest finite value overflow to infinity
+fn round_to(
+    values: &Array<Float>,
+    precision: Float,
+    min_exponent: Float,
+    max: Float,
+) -> Array<Float> {
+    // Subnormal values are spaced as the smallest normal ones
+    let exponent = arrayfire::maxof(
+        &arrayfire::floor(&arrayfire::log2(&arrayfire::abs(values))),
+        &min_exponent,
+        false,
+    );
+    let spacing = arrayfire::pow2(&(exponent - (precision - 1.0)));
+    let rounded = arrayfire::round(&(values / &spacing)) * &spacing;
+    let overflow = arrayfire::gt(&arrayfire::abs(&rounded), &max, false);
+    arrayfire::select(&(values * Float::INFINITY), &overflow, &rounded)
+}
+
+/// Rounds the values to half precision (`f16`), keeping them stored with the tensors
+/// element type. The gradients are rounded to half precision as well, which simulates
+/// mixed precision training
+#[inline]
+pub fn to_f16<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("to_f16");
+    let round = |df: &Array<Float>, _: &[Array<Float>]| round_to(df, 11.0, -14.0, 65504.0);
+    x.push_unary(round(&x.data(), &[]), round, vec![])
+        .with_tangent(Tangent::Unary(round))
+}
+
+/// Rounds the values to brain floating point precision (`bf16`), see `to_f16`
+#[inline]
+pub fn to_bf16<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("to_bf16");
+    let round = |df: &Array<Float>, _: &[Array<Float>]| round_to(df, 8.0, -126.0, 3.389_531_4e38);
+    x.push_unary(round(&x.data(), &[]), round, vec![])
+        .with_tangent(Tangent::Unary(round))
+}
+
+/// Rounds the values to single precision (`f32`), see `to_f16`. It has no effect unless the
+/// `f64` feature is enabled
+#[inline]
+pub fn to_f32<const B: u64, const C: u64, const H: u64, const W: u64, X: Data>(
+    x: &Tensor<B, C, H, W, X>,
+) -> Tensor<B, C, H, W, X> {
+    let _op = profiler::forward("to_f32");
+    let round = |df: &Array<Float>, _: &[Array<Float>]| round_to(df, 24.0, -126.0, 3.402_823_5e38);
+    x.push_unary(round(&x.data(), &[]), round, vec![])
+        .with_tangent(Tangent::Unary(round))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{add, cos, div, mm, mul, reshape, sin, sub, Tensed};
+    #[cfg(feature = "nightly")]
+    use super::split;
+    use super::{
+        add, cos, div, mm, mul, nan_to_num, reshape, roll, sin, slice, sub, to_bf16, to_f16,
+        transpose, Tensed,
+    };
     use crate as mu;
+    use crate::tensor::Float;
     use crate::tests::equal_data;
     use arrayfire::{constant, dim4, Array};
 
@@ -188,6 +581,126 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn slice_forward_backward() {
+        let x = mu::custom::<1, 1, 3, 4>(&[
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0,
+        ]);
+        let z = slice::<1, 1, 2, 2, _>(&x, [0, 0, 1, 1], [1, 1, 1, 2]);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[5.0, 6.0, 11.0, 12.0], dim4!(2, 2, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(
+                &[0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0],
+                dim4!(3, 4, 1, 1)
+            )
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds shape")]
+    fn slice_out_of_bounds() {
+        let x = mu::fill::<1, 1, 3, 4>(1.0);
+        let _ = slice::<1, 1, 2, 2, _>(&x, [0, 0, 2, 0], [1, 1, 1, 1]);
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn split_forward_backward() {
+        let x = mu::custom::<2, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        let [left, right] = split::<2, 3, 2, 1, 2, 2, _>(&x);
+        assert!(equal_data(
+            left.data(),
+            Array::new(&[1.0, 2.0, 5.0, 6.0], dim4!(2, 1, 1, 2))
+        ));
+        assert!(equal_data(
+            right.data(),
+            Array::new(&[3.0, 4.0, 7.0, 8.0], dim4!(2, 1, 1, 2))
+        ));
+
+        let [first, _] = split::<2, 0, 2, 1, 2, 2, _>(&x);
+        assert!(equal_data(
+            first.data(),
+            Array::new(&[1.0, 2.0, 3.0, 4.0], dim4!(2, 2, 1, 1))
+        ));
+
+        mu::add(&mu::mul(&left, &left), &right).backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(
+                &[2.0, 4.0, 1.0, 1.0, 10.0, 12.0, 1.0, 1.0],
+                dim4!(2, 2, 1, 2)
+            )
+        ));
+    }
+
+    #[test]
+    fn roll_forward_backward() {
+        let x = mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let z = roll::<1, 3, 1, 1, 2, 3, _>(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[5.0, 6.0, 1.0, 2.0, 3.0, 4.0], dim4!(2, 3, 1, 1))
+        ));
+
+        mu::mul(
+            &z,
+            &mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).freeze(),
+        )
+        .backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[3.0, 4.0, 5.0, 6.0, 1.0, 2.0], dim4!(2, 3, 1, 1))
+        ));
+
+        let z = roll::<-1, 2, 1, 1, 2, 3, _>(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[2.0, 1.0, 4.0, 3.0, 6.0, 5.0], dim4!(2, 3, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn nan_to_num_forward_backward() {
+        let x = mu::custom::<1, 1, 1, 4>(&[1.0, Float::NAN, Float::INFINITY, Float::NEG_INFINITY]);
+        let z = nan_to_num(&x, 0.0, 10.0, -10.0);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.0, 0.0, 10.0, -10.0], dim4!(1, 4, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[1.0, 0.0, 0.0, 0.0], dim4!(1, 4, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn transpose_forward_backward() {
+        let x = mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let z = transpose(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.0, 3.0, 5.0, 2.0, 4.0, 6.0], dim4!(3, 2, 1, 1))
+        ));
+
+        mu::mul(
+            &z,
+            &mu::custom::<1, 1, 3, 2>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).freeze(),
+        )
+        .backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0], dim4!(2, 3, 1, 1))
+        ));
+    }
+
     #[test]
     fn sin_forward_backward() {
         let x = mu::eye::<1, 1, 2, 3>(0.5);
@@ -276,7 +789,7 @@ mod tests {
         assert!(equal_data(x.grad().data(), constant!(2.0; 3,2,1,1)));
         assert!(equal_data(
             y.grad().data(),
-            arrayfire::identity::<f32>(dim4!(3, 2, 1, 1)) * 3.0f32
+            arrayfire::identity::<Float>(dim4!(3, 2, 1, 1)) * (3.0 as Float)
         ));
     }
 
@@ -292,6 +805,27 @@ mod tests {
         assert!(equal_data(y.grad().data(), constant!(-0.125; 3,2,1,1)));
     }
 
+    #[test]
+    fn broadcast_batch_of_one() {
+        let x = mu::fill::<2, 1, 1, 2>(3.0);
+        let y = mu::custom::<1, 1, 1, 2>(&[1.0, 2.0]);
+        let z = mul(&add(&x, &y), &y);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[4.0, 10.0, 4.0, 10.0], dim4!(1, 2, 1, 2))
+        ));
+
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            Array::new(&[1.0, 2.0, 1.0, 2.0], dim4!(1, 2, 1, 2))
+        ));
+        assert!(equal_data(
+            y.grad().data(),
+            Array::new(&[10.0, 14.0], dim4!(1, 2, 1, 1))
+        ));
+    }
+
     #[test]
     fn mm_forward_backward() {
         let x = mu::eye::<1, 1, 3, 2>(3.0);
@@ -308,5 +842,37 @@ mod tests {
         z.backward();
         assert!(equal_data(x.grad().data(), constant!(2.0; 3,2,1,1)));
         assert!(equal_data(y.grad().data(), constant!(3.0; 2,4,1,1)));
+
+        // The partials of the shared `y` are summed over the batch
+        let x = mu::eye::<2, 1, 3, 2>(3.0);
+        let z = mm(&x, &y);
+        z.backward();
+        assert!(equal_data(x.grad().data(), constant!(2.0; 3,2,1,2)));
+        assert!(equal_data(y.grad().data(), constant!(6.0; 2,4,1,1)));
+    }
+
+    #[test]
+    fn to_f16_forward_backward() {
+        let x = mu::custom::<1, 1, 1, 3>(&[1.0, 1.000_244_1, 1e-8]);
+        let z = to_f16(&x);
+        assert!(equal_data(
+            z.data(),
+            Array::new(&[1.0, 1.0, 0.0], dim4!(1, 3, 1, 1))
+        ));
+
+        z.backward();
+        assert!(equal_data(x.grad().data(), constant!(1.0; 1, 3, 1, 1)));
+
+        let overflow = to_f16(&mu::fill::<1, 1, 1, 1>(70000.0)).to_scalar();
+        assert!(overflow.is_infinite());
+    }
+
+    #[test]
+    fn to_bf16_forward() {
+        let x = mu::custom::<1, 1, 1, 3>(&[1.0, 1.001, 70000.0]);
+        assert!(equal_data(
+            to_bf16(&x).data(),
+            Array::new(&[1.0, 1.0, 70144.0], dim4!(1, 3, 1, 1))
+        ));
     }
 }
@@ -0,0 +1,254 @@
+//! Opt-in profiler of the time taken by every operation, both to compute its result and to
+//! compute its partial derivatives during the backward pass.
+//!
+//! Operations name the nodes they create, i.e. `sin` or `mse`, and their profile is
+//! aggregated by name. Operations composed of others include the time of the latter, and the
+//! derivatives of tensors named with `Tensor::named` are aggregated by their name instead.
+//!
+//! ## Usage
+//! ```rust
+//! #![feature(generic_const_exprs)]
+//!
+//! use mushin as mu;
+//!
+//! mu::profiler::start(true);
+//! let x = mu::fill::<1, 1, 2, 2>(2.0);
+//! mu::sin(&mu::mul(&x, &x)).backward();
+//! let profile = mu::profiler::stop();
+//!
+//! println!("{profile}");
+//! let trace = profile.to_chrome_trace();
+//! ```
+
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SYNCHRONIZE: AtomicBool = AtomicBool::new(false);
+static SESSION: Mutex<Option<(Instant, Vec<Event>)>> = Mutex::new(None);
+
+thread_local! {
+    /// Names of the operations being computed, the innermost last
+    static OPERATIONS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Starts profiling the operations, discarding any previous profile.
+///
+/// With `synchronize` the device is synchronized before and after every operation, so that the time of each includes
+/// its computation and not only the time to queue it, at the cost of slowing them down
+#[inline]
+pub fn start(synchronize: bool) {
+    SYNCHRONIZE.store(synchronize, Ordering::Relaxed);
+    *SESSION.lock().unwrap_or_else(PoisonError::into_inner) = Some((Instant::now(), Vec::new()));
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops profiling and returns the operations profiled since `start`
+#[must_use]
+#[inline]
+pub fn stop() -> Profile {
+    ENABLED.store(false, Ordering::Relaxed);
+    let session = SESSION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    Profile(session.map(|(_, events)| events).unwrap_or_default())
+}
+
+/// Returns the name of the innermost operation being computed by this thread, if any
+pub(crate) fn current() -> Option<&'static str> {
+    OPERATIONS.with(|operations| operations.borrow().last().copied())
+}
+
+/// Synchronizes the device if requested by `start`
+fn synchronize() {
+    if SYNCHRONIZE.load(Ordering::Relaxed) {
+        crate::sync();
+    }
+}
+
+/// Marks the computation of an operation until dropped, see `forward` and `backward`
+pub(crate) struct Span {
+    name: &'static str,
+    pass: Pass,
+    start: Option<Instant>,
+}
+
+impl Span {
+    fn new(name: &'static str, pass: Pass) -> Self {
+        let start = ENABLED.load(Ordering::Relaxed).then(|| {
+            synchronize();
+            Instant::now()
+        });
+        Self { name, pass, start }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if self.pass == Pass::Forward {
+            OPERATIONS.with(|operations| operations.borrow_mut().pop());
+        }
+
+        let Some(start) = self.start else {
+            return;
+        };
+        synchronize();
+        let duration = start.elapsed();
+        if let Some((epoch, ref mut events)) =
+            *SESSION.lock().unwrap_or_else(PoisonError::into_inner)
+        {
+            events.push(Event {
+                name: self.name,
+                pass: self.pass,
+                start: start.saturating_duration_since(epoch),
+                duration,
+            });
+        }
+    }
+}
+
+/// Marks the computation of the result of the given operation until dropped. The nodes created
+/// meanwhile are named after it
+pub(crate) fn forward(name: &'static str) -> Span {
+    OPERATIONS.with(|operations| operations.borrow_mut().push(name));
+    Span::new(name, Pass::Forward)
+}
+
+/// Marks the computation of the partial derivatives of the given operation until dropped
+pub(crate) fn backward(name: &'static str) -> Span {
+    Span::new(name, Pass::Backward)
+}
+
+/// The pass of the computation graph an operation was profiled in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pass {
+    /// Computation of the result of the operation
+    Forward,
+    /// Computation of its partial derivatives, during the backward pass
+    Backward,
+}
+
+/// A single computation of an operation
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    /// Name of the operation
+    pub name: &'static str,
+    /// Whether the result or the partial derivatives were computed
+    pub pass: Pass,
+    /// Time elapsed since profiling started until the computation did
+    pub start: Duration,
+    /// Time taken by the computation
+    pub duration: Duration,
+}
+
+/// The operations computed while profiling. It displays as a table with the number of calls
+/// and time taken by every operation and pass, slowest first
+#[derive(Clone, Debug, Default)]
+pub struct Profile(Vec<Event>);
+
+impl Profile {
+    /// Returns the computations of the operations in the order they finished
+    #[must_use]
+    #[inline]
+    pub fn events(&self) -> &[Event] {
+        &self.0
+    }
+
+    /// Returns the number of calls and total time taken by every operation and pass
+    #[must_use]
+    #[inline]
+    pub fn summary(&self) -> BTreeMap<(&'static str, Pass), (usize, Duration)> {
+        let mut summary = BTreeMap::new();
+        for event in &self.0 {
+            let entry = summary
+                .entry((event.name, event.pass))
+                .or_insert((0, Duration::ZERO));
+            entry.0 += 1;
+            entry.1 += event.duration;
+        }
+        summary
+    }
+
+    /// Returns the profile in the Chrome trace event format, which can be loaded in
+    /// `chrome://tracing` or [Perfetto](https://ui.perfetto.dev)
+    #[must_use]
+    #[inline]
+    pub fn to_chrome_trace(&self) -> String {
+        let events = self
+            .0
+            .iter()
+            .map(|event| {
+                format!(
+                    "{{\"name\":\"{}\",\"cat\":\"{:?}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":0}}",
+                    event.name,
+                    event.pass,
+                    event.start.as_micros(),
+                    event.duration.as_micros()
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("{{\"traceEvents\":[{}]}}", events.join(","))
+    }
+}
+
+impl fmt::Display for Profile {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rows = self.summary().into_iter().collect::<Vec<_>>();
+        rows.sort_by_key(|&(_, (_, total))| Reverse(total));
+
+        writeln!(
+            f,
+            "{:<24} {:<8} {:>8} {:>12} {:>12}",
+            "operation", "pass", "calls", "total (ms)", "mean (ms)"
+        )?;
+        for ((name, pass), (calls, total)) in rows {
+            let total_ms = total.as_secs_f64() * 1e3;
+            #[allow(clippy::cast_precision_loss)]
+            let mean_ms = total_ms / calls as f64;
+            writeln!(
+                f,
+                "{name:<24} {:<8} {calls:>8} {total_ms:>12.3} {mean_ms:>12.3}",
+                format!("{pass:?}")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{start, stop, Pass};
+    use crate as mu;
+
+    #[test]
+    fn profile_operations() {
+        start(false);
+        let x = mu::fill::<1, 1, 2, 2>(2.0);
+        let z = mu::sin(&mu::mul(&x, &x));
+        z.backward();
+        // Named tensors are profiled by their name during the backward pass
+        mu::sin(&mu::mul(&x, &x).named("squares")).backward();
+        let profile = stop();
+
+        // Tests running meanwhile are profiled too
+        let summary = profile.summary();
+        assert!(summary[&("sin", Pass::Forward)].0 >= 1);
+        assert!(summary[&("mul", Pass::Forward)].0 >= 1);
+        assert!(summary[&("sin", Pass::Backward)].0 >= 1);
+        assert!(summary[&("mul", Pass::Backward)].0 >= 1);
+        assert!(summary[&("squares", Pass::Backward)].0 >= 1);
+        assert!(profile.to_chrome_trace().contains("\"name\":\"sin\""));
+        assert!(profile.to_string().starts_with("operation"));
+    }
+}
@@ -1,31 +1,45 @@
 use crate::{
-    graph::node::{BinaryReverseFn, Node, UnaryReverseFn},
+    graph::node::{BinaryReverseFn, Node, Tangent, UnaryReverseFn},
     tensor::{
         traits::{Data, Pair},
         variable::Variable,
+        Float,
     },
 };
 use arrayfire::Array;
 
 /// Data for a non-differentiable tensor not tracked in the computation graph
 #[derive(Clone)]
-pub struct Constant(Array<f32>);
+pub struct Constant(Array<Float>);
 
 impl Constant {
     /// Constructs constant data from a given array
-    pub fn new(data: Array<f32>) -> Self {
+    pub fn new(data: Array<Float>) -> Self {
         Self(data)
     }
 }
 
 impl Data for Constant {
-    fn push_unary(&self, data: Array<f32>, _reverse: UnaryReverseFn, _args: &[Array<f32>]) -> Self {
+    const TRACKED: bool = false;
+
+    fn push_unary(
+        &self,
+        data: Array<Float>,
+        _reverse: UnaryReverseFn,
+        _args: Vec<Array<Float>>,
+    ) -> Self {
         Self::new(data)
     }
 
-    fn values(&self) -> Array<f32> {
+    fn values(&self) -> Array<Float> {
         self.0.clone()
     }
+
+    fn set_tangent(&self, _tangent: Tangent) {}
+
+    fn eval(&self) {
+        self.0.eval();
+    }
 }
 
 impl Pair<Variable> for Constant {
@@ -34,12 +48,11 @@ impl Pair<Variable> for Constant {
     fn push_binary(
         &self,
         other: &Variable,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self::Output {
-        let node = Node::binary_constvar(data, other.node(), reverse, args);
-        Variable::new(other.tape().clone(), node)
+        Variable::new(Node::binary_constvar(data, other.node(), reverse, args))
     }
 }
 
@@ -49,18 +62,24 @@ impl Pair<Self> for Constant {
     fn push_binary(
         &self,
         _other: &Self,
-        data: Array<f32>,
+        data: Array<Float>,
         _reverse: BinaryReverseFn,
-        _args: &[Array<f32>],
+        _args: Vec<Array<Float>>,
     ) -> Self::Output {
         Self::new(data)
     }
 }
 
+impl From<Array<Float>> for Constant {
+    fn from(data: Array<Float>) -> Self {
+        Self::new(data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Constant;
-    use crate::graph::{node::Node, tape::Tape};
+    use crate::graph::node::Node;
     use crate::tensor::{
         traits::{Data, Pair},
         Variable,
@@ -82,7 +101,7 @@ mod tests {
         let constant = constant.push_unary(
             arrayfire::constant!(2.0; 1,1,1,1),
             |_, _| arrayfire::constant!(1.0; 1,1,1,1),
-            &[],
+            vec![],
         );
         assert!(equal_data(
             constant.values(),
@@ -103,7 +122,7 @@ mod tests {
                     arrayfire::constant!(1.0; 1,1,1,1),
                 )
             },
-            &[],
+            vec![],
         );
         assert!(equal_data(
             constant.values(),
@@ -114,10 +133,7 @@ mod tests {
     #[test]
     fn push_binary_variable() {
         let constant = Constant::new(arrayfire::constant!(5.0; 1,1,1,1));
-        let other = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(4.0; 1,1,1,1)),
-        );
+        let other = Variable::new(Node::declaration(arrayfire::constant!(4.0; 1,1,1,1)));
         let variable = constant.push_binary(
             &other,
             arrayfire::constant!(2.0; 1,1,1,1),
@@ -127,7 +143,7 @@ mod tests {
                     arrayfire::constant!(1.0; 1,1,1,1),
                 )
             },
-            &[],
+            vec![],
         );
         assert!(equal_data(
             variable.grad(),
@@ -0,0 +1,122 @@
+//! Human readable formatting of tensors. Values are printed in row-major order,
+//! truncated to their first and last ones for large tensors
+
+use crate::tensor::{constant::Constant, traits::Data, variable::Variable, Float, Tensor};
+use arrayfire::Array;
+use std::fmt;
+
+/// Number of values printed at each end of a truncated tensor
+const EDGE: usize = 3;
+
+/// Tensor values formatted as a list, truncated if longer than twice `EDGE`
+struct Values(Vec<Float>);
+
+impl Values {
+    /// Copies the given array to the host in row-major order
+    fn row_major(array: &Array<Float>) -> Self {
+        let array = arrayfire::transpose(array, false);
+        let mut host = vec![0.0; array.elements()];
+        array.host(&mut host);
+        Self(host)
+    }
+}
+
+impl fmt::Debug for Values {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.len() <= 2 * EDGE {
+            return f.debug_list().entries(&self.0).finish();
+        }
+
+        write!(f, "[")?;
+        for value in &self.0[..EDGE] {
+            write!(f, "{value:?}, ")?;
+        }
+        write!(f, "...")?;
+        for value in &self.0[self.0.len() - EDGE..] {
+            write!(f, ", {value:?}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Writes the kind of tensor data, its shape and values
+fn describe<const B: u64, const C: u64, const H: u64, const W: u64, D: Data>(
+    f: &mut fmt::Formatter<'_>,
+    kind: &str,
+    tensor: &Tensor<B, C, H, W, D>,
+) -> fmt::Result {
+    write!(
+        f,
+        "{kind} [{B}, {C}, {H}, {W}] {:?}",
+        Values::row_major(&tensor.0.values())
+    )
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64> fmt::Display
+    for Tensor<B, C, H, W, Variable>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        describe(f, "Variable", self)
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64> fmt::Display
+    for Tensor<B, C, H, W, Constant>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        describe(f, "Constant", self)
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64> fmt::Debug
+    for Tensor<B, C, H, W, Variable>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Variable")
+            .field("shape", &[B, C, H, W])
+            .field("data", &Values::row_major(&self.0.values()))
+            .field("grad", &Values::row_major(&self.0.grad()))
+            .finish()
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64> fmt::Debug
+    for Tensor<B, C, H, W, Constant>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Constant")
+            .field("shape", &[B, C, H, W])
+            .field("data", &Values::row_major(&self.0.values()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Values;
+    use crate as mu;
+    use crate::tensor::Float;
+
+    #[test]
+    fn values_truncated() {
+        assert_eq!(format!("{:?}", Values(vec![1.0, 2.0])), "[1.0, 2.0]");
+        assert_eq!(
+            format!("{:?}", Values((0..8).map(|v| v as Float).collect())),
+            "[0.0, 1.0, 2.0, ..., 5.0, 6.0, 7.0]"
+        );
+    }
+
+    #[test]
+    fn tensor_display_debug() {
+        let x = mu::custom::<1, 1, 2, 2>(&[1.0, 3.0, 2.0, 4.0]);
+        assert_eq!(format!("{x}"), "Variable [1, 1, 2, 2] [1.0, 2.0, 3.0, 4.0]");
+        assert_eq!(
+            format!("{:?}", x.freeze()),
+            "Constant { shape: [1, 1, 2, 2], data: [1.0, 2.0, 3.0, 4.0] }"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! This module includes the `DynTensor` type, a tensor whose shape is only known at runtime,
+//! i.e. the last partial batch of a dataset or a sequence of variable length.
+//!
+//! It supports the same operations as `Tensor`, recorded in the same computation graph, but
+//! shapes are checked when the operation is computed instead of at compile time. A `DynTensor`
+//! can be converted from any `Tensor` and back to a `Tensor` of its same shape.
+
+use crate::graph::node::Node;
+use crate::profiler;
+use crate::tensor::{
+    constant::Constant,
+    materialize,
+    traits::{Data, Pair},
+    variable::Variable,
+    BackwardOptions, Float, Tensor,
+};
+use crate::ShapeError;
+use arrayfire::{dim4, Array, Dim4, MatProp};
+use std::{error::Error, fmt};
+
+/// A tensor with a runtime shape `[B, C, H, W]`, holding either `Variable` or `Constant` data
+#[derive(Clone)]
+pub struct DynTensor<D: Data>(D);
+
+/// Returns the shape `[B, C, H, W]` of an array
+fn shape_of(dims: Dim4) -> [u64; 4] {
+    [dims[3], dims[2], dims[0], dims[1]]
+}
+
+/// Returns the array dimensions of a shape `[B, C, H, W]`
+fn dims_of(shape: [u64; 4]) -> Dim4 {
+    dim4!(shape[2], shape[3], shape[1], shape[0])
+}
+
+impl<D: Data + From<Array<Float>>> DynTensor<D> {
+    /// Creates a tensor of the given shape from its values, laid out in column-major order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of values does not match the shape
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn custom(shape: [u64; 4], values: &[Float]) -> Result<Self, ShapeError> {
+        let expected = shape.iter().product::<u64>() as usize;
+        if values.len() != expected {
+            return Err(ShapeError {
+                expected,
+                found: values.len(),
+            });
+        }
+        Ok(Self(D::from(Array::new(values, dims_of(shape)))))
+    }
+
+    /// Creates a tensor of the given shape filled with the given value
+    #[must_use]
+    #[inline]
+    pub fn fill(shape: [u64; 4], v: Float) -> Self {
+        Self(D::from(arrayfire::constant(v, dims_of(shape))))
+    }
+}
+
+impl<D: Data> DynTensor<D> {
+    /// Returns the shape of the tensor as `[B, C, H, W]`
+    #[must_use]
+    #[inline]
+    pub fn shape(&self) -> [u64; 4] {
+        shape_of(self.0.values().dims())
+    }
+
+    /// Copies the tensor values to the host, laid out in column-major order
+    #[must_use]
+    #[inline]
+    pub fn to_vec(&self) -> Vec<Float> {
+        let values = self.0.values();
+        let mut host = vec![0.0; values.elements()];
+        values.host(&mut host);
+        host
+    }
+
+    /// Sine operation
+    #[must_use]
+    #[inline]
+    pub fn sin(&self) -> Self {
+        let _op = profiler::forward("sin");
+        let data = self.0.values();
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| df * arrayfire::cos(&args[0]);
+        Self(
+            self.0
+                .push_unary(materialize::<D>(arrayfire::sin(&data)), reverse, vec![data]),
+        )
+    }
+
+    /// Cosine operation
+    #[must_use]
+    #[inline]
+    pub fn cos(&self) -> Self {
+        let _op = profiler::forward("cos");
+        let data = self.0.values();
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| df * -arrayfire::sin(&args[0]);
+        Self(
+            self.0
+                .push_unary(materialize::<D>(arrayfire::cos(&data)), reverse, vec![data]),
+        )
+    }
+
+    /// Changes the shape of the tensor to the given one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new shape does not have the same number of values
+    #[must_use]
+    #[inline]
+    pub fn reshape(&self, shape: [u64; 4]) -> Self {
+        let _op = profiler::forward("reshape");
+        let data = self.0.values();
+        assert!(
+            shape.iter().product::<u64>() == data.elements() as u64,
+            "can not reshape a tensor of shape {:?} to {shape:?}",
+            self.shape()
+        );
+
+        // The original array is only kept for its dimensions, it shares the tensor memory
+        let reverse =
+            |df: &Array<Float>, args: &[Array<Float>]| arrayfire::moddims(df, args[0].dims());
+        let result = arrayfire::moddims(&data, dims_of(shape));
+        Self(
+            self.0
+                .push_unary(materialize::<D>(result), reverse, vec![data]),
+        )
+    }
+
+    /// Checks both tensors have the same shape, for element-wise operations
+    fn same_shape<Y: Data>(&self, other: &DynTensor<Y>, op: &str) {
+        let (x, y) = (self.shape(), other.shape());
+        assert!(x == y, "can not {op} tensors of shapes {x:?} and {y:?}");
+    }
+
+    /// Element-wise addition
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tensors do not have the same shape
+    #[must_use]
+    #[inline]
+    pub fn add<Y: Data>(&self, other: &DynTensor<Y>) -> DynTensor<D::Output>
+    where
+        D: Pair<Y>,
+    {
+        let _op = profiler::forward("add");
+        self.same_shape(other, "add");
+        DynTensor(self.0.push_binary(
+            &other.0,
+            materialize::<D::Output>(arrayfire::add(&self.0.values(), &other.0.values(), false)),
+            |df: &Array<Float>, _: &[Array<Float>]| (df.clone(), df.clone()),
+            vec![],
+        ))
+    }
+
+    /// Element-wise substraction
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tensors do not have the same shape
+    #[must_use]
+    #[inline]
+    pub fn sub<Y: Data>(&self, other: &DynTensor<Y>) -> DynTensor<D::Output>
+    where
+        D: Pair<Y>,
+    {
+        let _op = profiler::forward("sub");
+        self.same_shape(other, "substract");
+        DynTensor(self.0.push_binary(
+            &other.0,
+            materialize::<D::Output>(arrayfire::sub(&self.0.values(), &other.0.values(), false)),
+            |df: &Array<Float>, _: &[Array<Float>]| (df.clone(), -df.clone()),
+            vec![],
+        ))
+    }
+
+    /// Element-wise multiplication
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tensors do not have the same shape
+    #[must_use]
+    #[inline]
+    pub fn mul<Y: Data>(&self, other: &DynTensor<Y>) -> DynTensor<D::Output>
+    where
+        D: Pair<Y>,
+    {
+        let _op = profiler::forward("mul");
+        self.same_shape(other, "multiply");
+        let (x, y) = (self.0.values(), other.0.values());
+        DynTensor(self.0.push_binary(
+            &other.0,
+            materialize::<D::Output>(arrayfire::mul(&x, &y, false)),
+            |df: &Array<Float>, args: &[Array<Float>]| (df * &args[1], df * &args[0]),
+            vec![x, y],
+        ))
+    }
+
+    /// Element-wise division
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tensors do not have the same shape
+    #[must_use]
+    #[inline]
+    pub fn div<Y: Data>(&self, other: &DynTensor<Y>) -> DynTensor<D::Output>
+    where
+        D: Pair<Y>,
+    {
+        let _op = profiler::forward("div");
+        self.same_shape(other, "divide");
+        let (x, y) = (self.0.values(), other.0.values());
+        DynTensor(self.0.push_binary(
+            &other.0,
+            materialize::<D::Output>(arrayfire::div(&x, &y, false)),
+            |df: &Array<Float>, args: &[Array<Float>]| {
+                let (a, b) = (&args[0], &args[1]);
+                (df / b, -(df * a / b / b))
+            },
+            vec![x, y],
+        ))
+    }
+
+    /// Common matrix multiplication of a `[B, C, H, K]` tensor by a `[1, 1, K, W]` one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shapes of the tensors do not match
+    #[must_use]
+    #[inline]
+    pub fn mm<Y: Data>(&self, other: &DynTensor<Y>) -> DynTensor<D::Output>
+    where
+        D: Pair<Y>,
+    {
+        let _op = profiler::forward("mm");
+        let (a, b) = (self.shape(), other.shape());
+        assert!(
+            b[0] == 1 && b[1] == 1 && a[3] == b[2],
+            "can not multiply matrices of shapes {a:?} and {b:?}"
+        );
+
+        let reverse = |df: &Array<Float>, args: &[Array<Float>]| {
+            (
+                arrayfire::matmul(df, &args[1], MatProp::NONE, MatProp::TRANS),
+                arrayfire::matmul(&args[0], df, MatProp::TRANS, MatProp::NONE),
+            )
+        };
+        let (x, y) = (self.0.values(), other.0.values());
+        DynTensor(self.0.push_binary(
+            &other.0,
+            materialize::<D::Output>(arrayfire::matmul(&x, &y, MatProp::NONE, MatProp::NONE)),
+            reverse,
+            vec![x, y],
+        ))
+    }
+}
+
+impl DynTensor<Variable> {
+    /// Returns the tensor gradients as another variable tensor
+    #[must_use]
+    #[inline]
+    pub fn grad(&self) -> Self {
+        Self(Variable::new(Node::declaration(self.0.grad())))
+    }
+
+    /// Consumes the variable tensor and returns it as a constant tensor
+    #[must_use]
+    #[inline]
+    pub fn freeze(self) -> DynTensor<Constant> {
+        DynTensor(Constant::new(self.0.values()))
+    }
+
+    /// Computes the gradients of all the ancestors of this tensor, see `Tensor::backward`
+    #[inline]
+    pub fn backward(&self) {
+        self.0.backward(BackwardOptions::new());
+    }
+
+    /// Same as `backward`, with the given options, see `Tensor::backward_with`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the computation graph was released by a previous backward pass
+    #[inline]
+    pub fn backward_with(&self, options: BackwardOptions) {
+        self.0.backward(options);
+    }
+}
+
+impl DynTensor<Constant> {
+    /// Consumes the constant tensor and returns it as a variable tensor
+    #[must_use]
+    #[inline]
+    pub fn unfreeze(self) -> DynTensor<Variable> {
+        DynTensor(Variable::new(Node::declaration(self.0.values())))
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> From<Tensor<B, C, H, W, D>>
+    for DynTensor<D>
+{
+    #[inline]
+    fn from(tensor: Tensor<B, C, H, W, D>) -> Self {
+        Self(tensor.0)
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> TryFrom<DynTensor<D>>
+    for Tensor<B, C, H, W, D>
+{
+    type Error = ShapeMismatch;
+
+    /// Converts the tensor into a `Tensor` of the same shape, sharing its node in the
+    /// computation graph
+    #[inline]
+    fn try_from(tensor: DynTensor<D>) -> Result<Self, Self::Error> {
+        let found = tensor.shape();
+        if found == [B, C, H, W] {
+            Ok(Self(tensor.0))
+        } else {
+            Err(ShapeMismatch {
+                expected: [B, C, H, W],
+                found,
+            })
+        }
+    }
+}
+
+/// The runtime shape of a `DynTensor` does not match the expected one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeMismatch {
+    /// The expected shape `[B, C, H, W]`
+    pub expected: [u64; 4],
+    /// The shape of the tensor
+    pub found: [u64; 4],
+}
+
+impl fmt::Display for ShapeMismatch {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected tensor of shape {:?}, found {:?}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl Error for ShapeMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynTensor, ShapeMismatch};
+    use crate as mu;
+    use crate::tensor::{constant::Constant, traits::Tensed, variable::Variable, Tensor};
+    use crate::tests::equal_data;
+
+    #[test]
+    fn dyn_matches_static() {
+        let x = mu::custom::<1, 1, 2, 3>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let w = mu::custom::<1, 1, 3, 2>(&[0.5, -1.0, 2.0, 1.0, 0.0, -0.5]);
+        let z = mu::mul(&mu::sin(&x), &x);
+        let z = mu::mm(&z, &w);
+        z.backward();
+
+        let dx = DynTensor::<Variable>::custom([1, 1, 2, 3], &x.to_vec()).unwrap();
+        let dw: DynTensor<Variable> = w.clone().into();
+        let dz = dx.sin().mul(&dx).mm(&dw.freeze().unfreeze());
+        dz.backward();
+
+        assert_eq!(dz.shape(), [1, 1, 2, 2]);
+        let dz: Tensor<1, 1, 2, 2, Variable> = dz.try_into().unwrap();
+        assert!(equal_data(dz.data(), z.data()));
+        assert!(equal_data(
+            Tensor::<1, 1, 2, 3, Variable>::try_from(dx.grad())
+                .unwrap()
+                .data(),
+            x.grad().data()
+        ));
+    }
+
+    #[test]
+    fn dyn_shape_checks() {
+        let x = DynTensor::<Constant>::fill([2, 1, 1, 3], 1.0);
+        assert_eq!(x.reshape([1, 1, 3, 2]).shape(), [1, 1, 3, 2]);
+        assert!(DynTensor::<Constant>::custom([2, 1, 1, 3], &[1.0]).is_err());
+        assert_eq!(
+            Tensor::<1, 1, 1, 3, Constant>::try_from(x).err(),
+            Some(ShapeMismatch {
+                expected: [1, 1, 1, 3],
+                found: [2, 1, 1, 3]
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can not add tensors of shapes [1, 1, 1, 2] and [1, 1, 2, 1]")]
+    fn dyn_shape_mismatch() {
+        let x = DynTensor::<Constant>::fill([1, 1, 1, 2], 1.0);
+        let y = DynTensor::<Constant>::fill([1, 1, 2, 1], 1.0);
+        let _ = x.add(&y);
+    }
+}
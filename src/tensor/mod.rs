@@ -19,28 +19,195 @@
 //! tracked in the computation graph.
 
 pub mod constant;
+mod display;
+pub mod dynamic;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod traits;
 pub mod variable;
 
 use crate::graph::{
-    node::{BinaryReverseFn, Node, UnaryReverseFn},
-    tape::Tape,
+    node::{BinaryReverseFn, Node, Tangent, UnaryReverseFn},
+    shared::Threaded,
+    tape::GraphInfo,
 };
-use arrayfire::Array;
+use crate::profiler;
+use arrayfire::{Array, Seq};
 use constant::Constant;
+use std::cell::Cell;
 use traits::{Data, Pair, Tensed};
 use variable::Variable;
 
+/// Element type of the tensors, `f64` with the `f64` feature or `f32` otherwise
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+/// Element type of the tensors, `f64` with the `f64` feature or `f32` otherwise
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+thread_local! {
+    /// Whether the operations computed by this thread are evaluated lazily
+    static LAZY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables lazy evaluation of the operations computed by the current thread,
+/// disabled by default. Other threads keep their own setting.
+///
+/// Operations are evaluated as soon as they are computed unless lazy evaluation is enabled,
+/// in which case chains of element-wise operations are left to the arrayfire JIT compiler,
+/// which fuses them into a single kernel once their result is needed, i.e. by a matrix
+/// multiplication, a reduction or when copying the values to the host. This saves kernel
+/// launches and intermediate buffers, at the cost of deferring any error to that point
+#[inline]
+pub fn set_lazy(lazy: bool) {
+    LAZY.with(|cell| cell.set(lazy));
+}
+
+/// Returns `true` if lazy evaluation is enabled for the current thread, see `set_lazy`
+#[must_use]
+#[inline]
+pub fn is_lazy() -> bool {
+    LAZY.with(Cell::get)
+}
+
+/// Blocks the host until all the operations queued in the active device are done.
+///
+/// Operations run asynchronously, and the host otherwise only waits for them when reading
+/// their results, such as with `Tensor::to_vec`. Synchronizing is needed to measure the time
+/// they take
+#[inline]
+pub fn sync() {
+    arrayfire::sync(arrayfire::get_device());
+}
+
+/// Runs the given function with the device of the given id active, then restores the active
+/// device. Arrays created by the function are held by that device
+///
+/// # Panics
+///
+/// Panics if there is no device with the given id
+pub fn on_device<T>(id: i32, f: impl FnOnce() -> T) -> T {
+    assert!(
+        (0..arrayfire::device_count()).contains(&id),
+        "there is no device {id}"
+    );
+    let active = arrayfire::get_device();
+    arrayfire::set_device(id);
+    let result = f();
+    arrayfire::set_device(active);
+    result
+}
+
+/// Copies the values to the device of the given id through the host, see `on_device`
+fn copy_to_device(values: &Array<Float>, id: i32) -> Array<Float> {
+    let mut host = vec![0.0; values.elements()];
+    values.host(&mut host);
+    on_device(id, || Array::new(&host, values.dims()))
+}
+
+/// Returns the device kept as the argument at the given index of a `to_device` operation,
+/// the source at 0 and the destination at 1
+#[allow(clippy::cast_possible_truncation)]
+fn device_arg(args: &[Array<Float>], index: usize) -> i32 {
+    let mut host = [0.0];
+    args[index].host(&mut host);
+    host[0] as i32
+}
+
+/// Evaluates the result of an operation right away, unless lazy evaluation is enabled and
+/// the result is tracked in the computation graph. Results computed only from constants are
+/// always evaluated, folding them into a single buffer that is not computed again every time
+/// they are used, i.e. positional encodings shared by every step
+fn materialize<D: Data>(data: Array<Float>) -> Array<Float> {
+    if !is_lazy() || !D::TRACKED {
+        data.eval();
+    }
+    data
+}
+
+/// Options of a backward pass, see `Tensor::backward_with`
+#[derive(Clone, Copy, Debug)]
+pub struct BackwardOptions {
+    retain_graph: bool,
+    accumulate: bool,
+    optimize: Option<bool>,
+}
+
+impl BackwardOptions {
+    /// Returns the options of a plain `backward` call, which retains the computation graph and
+    /// accumulates the gradients
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            retain_graph: true,
+            accumulate: true,
+            optimize: None,
+        }
+    }
+
+    /// Consumes the options and returns a copy that keeps the computation graph if `retain` is
+    /// true, or otherwise releases the intermediate results once the gradients are computed
+    #[must_use]
+    #[inline]
+    pub const fn retain_graph(mut self, retain: bool) -> Self {
+        self.retain_graph = retain;
+        self
+    }
+
+    /// Consumes the options and returns a copy that adds the gradients to those of previous
+    /// backward passes if `accumulate` is true, or otherwise overwrites them. Only the
+    /// gradients of declared variables accumulate, those of intermediate results are always
+    /// the ones of the last pass
+    #[must_use]
+    #[inline]
+    pub const fn accumulate(mut self, accumulate: bool) -> Self {
+        self.accumulate = accumulate;
+        self
+    }
+
+    /// Consumes the options and returns a copy that eliminates the duplicated operations of
+    /// the computation graph before computing the gradients if `optimize` is true, see
+    /// `Tensor::optimize`. Unless set, they are only eliminated with lazy evaluation enabled
+    #[must_use]
+    #[inline]
+    pub const fn optimize(mut self, optimize: bool) -> Self {
+        self.optimize = Some(optimize);
+        self
+    }
+
+    /// Returns `true` if the computation graph is to be optimized before the backward pass
+    pub(crate) fn optimizes(self) -> bool {
+        self.optimize.unwrap_or_else(is_lazy)
+    }
+}
+
+impl Default for BackwardOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single value, i.e. a loss
+pub type Scalar<D = Variable> = Tensor<1, 1, 1, 1, D>;
+
+/// A row vector of `W` values
+pub type Vector<const W: u64, D = Variable> = Tensor<1, 1, 1, W, D>;
+
+/// A matrix of `H` rows and `W` columns
+pub type Matrix<const H: u64, const W: u64, D = Variable> = Tensor<1, 1, H, W, D>;
+
+/// A batch of `B` row vectors of `W` values, as taken by the `Linear` layer
+pub type Batch<const B: u64, const W: u64, D = Variable> = Tensor<B, 1, 1, W, D>;
+
 #[derive(Clone)]
 pub struct Tensor<const B: u64, const C: u64, const H: u64, const W: u64, D: Data>(D);
 
 impl<const B: u64, const C: u64, const H: u64, const W: u64> Tensor<B, C, H, W, Variable> {
     /// Returns the tensor gradients as another variable tensor
     pub fn grad(&self) -> Self {
-        Self(Variable::new(
-            Tape::default(),
-            Node::declaration(self.0.grad()),
-        ))
+        Self(Variable::new(Node::declaration(self.0.grad())))
     }
 
     /// Consumes the variable tensor and returns it as a constant tensor
@@ -48,20 +215,157 @@ impl<const B: u64, const C: u64, const H: u64, const W: u64> Tensor<B, C, H, W,
         Tensor(Constant::new(self.data()))
     }
 
+    /// Returns the values of this tensor as a constant tensor, cut from the computation graph,
+    /// i.e. to carry the hidden state of a recurrent model over to the next batch
+    pub fn detach(&self) -> Tensor<B, C, H, W, Constant> {
+        Tensor(Constant::new(self.data()))
+    }
+
+    /// Returns a copy of the tensor held by the device of the given id, as the constant
+    /// `to_device` does. The copy stays in the computation graph: its gradients are copied
+    /// back to the device of this tensor, and its tangents to the device of the copy
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no device with the given id
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_device(&self, id: i32) -> Self {
+        let _op = profiler::forward("to_device");
+        let devices = [self.device(), id]
+            .map(|device| Array::new(&[device as Float], arrayfire::dim4!(1, 1, 1, 1)));
+
+        self.push_unary(
+            copy_to_device(&self.data(), id),
+            |df: &Array<Float>, args: &[Array<Float>]| copy_to_device(df, device_arg(args, 0)),
+            devices.to_vec(),
+        )
+        .with_tangent(Tangent::Unary(|dx, args| {
+            copy_to_device(dx, device_arg(args, 1))
+        }))
+    }
+
     /// Starting from this tensor node, compute the reverse auto differentiation.
     /// Once called, all the ancestor nodes for which this tensor depends on will have
-    /// their gradients filled with the derivative with respect to this tensor
+    /// their gradients filled with the derivative with respect to this tensor.
+    /// Nodes that are not ancestors of this tensor are neither visited nor modified, even if
+    /// they share ancestors with it. The operations computing the gradients are queued in the
+    /// device without blocking the host, see `sync`.
+    ///
+    /// Every value of this tensor is seeded with a derivative of one, so the gradients of a
+    /// tensor of several values, i.e. of per-sample losses, are those of the sum of its values.
+    /// Reduce them explicitly, see `nn::losses::Reduction`, to average them instead
     pub fn backward(&self) {
-        // derivative of self wrt to self is one
-        self.0.node().ones_grad();
-        for node in self.0.tape().nodes().rev() {
-            node.reverse();
-        }
+        self.backward_with(BackwardOptions::new());
+    }
+
+    /// Same as `backward`, with explicit control over whether the computation graph is kept
+    /// for further backward passes and whether the gradients accumulate to those of previous
+    /// passes or overwrite them
+    ///
+    /// # Panics
+    ///
+    /// Panics if the computation graph was released by a previous backward pass
+    pub fn backward_with(&self, options: BackwardOptions) {
+        self.0.backward(options);
+    }
+
+    /// Registers a function to be called with the gradients of this tensor during every
+    /// backward pass, right before they are propagated to its ancestors. If the function
+    /// returns new gradients, they replace the original ones, i.e. to clip them
+    pub fn register_hook<F>(&self, hook: F)
+    where
+        F: Fn(&Array<Float>) -> Option<Array<Float>> + Threaded + 'static,
+    {
+        self.0.node().register_hook(Box::new(hook));
+    }
+
+    /// Registers a function to transform every partial derivative before it is accumulated to
+    /// the gradients of this tensor, see `register_hook` to transform them once accumulated
+    pub fn register_grad_transform<F>(&self, transform: F)
+    where
+        F: Fn(&Array<Float>) -> Array<Float> + Threaded + 'static,
+    {
+        self.0.node().register_grad_transform(transform);
+    }
+
+    /// Zeroes the gradients of the values of this tensor where the mask is 0, so that only
+    /// the ones where it is 1 are trained, see `Node::mask_grad`
+    pub fn mask_grad(&self, mask: &Tensor<B, C, H, W, Constant>) {
+        self.0.node().mask_grad(mask.data());
+    }
+
+    /// Names this tensor, i.e. `encoder_out`, to tell it apart in the DOT export of the
+    /// computation graph and in the profile of the backward pass, which aggregates the
+    /// derivatives of named tensors by their name instead of their operation
+    #[must_use]
+    pub fn named(self, name: &'static str) -> Self {
+        self.0.node().set_label(name);
+        self
+    }
+
+    /// Returns the name given to this tensor, or otherwise the name of the operation it results
+    /// from, i.e. `sin`, or `Declaration` if it is not the result of an operation
+    pub fn name(&self) -> &'static str {
+        self.0.node().name()
+    }
+
+    /// Returns the computation graph up until this tensor in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        self.0.tape().to_dot()
+    }
+
+    /// Eliminates the operations of the computation graph up until this tensor that are
+    /// computed more than once on the same tensors, i.e. by a loop body rebuilding the same
+    /// subexpression on every iteration. Duplicates forward their gradients to the first
+    /// computation, so its derivatives are computed once during the backward pass and the
+    /// gradients of the first computation include those of its duplicates. Returns the
+    /// number of duplicated operations eliminated
+    pub fn optimize(&self) -> usize {
+        self.0.tape().eliminate_duplicates()
+    }
+
+    /// Returns the number of nodes of the computation graph up until this tensor, including
+    /// its own, see `memory_stats` for the memory they hold
+    pub fn node_count(&self) -> usize {
+        self.0.tape().nodes().len()
+    }
+
+    /// Returns the number of nodes of the computation graph up until this tensor, how many
+    /// result from every kind of operation and the memory they hold. Compared with
+    /// `memory_stats`, it tells which graph keeps growing, i.e. when inputs are not frozen
+    pub fn graph_info(&self) -> GraphInfo {
+        self.0.tape().info()
+    }
+
+    /// Overwrites the values of this tensor in place, without adding nodes to the computation
+    /// graph, i.e. to load weights or sync the parameters of a target network
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values do not have the shape of the tensor
+    pub fn set_data(&self, values: &Array<Float>) {
+        let dims = values.dims();
+        assert!(
+            dims == arrayfire::dim4!(H, W, C, B),
+            "values of shape [{}, {}, {}, {}] do not fit tensor shape [{B}, {C}, {H}, {W}]",
+            dims[3],
+            dims[2],
+            dims[0],
+            dims[1]
+        );
+        self.0.node().set_data(values.clone());
+    }
+
+    /// Overwrites the values of this tensor in place with those of another tensor of the same
+    /// shape, see `set_data`
+    pub fn copy_from<Y: Data>(&self, other: &Tensor<B, C, H, W, Y>) {
+        self.0.node().set_data(other.data());
     }
 
     /// Set all gradients to zero, including this tensor's and all its ancestors
     pub fn reset(&self) {
-        for node in self.0.tape().nodes().rev() {
+        for node in self.0.tape().nodes() {
             node.zero_grad();
         }
     }
@@ -70,10 +374,123 @@ impl<const B: u64, const C: u64, const H: u64, const W: u64> Tensor<B, C, H, W,
 impl<const B: u64, const C: u64, const H: u64, const W: u64> Tensor<B, C, H, W, Constant> {
     /// Consumes the constant tensor and returns it as a variable tensor
     pub fn unfreeze(self) -> Tensor<B, C, H, W, Variable> {
-        Tensor(Variable::new(
-            Tape::default(),
-            Node::declaration(self.data()),
-        ))
+        Tensor(Variable::new(Node::declaration(self.data())))
+    }
+
+    /// Returns a copy of the tensor held by the device of the given id, as used by
+    /// `arrayfire::set_device`, i.e. to spread batches over several GPUs. The values are
+    /// copied through the host, and the active device is left unchanged.
+    ///
+    /// The host copy is pageable memory rather than pinned: arrayfire only hands out pinned
+    /// memory as raw pointers, which would take the unsafe code this crate denies
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no device with the given id
+    #[must_use]
+    pub fn to_device(&self, id: i32) -> Self {
+        Constant::new(copy_to_device(&self.data(), id)).into()
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> Tensor<B, C, H, W, D> {
+    /// Sets the forward mode derivatives of the operation that resulted in this tensor,
+    /// so that it supports `jvp`
+    #[must_use]
+    pub fn with_tangent(self, tangent: Tangent) -> Self {
+        self.0.set_tangent(tangent);
+        self
+    }
+
+    /// Evaluates the operations pending on the values of this tensor, and on its gradients if
+    /// variable, which lazy evaluation otherwise leaves to the arrayfire JIT compiler until
+    /// they are needed. The evaluation is queued in the device without blocking the host,
+    /// see `sync`
+    #[inline]
+    pub fn eval(&self) {
+        self.0.eval();
+    }
+
+    /// Returns the id of the device holding the tensor values, see `to_device`
+    #[must_use]
+    #[inline]
+    pub fn device(&self) -> i32 {
+        self.0.values().get_device_id()
+    }
+
+    /// Copies the tensor values to the host, laid out in the same column-major order taken by `custom`
+    #[must_use]
+    #[inline]
+    pub fn to_vec(&self) -> Vec<Float> {
+        let values = self.0.values();
+        let mut host = vec![0.0; values.elements()];
+        values.host(&mut host);
+        host
+    }
+}
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> Tensor<B, C, H, W, D> {
+    /// Returns the value at the given batch, channel, row and column, copying only that value
+    /// to the host
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of the tensor shape
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn at(&self, b: u64, c: u64, h: u64, w: u64) -> Float {
+        assert!(
+            b < B && c < C && h < H && w < W,
+            "index [{b}, {c}, {h}, {w}] is out of the tensor shape [{B}, {C}, {H}, {W}]"
+        );
+        let seq = |i: u64| Seq::new(i as i32, i as i32, 1);
+        let mut host = [0.0];
+        arrayfire::index(&self.0.values(), &[seq(h), seq(w), seq(c), seq(b)]).host(&mut host);
+        host[0]
+    }
+
+    /// Returns `true` if every value of this tensor is within `atol + rtol * |y|` of the
+    /// corresponding value `y` of the other tensor. The comparison runs on the device, only the
+    /// result is copied to the host
+    #[must_use]
+    #[inline]
+    pub fn allclose<Y: Data>(
+        &self,
+        other: &Tensor<B, C, H, W, Y>,
+        rtol: Float,
+        atol: Float,
+    ) -> bool {
+        let (x, y) = (self.0.values(), other.0.values());
+        let tolerance = arrayfire::abs(&y) * rtol + atol;
+        let difference = arrayfire::abs(&(x - y));
+        arrayfire::all_true_all(&arrayfire::le(&difference, &tolerance, false)).0
+    }
+
+    /// Same as `at`, with the indices checked against the tensor shape at compile time
+    #[cfg(feature = "nightly")]
+    #[must_use]
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn get<const IB: u64, const IC: u64, const IH: u64, const IW: u64>(&self) -> Float
+    where
+        [(); (B - IB - 1) as usize]:,
+        [(); (C - IC - 1) as usize]:,
+        [(); (H - IH - 1) as usize]:,
+        [(); (W - IW - 1) as usize]:,
+    {
+        self.at(IB, IC, IH, IW)
+    }
+}
+
+impl<D: Data> Tensor<1, 1, 1, 1, D> {
+    /// Returns the only value of a scalar tensor, i.e. a reduced loss
+    #[must_use]
+    #[inline]
+    pub fn to_scalar(&self) -> Float {
+        let mut host = [0.0];
+        self.0.values().host(&mut host);
+        host[0]
     }
 }
 
@@ -92,24 +509,29 @@ impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> Tensed
 
     fn push_unary<const YB: u64, const YC: u64, const YH: u64, const YW: u64>(
         &self,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: UnaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Tensor<YB, YC, YH, YW, D> {
-        Tensor(self.0.push_unary(data, reverse, args))
+        Tensor(self.0.push_unary(materialize::<D>(data), reverse, args))
     }
 
     fn push_binary<const ZB: u64, const ZC: u64, const ZH: u64, const ZW: u64, Y: Tensed>(
         &self,
         other: &Y,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Tensor<ZB, ZC, ZH, ZW, <Self::Data as Pair<Y::Data>>::Output>
     where
         Self::Data: Pair<Y::Data>,
     {
-        Tensor(self.0.push_binary(other.inner(), data, reverse, args))
+        Tensor(self.0.push_binary(
+            other.inner(),
+            materialize::<<Self::Data as Pair<Y::Data>>::Output>(data),
+            reverse,
+            args,
+        ))
     }
 }
 
@@ -128,3 +550,251 @@ impl<const B: u64, const C: u64, const H: u64, const W: u64> From<Variable>
         Self(variable)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{set_lazy, sync, BackwardOptions};
+    use crate as mu;
+    use crate::tensor::traits::Tensed;
+    use crate::tensor::Float;
+    use crate::tests::equal_data;
+
+    #[test]
+    fn backward_with_overwrite() {
+        let x = mu::fill::<1, 1, 1, 1>(3.0);
+        let z = mu::mul(&x, &x);
+
+        z.backward();
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(12.0; 1,1,1,1)
+        ));
+
+        z.backward_with(BackwardOptions::new().accumulate(false));
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(6.0; 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn backward_shared_intermediate() {
+        let x = mu::fill::<1, 1, 1, 1>(3.0);
+        let y = mu::mul(&x, &x);
+        let first = mu::mul(&y, &mu::fill::<1, 1, 1, 1>(2.0).freeze());
+        let second = mu::mul(&y, &mu::fill::<1, 1, 1, 1>(3.0).freeze());
+
+        // Separately, the gradients are 2 * 2x = 12 and 3 * 2x = 18
+        first.backward();
+        second.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(30.0; 1,1,1,1)
+        ));
+        assert!(equal_data(
+            y.grad().data(),
+            arrayfire::constant!(3.0; 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn register_hook_replaces_grad() {
+        let x = mu::fill::<1, 1, 1, 1>(3.0);
+        let y = mu::mul(&x, &x);
+        let z = mu::mul(&y, &mu::fill::<1, 1, 1, 1>(10.0).freeze());
+
+        y.register_hook(|grad| {
+            Some(arrayfire::clamp(
+                grad,
+                &(-1.0 as Float),
+                &(1.0 as Float),
+                false,
+            ))
+        });
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(6.0; 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "computation graph was released")]
+    fn backward_with_released_graph() {
+        let x = mu::fill::<1, 1, 1, 1>(3.0);
+        let z = mu::mul(&x, &x);
+
+        z.backward_with(BackwardOptions::new().retain_graph(false));
+        z.backward();
+    }
+
+    #[test]
+    fn grad_transform_every_partial() {
+        let x = mu::fill::<1, 1, 1, 1>(3.0);
+        let z = mu::add(&mu::mul(&x, &x), &x);
+
+        // Partials are 3, 3 and 1, each clamped to 2 before accumulating
+        x.register_grad_transform(|partial| {
+            arrayfire::clamp(partial, &(0.0 as Float), &(2.0 as Float), false)
+        });
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(5.0; 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn to_device_keeps_graph() {
+        let x = mu::fill::<1, 1, 2, 2>(2.0);
+        let y = x.to_device(0);
+        assert_eq!(y.device(), 0);
+
+        mu::mul(&y, &y).backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(4.0; 2,2,1,1)
+        ));
+    }
+
+    #[test]
+    fn mask_grad_freezes_values() {
+        let x = mu::custom::<1, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0]);
+        let z = mu::mul(&x, &x);
+
+        // Only the second row is trained
+        x.mask_grad(&mu::custom(&[0.0, 1.0, 0.0, 1.0]).freeze());
+        z.backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::Array::new(&[0.0, 4.0, 0.0, 8.0], arrayfire::dim4!(2, 2, 1, 1))
+        ));
+    }
+
+    #[test]
+    fn backward_only_visits_ancestors() {
+        let z = mu::fill::<1, 1, 1, 1>(2.0);
+        let fake = mu::mul(&z, &z);
+        let real = mu::fill::<1, 1, 1, 1>(1.0);
+        let d_fake = mu::sin(&fake);
+        let d_real = mu::sin(&real);
+
+        d_fake.backward();
+        d_real.backward();
+        assert!(equal_data(
+            fake.grad().data(),
+            arrayfire::constant!((4.0 as Float).cos(); 1,1,1,1)
+        ));
+
+        d_fake.reset();
+        assert!(equal_data(
+            fake.grad().data(),
+            arrayfire::constant!(0.0; 1,1,1,1)
+        ));
+        assert!(equal_data(
+            real.grad().data(),
+            arrayfire::constant!((1.0 as Float).cos(); 1,1,1,1)
+        ));
+    }
+
+    #[test]
+    fn element_indexing() {
+        let x = mu::custom::<2, 1, 2, 3>(&[
+            0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0,
+        ]);
+        assert!((x.at(1, 0, 1, 2) - 11.0).abs() < Float::EPSILON);
+        assert!((x.at(0, 0, 1, 0) - 1.0).abs() < Float::EPSILON);
+        #[cfg(feature = "nightly")]
+        assert!((x.get::<1, 0, 0, 1>() - 8.0).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn in_place_updates() {
+        let x = mu::fill::<1, 1, 2, 2>(1.0);
+        let y = mu::mul(&x, &x);
+        let nodes = y.node_count();
+
+        x.set_data(&arrayfire::constant!(3.0; 2, 2, 1, 1));
+        assert!(equal_data(x.data(), arrayfire::constant!(3.0; 2, 2, 1, 1)));
+        x.copy_from(&mu::fill::<1, 1, 2, 2>(2.0).freeze());
+        assert!(equal_data(x.data(), arrayfire::constant!(2.0; 2, 2, 1, 1)));
+        assert_eq!(y.node_count(), nodes);
+
+        mu::mul(&x, &x).backward();
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(4.0; 2, 2, 1, 1)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "values of shape [1, 1, 1, 2] do not fit tensor shape [1, 1, 2, 2]")]
+    fn in_place_update_shape() {
+        mu::fill::<1, 1, 2, 2>(1.0).set_data(&arrayfire::constant!(0.0; 1, 2, 1, 1));
+    }
+
+    #[test]
+    fn close_values() {
+        let x = mu::custom::<1, 1, 1, 3>(&[1.0, 100.0, 0.0]);
+        let y = mu::custom::<1, 1, 1, 3>(&[1.001, 100.05, 0.0005]).freeze();
+        assert!(x.allclose(&y, 1e-3, 1e-3));
+        assert!(!x.allclose(&y, 1e-3, 1e-4));
+        assert!(!x.allclose(&y, 0.0, 1e-3));
+    }
+
+    #[test]
+    #[should_panic(expected = "index [0, 0, 2, 0] is out of the tensor shape [2, 1, 2, 3]")]
+    fn element_out_of_shape() {
+        let _ = mu::fill::<2, 1, 2, 3>(0.0).at(0, 0, 2, 0);
+    }
+
+    #[test]
+    fn lazy_evaluation() {
+        // The mode is set for this thread only, tests running meanwhile stay eager
+        set_lazy(true);
+        let x = mu::fill::<1, 1, 2, 2>(2.0);
+        let z = mu::mul(&mu::add(&x, &x), &mu::sin(&x));
+        z.backward();
+        set_lazy(false);
+
+        z.eval();
+        x.eval();
+        sync();
+
+        let eager = mu::mul(&mu::add(&x, &x), &mu::sin(&x));
+        assert!(equal_data(z.data(), eager.data()));
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(2.0 * ((2.0 as Float).sin() + 2.0 * (2.0 as Float).cos()); 2,2,1,1)
+        ));
+    }
+
+    #[test]
+    fn optimize_duplicated_operations() {
+        let x = mu::fill::<1, 1, 1, 1>(2.0);
+        let z = mu::mul(&mu::sin(&x), &mu::sin(&x));
+        assert_eq!(z.optimize(), 1);
+        assert_eq!(z.optimize(), 0);
+
+        z.backward_with(BackwardOptions::new().optimize(true));
+        assert!(equal_data(
+            x.grad().data(),
+            arrayfire::constant!(2.0 * (2.0 as Float).sin() * (2.0 as Float).cos(); 1,1,1,1)
+        ));
+
+        // Same operation on different constants
+        let c = mu::fill::<1, 1, 1, 1>(3.0).freeze();
+        let z = mu::add(&mu::mul(&x, &c), &mu::mul(&x, &x.clone().freeze()));
+        assert_eq!(z.optimize(), 0);
+    }
+
+    #[cfg(feature = "f64")]
+    #[test]
+    fn double_precision() {
+        // The gradient of x^3 at 1e-30 underflows to zero with f32 values
+        let x = mu::fill::<1, 1, 1, 1>(1e-30);
+        mu::mul(&mu::mul(&x, &x), &x).backward();
+        assert!((x.grad().to_scalar() - 3e-60).abs() < 1e-70);
+    }
+}
@@ -0,0 +1,77 @@
+//! `serde` support for tensors. A tensor is serialized as its `shape` `[B, C, H, W]` and its
+//! `data` in the same column-major order accepted by `custom`
+
+use crate::tensor::{traits::Data, Float, Tensor};
+use arrayfire::{dim4, Array};
+use serde::{de::Error, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+impl<const B: u64, const C: u64, const H: u64, const W: u64, D: Data> Serialize
+    for Tensor<B, C, H, W, D>
+{
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Tensor", 2)?;
+        state.serialize_field("shape", &[B, C, H, W])?;
+        state.serialize_field("data", &self.to_vec())?;
+        state.end()
+    }
+}
+
+/// The serialized form of a tensor, before validating it against the static shape
+#[derive(Deserialize)]
+#[serde(rename = "Tensor")]
+struct Raw {
+    shape: [u64; 4],
+    data: Vec<Float>,
+}
+
+/// Deserialized `Variable` tensors are new declarations in the computation graph
+impl<'de, const B: u64, const C: u64, const H: u64, const W: u64, D> Deserialize<'de>
+    for Tensor<B, C, H, W, D>
+where
+    D: Data + From<Array<Float>>,
+{
+    #[inline]
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+
+        if raw.shape != [B, C, H, W] {
+            return Err(De::Error::custom(format!(
+                "expected tensor of shape {:?}, found {:?}",
+                [B, C, H, W],
+                raw.shape
+            )));
+        }
+        if raw.data.len() as u64 != B * C * H * W {
+            return Err(De::Error::invalid_length(
+                raw.data.len(),
+                &"as many values as the tensor shape",
+            ));
+        }
+
+        Ok(Self(D::from(Array::new(&raw.data, dim4!(H, W, C, B)))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as mu;
+    use crate::tensor::{constant::Constant, traits::Tensed, Tensor};
+    use crate::tests::equal_data;
+
+    #[test]
+    fn tensor_serde() {
+        let x = mu::custom::<1, 1, 2, 2>(&[1.0, 2.0, 3.0, 4.0]);
+        let json = serde_json::to_string(&x).unwrap();
+        assert_eq!(json, r#"{"shape":[1,1,2,2],"data":[1.0,2.0,3.0,4.0]}"#);
+
+        let y: Tensor<1, 1, 2, 2, Constant> = serde_json::from_str(&json).unwrap();
+        assert!(equal_data(x.data(), y.data()));
+
+        assert!(serde_json::from_str::<Tensor<1, 1, 4, 1, Constant>>(&json).is_err());
+        assert!(serde_json::from_str::<Tensor<1, 1, 2, 2, Constant>>(
+            r#"{"shape":[1,1,2,2],"data":[1.0]}"#
+        )
+        .is_err());
+    }
+}
@@ -1,15 +1,28 @@
+use crate::tensor::Float;
 use crate::{
-    graph::node::{BinaryReverseFn, UnaryReverseFn},
+    graph::node::{BinaryReverseFn, Tangent, UnaryReverseFn},
     tensor::Tensor,
 };
 use arrayfire::Array;
 
 /// Common methods for types holding data for a tensor. Either `Variable` or `Constant` data.
 pub trait Data {
+    /// Whether the data is tracked in the computation graph, `false` for `Constant` data
+    const TRACKED: bool;
+
     /// Returns the tensor data as an arrayfire array
-    fn values(&self) -> Array<f32>;
+    fn values(&self) -> Array<Float>;
     /// Pushes new data, resulting from a unary operation, to the computation graph (if data is variable)
-    fn push_unary(&self, data: Array<f32>, reverse: UnaryReverseFn, args: &[Array<f32>]) -> Self;
+    fn push_unary(
+        &self,
+        data: Array<Float>,
+        reverse: UnaryReverseFn,
+        args: Vec<Array<Float>>,
+    ) -> Self;
+    /// Sets the forward mode derivatives of the operation that resulted in this data (if data is variable)
+    fn set_tangent(&self, tangent: Tangent);
+    /// Evaluates the pending operations of the tensor data (and its gradients if data is variable)
+    fn eval(&self);
 }
 
 /// Common methods for pairs of types holding data for tensors. Depending on the combination of types,
@@ -27,9 +40,9 @@ pub trait Pair<Y: Data> {
     fn push_binary(
         &self,
         other: &Y,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self::Output;
 }
 
@@ -49,24 +62,24 @@ pub trait Tensed {
     /// Pushes new data, resulting from a unary operation, to the computation graph (if data is variable)
     fn push_unary<const B: u64, const C: u64, const H: u64, const W: u64>(
         &self,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: UnaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Tensor<B, C, H, W, Self::Data>;
 
     /// Pushes new data, resulting from a binary operation, to the computation graph (if output is variable)
     fn push_binary<const B: u64, const C: u64, const H: u64, const W: u64, Y: Tensed>(
         &self,
         other: &Y,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Tensor<B, C, H, W, <Self::Data as Pair<Y::Data>>::Output>
     where
         Self::Data: Pair<Y::Data>;
 
     /// Returns the tensor data as an arrayfire array
-    fn data(&self) -> Array<f32> {
+    fn data(&self) -> Array<Float> {
         self.inner().values()
     }
 }
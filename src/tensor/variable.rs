@@ -1,56 +1,95 @@
+use crate::graph::{shared::Shared, tape::Tape};
 use crate::{
-    graph::{
-        node::{BinaryReverseFn, Node, UnaryReverseFn},
-        tape::Tape,
-    },
+    graph::node::{BinaryReverseFn, Node, Tangent, UnaryReverseFn},
     tensor::{
         constant::Constant,
         traits::{Data, Pair},
+        BackwardOptions, Float,
     },
 };
 use arrayfire::Array;
-use std::rc::Rc;
 
 /// Data for a tensor being tracked in the computation graph
 #[derive(Clone)]
 pub struct Variable {
-    tape: Tape,
-    node: Rc<Node>,
+    node: Shared<Node>,
 }
 
 impl Variable {
-    /// Constructs variable data from the given tape and node. The node is pushed to the tape.
-    pub fn new(mut tape: Tape, node: Node) -> Self {
-        let node = Rc::new(node);
-        tape.push(node.clone());
-        Self { tape, node }
+    /// Constructs variable data from the given node, which links to the nodes of its ancestors
+    pub fn new(node: Node) -> Self {
+        Self {
+            node: Shared::new(node),
+        }
     }
 
     /// Returns the gradients of the holded data as an arrayfire array
-    pub fn grad(&self) -> Array<f32> {
-        self.node.grad().clone()
+    pub fn grad(&self) -> Array<Float> {
+        self.node.grad()
     }
 
     /// Returns the tape tracking the computation graph up until the existence of this variable
-    pub const fn tape(&self) -> &Tape {
-        &self.tape
+    pub fn tape(&self) -> Tape {
+        Tape::new(self.node())
+    }
+
+    /// Computes the gradients of all the ancestors of this variable with respect to it,
+    /// see `Tensor::backward_with`
+    pub fn backward(&self, options: BackwardOptions) {
+        let mut tape = self.tape();
+        if options.optimizes() && tape.eliminate_duplicates() > 0 {
+            tape = self.tape();
+        }
+        // Intermediate gradients are always those of this pass only, otherwise the ones left
+        // by a previous pass would be propagated again to the shared ancestors
+        for node in tape.nodes() {
+            if !options.accumulate || !node.is_declaration() {
+                node.zero_grad();
+            }
+        }
+
+        // derivative of self wrt to self is one
+        self.node.ones_grad();
+        for node in tape.nodes().rev() {
+            node.reverse();
+        }
+
+        if !options.retain_graph {
+            for node in tape.nodes() {
+                node.release();
+            }
+        }
     }
 
     /// Returns the node in the computation graph holding the data and gradients of this variable
-    pub fn node(&self) -> Rc<Node> {
+    pub fn node(&self) -> Shared<Node> {
         self.node.clone()
     }
 }
 
 impl Data for Variable {
-    fn push_unary(&self, data: Array<f32>, reverse: UnaryReverseFn, args: &[Array<f32>]) -> Self {
-        let node = Node::unary(data, self.node(), reverse, args);
-        Self::new(self.tape().clone(), node)
+    const TRACKED: bool = true;
+
+    fn push_unary(
+        &self,
+        data: Array<Float>,
+        reverse: UnaryReverseFn,
+        args: Vec<Array<Float>>,
+    ) -> Self {
+        Self::new(Node::unary(data, self.node(), reverse, args))
     }
 
-    fn values(&self) -> Array<f32> {
+    fn values(&self) -> Array<Float> {
         self.node().data().clone()
     }
+
+    fn set_tangent(&self, tangent: Tangent) {
+        self.node.set_tangent(tangent);
+    }
+
+    fn eval(&self) {
+        self.node.eval();
+    }
 }
 
 impl Pair<Self> for Variable {
@@ -59,12 +98,16 @@ impl Pair<Self> for Variable {
     fn push_binary(
         &self,
         other: &Self,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self::Output {
-        let node = Node::binary_varvar(data, (self.node(), other.node()), reverse, args);
-        Self::new(self.tape().merge(other.tape()), node)
+        Self::new(Node::binary_varvar(
+            data,
+            (self.node(), other.node()),
+            reverse,
+            args,
+        ))
     }
 }
 
@@ -74,25 +117,30 @@ impl Pair<Constant> for Variable {
     fn push_binary(
         &self,
         _other: &Constant,
-        data: Array<f32>,
+        data: Array<Float>,
         reverse: BinaryReverseFn,
-        args: &[Array<f32>],
+        args: Vec<Array<Float>>,
     ) -> Self::Output {
-        let node = Node::binary_varconst(data, self.node(), reverse, args);
-        Self::new(self.tape().clone(), node)
+        Self::new(Node::binary_varconst(data, self.node(), reverse, args))
     }
 }
 
-impl From<Array<f32>> for Variable {
-    fn from(data: Array<f32>) -> Self {
-        Self::new(Tape::default(), Node::declaration(data))
+impl From<Array<Float>> for Variable {
+    fn from(data: Array<Float>) -> Self {
+        Self::new(Node::declaration(data))
+    }
+}
+
+impl From<Shared<Node>> for Variable {
+    fn from(node: Shared<Node>) -> Self {
+        Self { node }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Variable;
-    use crate::graph::{node::Node, tape::Tape};
+    use crate::graph::node::Node;
     use crate::tensor::{
         traits::{Data, Pair},
         Constant,
@@ -101,10 +149,7 @@ mod tests {
 
     #[test]
     fn new() {
-        let variable = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)),
-        );
+        let variable = Variable::new(Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)));
         assert!(equal_data(
             variable.values(),
             arrayfire::constant!(5.0; 1,1,1,1)
@@ -113,14 +158,11 @@ mod tests {
 
     #[test]
     fn push_unary() {
-        let variable = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)),
-        );
+        let variable = Variable::new(Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)));
         let variable = variable.push_unary(
             arrayfire::constant!(2.0; 1,1,1,1),
             |_, _| arrayfire::constant!(1.0; 1,1,1,1),
-            &[],
+            vec![],
         );
         assert!(equal_data(
             variable.grad(),
@@ -130,10 +172,7 @@ mod tests {
 
     #[test]
     fn push_binary_constant() {
-        let variable = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)),
-        );
+        let variable = Variable::new(Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)));
         let other = Constant::new(arrayfire::constant!(4.0; 1,1,1,1));
         let variable = variable.push_binary(
             &other,
@@ -144,7 +183,7 @@ mod tests {
                     arrayfire::constant!(1.0; 1,1,1,1),
                 )
             },
-            &[],
+            vec![],
         );
         assert!(equal_data(
             variable.grad(),
@@ -154,14 +193,8 @@ mod tests {
 
     #[test]
     fn push_binary_variable() {
-        let variable = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)),
-        );
-        let other = Variable::new(
-            Tape::default(),
-            Node::declaration(arrayfire::constant!(4.0; 1,1,1,1)),
-        );
+        let variable = Variable::new(Node::declaration(arrayfire::constant!(5.0; 1,1,1,1)));
+        let other = Variable::new(Node::declaration(arrayfire::constant!(4.0; 1,1,1,1)));
         let variable = variable.push_binary(
             &other,
             arrayfire::constant!(2.0; 1,1,1,1),
@@ -171,7 +204,7 @@ mod tests {
                     arrayfire::constant!(1.0; 1,1,1,1),
                 )
             },
-            &[],
+            vec![],
         );
         assert!(equal_data(
             variable.grad(),